@@ -0,0 +1,175 @@
+// benches/concurrent_rooms.rs
+// 多数のルームから同時に読み書きしたときの処理量と読み取りの待ち時間を、読み取り用の接続の数ごとに比べます
+//
+// cargo bench --bench concurrent_rooms
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use shiritori::{
+    database::{
+        db::{DataBase, DEFAULT_READERS},
+        migration::MigrationMode,
+        repository::Repository,
+    },
+    dictionary::{DictionaryEntry, DictionaryFormat},
+};
+
+/// 同時に動かすルームの数
+const ROOMS: u64 = 64;
+/// 1ルームあたりの操作の回数
+const OPS_PER_ROOM: u64 = 200;
+/// 書き込みを混ぜる場合は、この回数に1回書き込む
+const WRITE_EVERY: u64 = 10;
+/// 辞書の読み込み（長い書き込みのトランザクション）で登録する単語の数
+const DICTIONARY_WORDS: usize = 20_000;
+/// 計測の回数（接続の数ごとに交互に計測し、中央値を表示します）
+const ROUNDS: usize = 5;
+
+/// 計測する負荷
+#[derive(Clone, Copy)]
+enum Workload {
+    /// 読み取りだけ
+    ReadsOnly,
+    /// WRITE_EVERY回に1回、ルームの設定を書き込む
+    MixedWrites,
+    /// 別のタスクが辞書を繰り返し読み込んでいる間に読み取る
+    DuringDictionaryLoad,
+}
+
+impl Workload {
+    fn label(&self) -> &'static str {
+        match self {
+            Workload::ReadsOnly => "reads only",
+            Workload::MixedWrites => "1 write per 10 ops",
+            Workload::DuringDictionaryLoad => "reads while a dictionary loads",
+        }
+    }
+}
+
+/// 1回の計測の結果
+struct Measurement {
+    ops_per_sec: f64,
+    read_p50: Duration,
+    read_p99: Duration,
+}
+
+fn main() -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+    runtime.block_on(async {
+        let readers = [0, 1, DEFAULT_READERS, DEFAULT_READERS * 2];
+        println!("{} rooms x {} ops, median of {} rounds", ROOMS, OPS_PER_ROOM, ROUNDS);
+        for workload in [Workload::ReadsOnly, Workload::MixedWrites, Workload::DuringDictionaryLoad] {
+            println!("{}:", workload.label());
+            let mut results: Vec<Vec<Measurement>> = readers.iter().map(|_| Vec::new()).collect();
+            for _ in 0..ROUNDS {
+                for (i, &n) in readers.iter().enumerate() {
+                    results[i].push(run(n, workload).await?);
+                }
+            }
+            for (n, measurements) in readers.iter().zip(results) {
+                println!(
+                    "  readers = {:>2}: {:>8.0} ops/s, read p50 {:>7.0} us, p99 {:>7.0} us",
+                    n,
+                    median(measurements.iter().map(|m| m.ops_per_sec)),
+                    median(measurements.iter().map(|m| m.read_p50.as_secs_f64() * 1e6)),
+                    median(measurements.iter().map(|m| m.read_p99.as_secs_f64() * 1e6)),
+                );
+            }
+        }
+        Ok(())
+    })
+}
+
+/// ---
+/// readersの数の読み取り用の接続で、全ルームから同時に操作して計測します。
+/// readersが0の場合は、読み取りも書き込み用の接続1つで行います。
+/// ---
+async fn run(readers: usize, workload: Workload) -> Result<Measurement> {
+    let path = std::env::temp_dir().join(format!("shiritori-bench-{}-{}.db", std::process::id(), readers));
+    let path_str = path.to_string_lossy().into_owned();
+    remove_db(&path_str);
+
+    let db = DataBase::with_readers(&path_str, readers).await?;
+    db.migrate(MigrationMode::Apply).await?;
+    let repo = Arc::new(Repository::new(db)?);
+    for room_id in 1..=ROOMS {
+        repo.create_room(room_id).await?;
+        repo.add_user(room_id * 10 + 1, room_id).await?;
+        repo.add_user(room_id * 10 + 2, room_id).await?;
+    }
+
+    let done = Arc::new(AtomicBool::new(false));
+    let loader = matches!(workload, Workload::DuringDictionaryLoad).then(|| {
+        let repo = repo.clone();
+        let done = done.clone();
+        let entries: Vec<DictionaryEntry> = (0..DICTIONARY_WORDS)
+            .map(|i| DictionaryEntry::new(&format!("単語{}", i), &format!("たんご{}", i)))
+            .collect();
+        tokio::spawn(async move {
+            while !done.load(Ordering::Relaxed) {
+                repo.load_dictionary("bench", DictionaryFormat::Plain, entries.clone()).await?;
+            }
+            anyhow::Ok(())
+        })
+    });
+
+    let start = Instant::now();
+    let tasks: Vec<_> = (1..=ROOMS)
+        .map(|room_id| {
+            let repo = repo.clone();
+            tokio::spawn(async move {
+                let mut latencies = Vec::with_capacity(OPS_PER_ROOM as usize);
+                for i in 0..OPS_PER_ROOM {
+                    if matches!(workload, Workload::MixedWrites) && i % WRITE_EVERY == 0 {
+                        repo.set_room_guild(room_id, i).await?;
+                        continue;
+                    }
+                    let read_start = Instant::now();
+                    match i % 3 {
+                        0 => drop(repo.get_vote_state(room_id).await?),
+                        1 => drop(repo.get_queue(room_id).await?),
+                        _ => drop(repo.get_room_config(room_id).await?),
+                    }
+                    latencies.push(read_start.elapsed());
+                }
+                anyhow::Ok(latencies)
+            })
+        })
+        .collect();
+    let mut latencies = Vec::new();
+    for task in tasks {
+        latencies.extend(task.await??);
+    }
+    let elapsed = start.elapsed();
+    done.store(true, Ordering::Relaxed);
+    if let Some(loader) = loader {
+        loader.await??;
+    }
+
+    drop(repo);
+    remove_db(&path_str);
+    latencies.sort();
+    Ok(Measurement {
+        ops_per_sec: (ROOMS * OPS_PER_ROOM) as f64 / elapsed.as_secs_f64(),
+        read_p50: latencies[latencies.len() / 2],
+        read_p99: latencies[latencies.len() * 99 / 100],
+    })
+}
+
+fn median(values: impl Iterator<Item = f64>) -> f64 {
+    let mut values: Vec<f64> = values.collect();
+    values.sort_by(f64::total_cmp);
+    values[values.len() / 2]
+}
+
+fn remove_db(path: &str) {
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path, suffix));
+    }
+}
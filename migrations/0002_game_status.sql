@@ -0,0 +1,5 @@
+-- ゲーム状態と「ん」ルール
+ALTER TABLE rooms ADD COLUMN status TEXT NOT NULL CHECK(status IN ('active', 'finished', 'paused')) DEFAULT 'active';
+ALTER TABLE rooms ADD COLUMN n_ending TEXT NOT NULL CHECK(n_ending IN ('lose', 'continue')) DEFAULT 'lose'; -- 「ん」で終わる単語の扱い
+ALTER TABLE rooms ADD COLUMN loser_id INTEGER;   -- ゲーム終了時の敗者
+ALTER TABLE rooms ADD COLUMN finished_at TEXT;   -- ゲーム終了日時
@@ -0,0 +1,39 @@
+-- 既出単語に手番・ユーザー・承認日時・投票結果を追加
+-- room_wordsを参照するトリガーは作り直しの間だけ削除する
+DROP TRIGGER voteword_already_used_check;
+
+CREATE TABLE room_words_new (
+    room_id INTEGER NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+    word TEXT NOT NULL,
+    turn INTEGER NOT NULL,                      -- ルーム内での手番（1からの連番）
+    user_id INTEGER,                            -- 単語を出したユーザー
+    approved_at TEXT DEFAULT (datetime('now')), -- 承認日時
+    good_count INTEGER NOT NULL DEFAULT 0,      -- 確定時の投票数
+    bad_count INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (room_id, word),
+    UNIQUE (room_id, turn)
+);
+
+-- 既存の単語は挿入順に手番を割り当てる（承認日時は不明）
+INSERT INTO room_words_new (room_id, word, turn, approved_at)
+SELECT room_id, word, ROW_NUMBER() OVER (PARTITION BY room_id ORDER BY rowid), NULL
+FROM room_words;
+
+DROP TABLE room_words;
+ALTER TABLE room_words_new RENAME TO room_words;
+
+CREATE TRIGGER voteword_already_used_check
+BEFORE INSERT ON room_votes
+FOR EACH ROW
+BEGIN
+    SELECT NEW.word = LOWER(NEW.word);
+    SELECT
+        CASE
+            WHEN EXISTS (
+                SELECT 1 FROM room_words
+                WHERE room_id = NEW.room_id
+                  AND word = NEW.word
+            )
+            THEN RAISE(ABORT, 'Word already used in this room')
+        END;
+END;
@@ -0,0 +1,14 @@
+-- 投票を表示しているメッセージ
+ALTER TABLE room_votes ADD COLUMN message_id INTEGER;
+
+-- メッセージの記録などで投票状態が消えないよう、単語・手番の変更時のみリセットする
+DROP TRIGGER reset_room_members_state_on_vote_change;
+
+CREATE TRIGGER reset_room_members_state_on_vote_change
+AFTER UPDATE OF word, current_user_id ON room_votes
+FOR EACH ROW
+BEGIN
+    UPDATE room_members
+    SET state = 'none'
+    WHERE room_id = NEW.room_id;
+END;
@@ -0,0 +1,4 @@
+-- 投票の確定ルール
+ALTER TABLE rooms ADD COLUMN vote_policy TEXT NOT NULL CHECK(vote_policy IN ('majority', 'supermajority', 'unanimous', 'no_objection')) DEFAULT 'majority';
+ALTER TABLE rooms ADD COLUMN vote_threshold INTEGER;            -- supermajorityの割合(%)、no_objectionの秒数
+ALTER TABLE rooms ADD COLUMN vote_quorum INTEGER NOT NULL DEFAULT 0; -- 確定に必要な最低投票数
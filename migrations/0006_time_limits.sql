@@ -0,0 +1,4 @@
+-- 投票・手番の制限時間（NULLは無制限）
+ALTER TABLE rooms ADD COLUMN vote_timeout INTEGER;  -- 投票を締め切るまでの秒数
+ALTER TABLE rooms ADD COLUMN turn_timeout INTEGER;  -- 単語を投稿するまでの秒数
+ALTER TABLE rooms ADD COLUMN turn_timeout_action TEXT NOT NULL CHECK(turn_timeout_action IN ('skip', 'eliminate')) DEFAULT 'skip';
@@ -0,0 +1,53 @@
+-- サーバー・ルームごとのゲーム設定
+-- NULLの項目は上位の設定（ルームならサーバー、サーバーなら既定値）を引き継ぐ
+CREATE TABLE guild_settings (
+    guild_id INTEGER PRIMARY KEY,   -- プログラム側でu64→i64に変換して保存
+    n_ending TEXT CHECK(n_ending IN ('lose', 'continue')),
+    vote_policy TEXT CHECK(vote_policy IN ('majority', 'supermajority', 'unanimous', 'no_objection')),
+    vote_threshold INTEGER,                             -- supermajorityの割合(%)、no_objectionの秒数
+    vote_quorum INTEGER CHECK(vote_quorum >= 0),
+    vote_timeout INTEGER CHECK(vote_timeout >= 0),      -- 0は無制限
+    turn_timeout INTEGER CHECK(turn_timeout >= 0),      -- 0は無制限
+    turn_timeout_action TEXT CHECK(turn_timeout_action IN ('skip', 'eliminate')),
+    min_word_length INTEGER CHECK(min_word_length >= 0),
+    allowed_scripts TEXT,                               -- 使える文字の種類（カンマ区切り）
+    language TEXT
+);
+
+CREATE TABLE room_settings (
+    room_id INTEGER PRIMARY KEY REFERENCES rooms(id) ON DELETE CASCADE,
+    guild_id INTEGER,                                   -- 設定を引き継ぐサーバー
+    n_ending TEXT CHECK(n_ending IN ('lose', 'continue')),
+    vote_policy TEXT CHECK(vote_policy IN ('majority', 'supermajority', 'unanimous', 'no_objection')),
+    vote_threshold INTEGER,
+    vote_quorum INTEGER CHECK(vote_quorum >= 0),
+    vote_timeout INTEGER CHECK(vote_timeout >= 0),
+    turn_timeout INTEGER CHECK(turn_timeout >= 0),
+    turn_timeout_action TEXT CHECK(turn_timeout_action IN ('skip', 'eliminate')),
+    min_word_length INTEGER CHECK(min_word_length >= 0),
+    allowed_scripts TEXT,
+    language TEXT
+);
+
+-- roomsに保存していた設定のうち、既定値と異なるものだけを移す
+INSERT INTO room_settings (room_id, n_ending, vote_policy, vote_threshold, vote_quorum, vote_timeout, turn_timeout, turn_timeout_action)
+SELECT
+    id,
+    NULLIF(n_ending, 'lose'),
+    CASE WHEN vote_policy = 'majority' AND vote_quorum = 0 THEN NULL ELSE vote_policy END,
+    CASE WHEN vote_policy = 'majority' AND vote_quorum = 0 THEN NULL ELSE vote_threshold END,
+    CASE WHEN vote_policy = 'majority' AND vote_quorum = 0 THEN NULL ELSE vote_quorum END,
+    vote_timeout,
+    turn_timeout,
+    NULLIF(turn_timeout_action, 'skip')
+FROM rooms
+WHERE n_ending != 'lose' OR vote_policy != 'majority' OR vote_quorum != 0
+   OR vote_timeout IS NOT NULL OR turn_timeout IS NOT NULL OR turn_timeout_action != 'skip';
+
+ALTER TABLE rooms DROP COLUMN n_ending;
+ALTER TABLE rooms DROP COLUMN vote_policy;
+ALTER TABLE rooms DROP COLUMN vote_threshold;
+ALTER TABLE rooms DROP COLUMN vote_quorum;
+ALTER TABLE rooms DROP COLUMN vote_timeout;
+ALTER TABLE rooms DROP COLUMN turn_timeout;
+ALTER TABLE rooms DROP COLUMN turn_timeout_action;
@@ -0,0 +1,22 @@
+-- 読み込んだ辞書
+CREATE TABLE dictionaries (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    format TEXT NOT NULL CHECK(format IN ('plain', 'ipadic', 'unidic', 'skk', 'jmdict')),
+    loaded_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- 辞書の単語（readingは正規化したよみ）
+CREATE TABLE dictionary_entries (
+    dictionary_id INTEGER NOT NULL REFERENCES dictionaries(id) ON DELETE CASCADE,
+    surface TEXT NOT NULL,
+    reading TEXT NOT NULL,
+    PRIMARY KEY (dictionary_id, surface, reading)
+) WITHOUT ROWID;
+
+CREATE INDEX idx_dictionary_entries_surface ON dictionary_entries(surface);
+CREATE INDEX idx_dictionary_entries_reading ON dictionary_entries(reading);
+
+-- 辞書に載っていない単語の扱い
+ALTER TABLE guild_settings ADD COLUMN dictionary_mode TEXT CHECK(dictionary_mode IN ('required', 'advisory', 'off'));
+ALTER TABLE room_settings ADD COLUMN dictionary_mode TEXT CHECK(dictionary_mode IN ('required', 'advisory', 'off'));
@@ -0,0 +1,10 @@
+-- 単語の表記(word)とよみ(reading)を分けて保存する
+-- readingはひらがな・小文字に正規化したよみで、接続と既出の判定に使う
+ALTER TABLE room_words ADD COLUMN reading TEXT;
+ALTER TABLE room_votes ADD COLUMN reading TEXT;
+
+-- 既存の単語は表記をそのままよみとする
+UPDATE room_words SET reading = LOWER(word);
+UPDATE room_votes SET reading = LOWER(word) WHERE word IS NOT NULL;
+
+CREATE INDEX idx_room_words_reading ON room_words(room_id, reading);
@@ -0,0 +1,55 @@
+-- 既出判定に使う正規化キー（NFKC・カタカナ→ひらがな・小文字化・空白除去）
+-- キーはアプリケーションで計算して保存する。ここではアプリケーションが接続に登録する
+-- canonical_key関数で既存の単語のキーを作る
+-- room_wordsを参照するトリガーは作り直しの間だけ削除する
+DROP TRIGGER voteword_already_used_check;
+
+CREATE TABLE room_words_new (
+    room_id INTEGER NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+    word TEXT NOT NULL,                         -- 表記
+    turn INTEGER NOT NULL,
+    user_id INTEGER,
+    approved_at TEXT DEFAULT (datetime('now')),
+    good_count INTEGER NOT NULL DEFAULT 0,
+    bad_count INTEGER NOT NULL DEFAULT 0,
+    reading TEXT NOT NULL,                      -- よみ（正規化済み）
+    word_key TEXT NOT NULL,                     -- 既出判定のキー
+    PRIMARY KEY (room_id, word_key),
+    UNIQUE (room_id, turn)
+);
+
+-- 正規化すると重複する既存の単語は、最初の単語以外のキーに手番を付けて履歴を残す
+INSERT INTO room_words_new (room_id, word, turn, user_id, approved_at, good_count, bad_count, reading, word_key)
+SELECT
+    room_id, word, turn, user_id, approved_at, good_count, bad_count,
+    canonical_key(COALESCE(reading, word)),
+    CASE
+        WHEN ROW_NUMBER() OVER (PARTITION BY room_id, canonical_key(COALESCE(reading, word)) ORDER BY turn) = 1
+        THEN canonical_key(COALESCE(reading, word))
+        ELSE canonical_key(COALESCE(reading, word)) || '#' || turn
+    END
+FROM room_words;
+
+DROP TABLE room_words;
+ALTER TABLE room_words_new RENAME TO room_words;
+-- 作り直しで消えたインデックス（0009で作成）を作り直す
+CREATE INDEX idx_room_words_reading ON room_words(room_id, reading);
+
+ALTER TABLE room_votes ADD COLUMN word_key TEXT;
+UPDATE room_votes
+SET reading = canonical_key(reading), word_key = canonical_key(reading)
+WHERE word IS NOT NULL;
+
+-- 投票中の単語の既出チェック（正規化キーで比較する）
+CREATE TRIGGER voteword_already_used_check
+BEFORE INSERT ON room_votes
+FOR EACH ROW
+WHEN NEW.word_key IS NOT NULL
+BEGIN
+    SELECT RAISE(ABORT, 'Word already used in this room')
+    WHERE EXISTS (
+        SELECT 1 FROM room_words
+        WHERE room_id = NEW.room_id
+          AND word_key = NEW.word_key
+    );
+END;
@@ -0,0 +1,3 @@
+-- コンピューターの参加者の強さ
+-- 人間の参加者はNULL
+ALTER TABLE room_members ADD COLUMN computer_level TEXT CHECK(computer_level IN ('easy', 'normal', 'hard'));
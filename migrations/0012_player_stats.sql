@@ -0,0 +1,20 @@
+-- 成績の記録
+-- 投票の確定・ゲームの終了と同じトランザクションで追加し、集計はこの表から行う
+-- ルームを削除しても成績は残すため、roomsは参照しない
+CREATE TABLE score_events (
+    id INTEGER PRIMARY KEY,
+    room_id INTEGER NOT NULL,
+    guild_id INTEGER,                   -- 記録時にルームが属していたサーバー
+    user_id INTEGER NOT NULL,
+    kind TEXT NOT NULL CHECK(kind IN ('accepted', 'rejected', 'won', 'lost')),
+    word TEXT,                          -- accepted/rejectedの単語
+    reading_length INTEGER,             -- よみの文字数
+    response_secs INTEGER,              -- 手番が回ってから単語を出すまでの秒数
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX idx_score_events_room ON score_events(room_id, created_at);
+CREATE INDEX idx_score_events_guild ON score_events(guild_id, created_at);
+
+-- 投票中の単語を出すまでにかかった秒数（手番の開始が不明な場合はNULL）
+ALTER TABLE room_votes ADD COLUMN response_secs INTEGER;
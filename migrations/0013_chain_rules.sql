@@ -0,0 +1,7 @@
+-- 単語の接続判定ルール（NULLは上位の設定を引き継ぐ）
+ALTER TABLE guild_settings ADD COLUMN long_vowel TEXT CHECK(long_vowel IN ('vowel', 'skip'));
+ALTER TABLE guild_settings ADD COLUMN expand_small_kana INTEGER CHECK(expand_small_kana IN (0, 1));
+ALTER TABLE guild_settings ADD COLUMN ignore_dakuten INTEGER CHECK(ignore_dakuten IN (0, 1));
+ALTER TABLE room_settings ADD COLUMN long_vowel TEXT CHECK(long_vowel IN ('vowel', 'skip'));
+ALTER TABLE room_settings ADD COLUMN expand_small_kana INTEGER CHECK(expand_small_kana IN (0, 1));
+ALTER TABLE room_settings ADD COLUMN ignore_dakuten INTEGER CHECK(ignore_dakuten IN (0, 1));
@@ -0,0 +1,515 @@
+// src/archive/mod.rs
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    database::repository::{RoomStatus, VoteTally, WordRecord},
+    dictionary::parser::split_csv_line,
+    rules::{
+        chain::LongVowelRule,
+        config::{ConfigOverrides, DictionaryMode, Language},
+        script::ScriptSet,
+        terminal::NEndingRule,
+        timeout::TurnTimeoutAction,
+        vote::{VotePolicy, VoteRule},
+    },
+};
+
+/// 書き出すファイルの形式のversion（読み込めるのはこのversionまで）
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// 日時の書式（データベースと同じ）
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// CSVの単語の列
+const CSV_WORD_HEADER: &str = "turn,word,reading,user_id,approved_at,good,bad";
+
+/// 数値で書き出す設定の項目
+const NUMERIC_SETTINGS: [&str; 5] = ["vote_threshold", "vote_quorum", "vote_timeout", "turn_timeout", "min_word_length"];
+
+/// ---
+/// ルームの履歴を書き出す形式
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Json,
+    /// 設定の表と単語の表を空行で区切ったCSV
+    Csv,
+}
+
+impl ArchiveFormat {
+    pub const ALL: [ArchiveFormat; 2] = [ArchiveFormat::Json, ArchiveFormat::Csv];
+
+    /// コマンドの引数用の文字列を返します（拡張子と同じ）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Json => "json",
+            ArchiveFormat::Csv => "csv",
+        }
+    }
+
+    /// コマンドの引数の文字列から変換します
+    pub fn parse(s: &str) -> Option<Self> {
+        ArchiveFormat::ALL.into_iter().find(|format| format.as_str() == s)
+    }
+
+    /// ファイル名の拡張子から形式を判定します
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let (_, extension) = name.rsplit_once('.')?;
+        Self::parse(&extension.to_ascii_lowercase())
+    }
+}
+
+/// ---
+/// 書き出したルームの履歴
+/// settingsはルームに保存された設定だけで、サーバーから引き継いだ設定は含みません。
+/// ---
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomArchive {
+    pub room_id: u64,
+    pub status: RoomStatus,
+    pub loser_id: Option<u64>,
+    pub finished_at: Option<NaiveDateTime>,
+    pub settings: ConfigOverrides,
+    /// 手番の順に並んだ既出単語
+    pub words: Vec<WordRecord>,
+}
+
+/// JSONのファイル全体
+#[derive(Serialize, Deserialize)]
+struct ArchiveFile {
+    version: u32,
+    room_id: u64,
+    status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    loser_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    finished_at: Option<String>,
+    #[serde(default)]
+    settings: Map<String, Value>,
+    words: Vec<WordFile>,
+}
+
+/// JSONの単語1つ
+#[derive(Serialize, Deserialize)]
+struct WordFile {
+    turn: u64,
+    word: String,
+    reading: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    user_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    approved_at: Option<String>,
+    #[serde(default)]
+    good: u64,
+    #[serde(default)]
+    bad: u64,
+}
+
+impl RoomArchive {
+    /// 指定した形式で書き出します
+    pub fn encode(&self, format: ArchiveFormat) -> Result<String> {
+        match format {
+            ArchiveFormat::Json => self.to_json(),
+            ArchiveFormat::Csv => Ok(self.to_csv()),
+        }
+    }
+
+    /// ---
+    /// 指定した形式のファイルを読み込みます。
+    /// versionが新しいファイルや、不明な設定・状態を含むファイルはエラーになります。
+    /// ---
+    pub fn decode(format: ArchiveFormat, bytes: &[u8]) -> Result<Self> {
+        // BOMは読み飛ばす
+        let bytes = bytes.strip_prefix("\u{FEFF}".as_bytes()).unwrap_or(bytes);
+        let text = std::str::from_utf8(bytes).context("ファイルがUTF-8ではありません")?;
+        match format {
+            ArchiveFormat::Json => Self::from_json(text),
+            ArchiveFormat::Csv => Self::from_csv(text),
+        }
+    }
+
+    fn to_json(&self) -> Result<String> {
+        let settings = settings_to_pairs(&self.settings)
+            .into_iter()
+            .map(|(key, value)| {
+                let value = match value.parse::<u64>() {
+                    Ok(n) if NUMERIC_SETTINGS.contains(&key) => Value::from(n),
+                    _ => Value::from(value),
+                };
+                (key.to_string(), value)
+            })
+            .collect();
+        let file = ArchiveFile {
+            version: ARCHIVE_VERSION,
+            room_id: self.room_id,
+            status: self.status.as_str().to_string(),
+            loser_id: self.loser_id,
+            finished_at: self.finished_at.map(format_datetime),
+            settings,
+            words: self
+                .words
+                .iter()
+                .map(|record| WordFile {
+                    turn: record.turn,
+                    word: record.word.clone(),
+                    reading: record.reading.clone(),
+                    user_id: record.user_id,
+                    approved_at: record.approved_at.map(format_datetime),
+                    good: record.tally.good,
+                    bad: record.tally.bad,
+                })
+                .collect(),
+        };
+        Ok(serde_json::to_string_pretty(&file)?)
+    }
+
+    fn from_json(text: &str) -> Result<Self> {
+        let file: ArchiveFile = serde_json::from_str(text).context("JSONを読み込めません")?;
+        check_version(file.version)?;
+
+        let settings: Vec<(String, String)> = file
+            .settings
+            .into_iter()
+            .map(|(key, value)| match value {
+                Value::String(s) => Ok((key, s)),
+                Value::Number(n) => Ok((key, n.to_string())),
+                value => anyhow::bail!("設定{}の値が不正です: {}", key, value),
+            })
+            .collect::<Result<_>>()?;
+        let words = file
+            .words
+            .into_iter()
+            .map(|word| {
+                Ok(WordRecord {
+                    room_id: file.room_id,
+                    turn: word.turn,
+                    word: word.word,
+                    reading: word.reading,
+                    user_id: word.user_id,
+                    approved_at: word.approved_at.as_deref().map(parse_datetime).transpose()?,
+                    tally: VoteTally { good: word.good, bad: word.bad },
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            room_id: file.room_id,
+            status: parse_status(&file.status)?,
+            loser_id: file.loser_id,
+            finished_at: file.finished_at.as_deref().map(parse_datetime).transpose()?,
+            settings: settings_from_pairs(&settings)?,
+            words,
+        })
+    }
+
+    fn to_csv(&self) -> String {
+        let mut lines = vec!["key,value".to_string()];
+        let mut meta = vec![
+            ("version", ARCHIVE_VERSION.to_string()),
+            ("room_id", self.room_id.to_string()),
+            ("status", self.status.as_str().to_string()),
+        ];
+        if let Some(loser_id) = self.loser_id {
+            meta.push(("loser_id", loser_id.to_string()));
+        }
+        if let Some(finished_at) = self.finished_at {
+            meta.push(("finished_at", format_datetime(finished_at)));
+        }
+        meta.extend(settings_to_pairs(&self.settings));
+        lines.extend(meta.iter().map(|(key, value)| csv_line(&[key, value])));
+
+        lines.push(String::new());
+        lines.push(CSV_WORD_HEADER.to_string());
+        for record in &self.words {
+            lines.push(csv_line(&[
+                &record.turn.to_string(),
+                &record.word,
+                &record.reading,
+                &record.user_id.map(|id| id.to_string()).unwrap_or_default(),
+                &record.approved_at.map(format_datetime).unwrap_or_default(),
+                &record.tally.good.to_string(),
+                &record.tally.bad.to_string(),
+            ]));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    fn from_csv(text: &str) -> Result<Self> {
+        let mut lines = csv_records(text).into_iter();
+        anyhow::ensure!(lines.next().map(str::trim) == Some("key,value"), "CSVの1行目が key,value ではありません");
+
+        // 空行までが設定の表
+        let mut room_id = None;
+        let mut status = None;
+        let mut loser_id = None;
+        let mut finished_at = None;
+        let mut version = None;
+        let mut settings = Vec::new();
+        for line in lines.by_ref() {
+            if line.trim().is_empty() {
+                break;
+            }
+            let [key, value]: [String; 2] = split_csv_line(line)
+                .try_into()
+                .map_err(|fields| anyhow::anyhow!("設定の行の列数が不正です: {:?}", fields))?;
+            match key.as_str() {
+                "version" => version = Some(parse_number::<u32>(&key, &value)?),
+                "room_id" => room_id = Some(parse_number::<u64>(&key, &value)?),
+                "status" => status = Some(parse_status(&value)?),
+                "loser_id" => loser_id = Some(parse_number::<u64>(&key, &value)?),
+                "finished_at" => finished_at = Some(parse_datetime(&value)?),
+                _ => settings.push((key, value)),
+            }
+        }
+        check_version(version.context("versionがありません")?)?;
+        let room_id = room_id.context("room_idがありません")?;
+
+        anyhow::ensure!(
+            lines.next().map(str::trim) == Some(CSV_WORD_HEADER),
+            "単語の表の見出しが {} ではありません",
+            CSV_WORD_HEADER
+        );
+        let mut words = Vec::new();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let [turn, word, reading, user_id, approved_at, good, bad]: [String; 7] = split_csv_line(line)
+                .try_into()
+                .map_err(|fields| anyhow::anyhow!("単語の行の列数が不正です: {:?}", fields))?;
+            words.push(WordRecord {
+                room_id,
+                turn: parse_number("turn", &turn)?,
+                word,
+                reading,
+                user_id: Some(user_id).filter(|s| !s.is_empty()).map(|s| parse_number("user_id", &s)).transpose()?,
+                approved_at: Some(approved_at).filter(|s| !s.is_empty()).map(|s| parse_datetime(&s)).transpose()?,
+                tally: VoteTally { good: parse_number("good", &good)?, bad: parse_number("bad", &bad)? },
+            });
+        }
+
+        Ok(Self {
+            room_id,
+            status: status.context("statusがありません")?,
+            loser_id,
+            finished_at,
+            settings: settings_from_pairs(&settings)?,
+            words,
+        })
+    }
+}
+
+fn check_version(version: u32) -> Result<()> {
+    anyhow::ensure!(
+        version <= ARCHIVE_VERSION,
+        "ファイルのversion {} はこのバージョンでは読み込めません（{}まで対応）",
+        version,
+        ARCHIVE_VERSION
+    );
+    Ok(())
+}
+
+fn parse_status(s: &str) -> Result<RoomStatus> {
+    RoomStatus::parse(s).with_context(|| format!("不明なゲーム状態です: {}", s))
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T> {
+    value.trim().parse().map_err(|_| anyhow::anyhow!("{}の値が数値ではありません: {}", key, value))
+}
+
+fn format_datetime(datetime: NaiveDateTime) -> String {
+    datetime.format(DATETIME_FORMAT).to_string()
+}
+
+fn parse_datetime(s: &str) -> Result<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s.trim(), DATETIME_FORMAT).with_context(|| format!("日時の書式が不正です: {}", s))
+}
+
+/// 値をCSVの1行にします（カンマ・ダブルクォート・改行を含む値はダブルクォートで囲みます）
+fn csv_line(fields: &[&str]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// ---
+/// CSVのテキストを行に分けます。
+/// ダブルクォートで囲まれた値の中の改行では分けず、行末の改行（\r\nの\rも）は除きます。
+/// ---
+fn csv_records(text: &str) -> Vec<&str> {
+    let mut records = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            // 値の中の""は2回切り替わるため、囲みの内外は変わらない
+            '"' => quoted = !quoted,
+            '\n' if !quoted => {
+                records.push(text[start..i].strip_suffix('\r').unwrap_or(&text[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < text.len() {
+        records.push(text[start..].strip_suffix('\r').unwrap_or(&text[start..]));
+    }
+    records
+}
+
+/// ---
+/// 設定を項目名と値の組にします。
+/// 項目名と値の書式はデータベースのroom_settingsと同じです。
+/// ---
+fn settings_to_pairs(settings: &ConfigOverrides) -> Vec<(&'static str, String)> {
+    let vote_rule = settings.vote_rule;
+    [
+        ("n_ending", settings.n_ending.map(|rule| rule.as_str().to_string())),
+        ("vote_policy", vote_rule.map(|rule| rule.policy.as_str().to_string())),
+        ("vote_threshold", vote_rule.and_then(|rule| rule.policy.threshold()).map(|v| v.to_string())),
+        ("vote_quorum", vote_rule.map(|rule| rule.quorum.to_string())),
+        ("vote_timeout", settings.vote_timeout.map(|secs| secs.to_string())),
+        ("turn_timeout", settings.turn_timeout.map(|secs| secs.to_string())),
+        ("turn_timeout_action", settings.turn_timeout_action.map(|action| action.as_str().to_string())),
+        ("min_word_length", settings.min_word_length.map(|length| length.to_string())),
+        ("allowed_scripts", settings.allowed_scripts.map(|scripts| scripts.to_db())),
+        ("language", settings.language.map(|language| language.as_str().to_string())),
+        ("dictionary_mode", settings.dictionary_mode.map(|mode| mode.as_str().to_string())),
+        ("long_vowel", settings.long_vowel.map(|rule| rule.as_str().to_string())),
+        ("expand_small_kana", settings.expand_small_kana.map(|expand| expand.to_string())),
+        ("ignore_dakuten", settings.ignore_dakuten.map(|ignore| ignore.to_string())),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| value.map(|value| (key, value)))
+    .collect()
+}
+
+/// 項目名と値の組から設定を読み込みます（不明な項目はエラー）
+fn settings_from_pairs(pairs: &[(String, String)]) -> Result<ConfigOverrides> {
+    let mut settings = ConfigOverrides::default();
+    let mut policy = None;
+    let mut threshold = None;
+    let mut quorum = None;
+    for (key, value) in pairs {
+        let invalid = || anyhow::anyhow!("設定{}の値が不正です: {}", key, value);
+        match key.as_str() {
+            "n_ending" => settings.n_ending = Some(NEndingRule::parse(value).ok_or_else(invalid)?),
+            "vote_policy" => policy = Some(value.as_str()),
+            "vote_threshold" => threshold = Some(parse_number::<u32>(key, value)?),
+            "vote_quorum" => quorum = Some(parse_number::<u32>(key, value)?),
+            "vote_timeout" => settings.vote_timeout = Some(parse_number(key, value)?),
+            "turn_timeout" => settings.turn_timeout = Some(parse_number(key, value)?),
+            "turn_timeout_action" => {
+                settings.turn_timeout_action = Some(TurnTimeoutAction::parse(value).ok_or_else(invalid)?)
+            }
+            "min_word_length" => settings.min_word_length = Some(parse_number(key, value)?),
+            "allowed_scripts" => settings.allowed_scripts = Some(ScriptSet::parse(value).ok_or_else(invalid)?),
+            "language" => settings.language = Some(Language::parse(value).ok_or_else(invalid)?),
+            "dictionary_mode" => settings.dictionary_mode = Some(DictionaryMode::parse(value).ok_or_else(invalid)?),
+            "long_vowel" => settings.long_vowel = Some(LongVowelRule::parse(value).ok_or_else(invalid)?),
+            "expand_small_kana" => settings.expand_small_kana = Some(value.parse().map_err(|_| invalid())?),
+            "ignore_dakuten" => settings.ignore_dakuten = Some(value.parse().map_err(|_| invalid())?),
+            _ => anyhow::bail!("不明な設定です: {}", key),
+        }
+    }
+    if let Some(policy) = policy {
+        let policy = VotePolicy::parse(policy, threshold)
+            .with_context(|| format!("不明な投票ルールです: {} ({:?})", policy, threshold))?;
+        settings.vote_rule = Some(VoteRule { policy, quorum: quorum.unwrap_or(0) });
+    }
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> RoomArchive {
+        let datetime = |s: &str| NaiveDateTime::parse_from_str(s, DATETIME_FORMAT).ok();
+        RoomArchive {
+            room_id: 1000,
+            status: RoomStatus::Finished,
+            loser_id: Some(2),
+            finished_at: datetime("2025-01-02 03:04:05"),
+            settings: ConfigOverrides {
+                vote_rule: Some(VoteRule { policy: VotePolicy::Supermajority { percent: 60 }, quorum: 2 }),
+                allowed_scripts: ScriptSet::parse("hiragana,katakana"),
+                long_vowel: Some(LongVowelRule::Skip),
+                ignore_dakuten: Some(true),
+                ..Default::default()
+            },
+            words: vec![
+                WordRecord {
+                    room_id: 1000,
+                    turn: 1,
+                    word: "しりとり".to_string(),
+                    reading: "しりとり".to_string(),
+                    user_id: Some(1),
+                    approved_at: datetime("2025-01-02 03:00:00"),
+                    tally: VoteTally { good: 1, bad: 0 },
+                },
+                WordRecord {
+                    room_id: 1000,
+                    turn: 2,
+                    word: "\"林檎\",".to_string(),
+                    reading: "りんご".to_string(),
+                    user_id: None,
+                    approved_at: None,
+                    tally: VoteTally { good: 2, bad: 1 },
+                },
+                // 改行を含む単語（CSVでは1つの値が複数の行にまたがる）
+                WordRecord {
+                    room_id: 1000,
+                    turn: 3,
+                    word: "apple\npie\r\n".to_string(),
+                    reading: "あっぷるぱい".to_string(),
+                    user_id: Some(2),
+                    approved_at: datetime("2025-01-02 03:01:00"),
+                    tally: VoteTally { good: 1, bad: 0 },
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let archive = sample();
+        for format in ArchiveFormat::ALL {
+            let text = archive.encode(format)?;
+            assert_eq!(
+                RoomArchive::decode(format, text.as_bytes())?,
+                archive,
+                "{}で書き出した履歴を読み込めません。\n{}",
+                format.as_str(),
+                text
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let json = sample().encode(ArchiveFormat::Json).unwrap();
+        let newer = json.replace("\"version\": 1", "\"version\": 2");
+        assert!(RoomArchive::decode(ArchiveFormat::Json, newer.as_bytes()).is_err(), "新しいversionが読み込まれました。");
+
+        let csv = sample().encode(ArchiveFormat::Csv).unwrap();
+        let unknown = csv.replace("vote_quorum", "unknown_setting");
+        assert!(RoomArchive::decode(ArchiveFormat::Csv, unknown.as_bytes()).is_err(), "不明な設定が読み込まれました。");
+    }
+
+    #[test]
+    fn test_format_from_file_name() {
+        assert_eq!(ArchiveFormat::from_file_name("room.JSON"), Some(ArchiveFormat::Json));
+        assert_eq!(ArchiveFormat::from_file_name("room.csv"), Some(ArchiveFormat::Csv));
+        assert_eq!(ArchiveFormat::from_file_name("room"), None);
+    }
+}
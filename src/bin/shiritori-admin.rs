@@ -0,0 +1,341 @@
+// src/bin/shiritori-admin.rs
+// データベースを管理するCLI（Discordには接続しません）
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use shiritori::{
+    bot::game::mention,
+    database::{
+        backup::{restore_snapshot, DEFAULT_RETENTION},
+        db::DataBase,
+        migration::{latest_version, MigrationMode},
+        repository::{RepoError, Repository, VoteResolution},
+    },
+    rules::computer::is_computer,
+};
+
+const USAGE: &str = "usage: shiritori-admin [--db PATH] <COMMAND>
+
+commands:
+  rooms                        list rooms with their status, members and words
+  queue <ROOM>                 show the turn order
+  vote <ROOM>                  show the open vote
+  repair <ROOM>                repair a broken turn order
+  resolve <ROOM>               force-resolve the open vote with the ballots cast so far
+  delete-word <ROOM> <TURN>    delete a word from the history
+  migrate [--dry-run]          apply pending schema migrations
+  backup <PATH>                write a consistent snapshot of the database
+  snapshot <DIR> [--keep N]    write a timestamped snapshot into DIR and keep the newest N (default 7)
+  restore <PATH>               replace the database with a snapshot (stop the bot first)
+
+The database path defaults to the DB_PATH environment variable (.env is read).";
+
+/// ---
+/// 管理用CLIのコマンド
+/// ---
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AdminCommand {
+    Rooms,
+    Queue { room_id: u64 },
+    Vote { room_id: u64 },
+    Repair { room_id: u64 },
+    Resolve { room_id: u64 },
+    DeleteWord { room_id: u64, turn: u64 },
+    Migrate { dry_run: bool },
+    Backup { path: PathBuf },
+    Snapshot { dir: PathBuf, keep: usize },
+    Restore { path: PathBuf },
+}
+
+/// ---
+/// コマンドライン引数を読み込みます。
+/// 戻り値はデータベースのパス（--dbで指定した場合）とコマンドです。
+/// ---
+fn parse_args(args: &[String]) -> Result<(Option<String>, AdminCommand)> {
+    let mut db_path = None;
+    let mut rest = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--db" {
+            db_path = Some(iter.next().context("--db requires a path")?.clone());
+        } else {
+            rest.push(arg.as_str());
+        }
+    }
+
+    let id = |value: Option<&&str>, what: &str| -> Result<u64> {
+        let value = value.with_context(|| format!("missing {}", what))?;
+        value.parse().with_context(|| format!("invalid {}: {}", what, value))
+    };
+    let path = |value: Option<&&str>| -> Result<PathBuf> { Ok(PathBuf::from(value.context("missing path")?)) };
+
+    let command = match rest.first().copied() {
+        Some("rooms") => AdminCommand::Rooms,
+        Some("queue") => AdminCommand::Queue { room_id: id(rest.get(1), "room id")? },
+        Some("vote") => AdminCommand::Vote { room_id: id(rest.get(1), "room id")? },
+        Some("repair") => AdminCommand::Repair { room_id: id(rest.get(1), "room id")? },
+        Some("resolve") => AdminCommand::Resolve { room_id: id(rest.get(1), "room id")? },
+        Some("delete-word") => AdminCommand::DeleteWord {
+            room_id: id(rest.get(1), "room id")?,
+            turn: id(rest.get(2), "turn")?,
+        },
+        Some("migrate") => AdminCommand::Migrate { dry_run: rest.get(1) == Some(&"--dry-run") },
+        Some("backup") => AdminCommand::Backup { path: path(rest.get(1))? },
+        Some("snapshot") => AdminCommand::Snapshot {
+            dir: path(rest.get(1))?,
+            keep: match rest.iter().position(|arg| *arg == "--keep") {
+                Some(i) => id(rest.get(i + 1), "--keep")? as usize,
+                None => DEFAULT_RETENTION,
+            },
+        },
+        Some("restore") => AdminCommand::Restore { path: path(rest.get(1))? },
+        Some(other) => anyhow::bail!("unknown command: {}", other),
+        None => anyhow::bail!("missing command"),
+    };
+    Ok((db_path, command))
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    let result = match parse_args(&args) {
+        Ok((db_path, command)) => run(db_path, command).await,
+        Err(e) => {
+            eprintln!("{:#}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("error: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(db_path: Option<String>, command: AdminCommand) -> Result<()> {
+    let db_path = match db_path {
+        Some(path) => path,
+        None => {
+            dotenv::dotenv().ok();
+            std::env::var("DB_PATH").context("DB_PATH is not set (use --db PATH)")?
+        }
+    };
+
+    // データベースを置き換えるため、接続を開く前に処理する
+    if let AdminCommand::Restore { path } = &command {
+        return restore(path, Path::new(&db_path));
+    }
+
+    let db = DataBase::new(&db_path).await?;
+    match command {
+        AdminCommand::Migrate { dry_run } => {
+            let mode = if dry_run { MigrationMode::DryRun } else { MigrationMode::Apply };
+            let report = db.migrate(mode).await?;
+            println!("schema version: {} -> {}", report.from_version, report.to_version);
+            for (version, name) in report.applied {
+                println!("  {}: {:04}_{}", if dry_run { "pending" } else { "applied" }, version, name);
+            }
+            return Ok(());
+        }
+        AdminCommand::Backup { path } => {
+            db.backup_to(&path).await?;
+            println!("wrote backup to {}", path.display());
+            return Ok(());
+        }
+        AdminCommand::Snapshot { dir, keep } => {
+            let snapshot = db.take_snapshot(&dir, keep).await?;
+            println!("wrote snapshot {} ({} bytes)", snapshot.path.display(), snapshot.size);
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    // 古いスキーマのままRepositoryを使うと失敗するため、先に移行を求める
+    let version = db.schema_version().await?;
+    anyhow::ensure!(
+        version == latest_version(),
+        "schema version is {} but {} is required; run `migrate` first",
+        version,
+        latest_version()
+    );
+    let repo = Repository::new(db)?;
+
+    match command {
+        AdminCommand::Rooms => rooms(&repo).await,
+        AdminCommand::Queue { room_id } => match repo.get_queue(room_id).await {
+            Err(RepoError::BrokenChain) => anyhow::bail!("the turn order of room {} is broken; run `repair {}`", room_id, room_id),
+            result => {
+                print_queue(&result?);
+                Ok(())
+            }
+        },
+        AdminCommand::Vote { room_id } => vote(&repo, room_id).await,
+        AdminCommand::Repair { room_id } => {
+            let queue = repo.repair_queue(room_id).await?;
+            println!("repaired the turn order of room {}", room_id);
+            print_queue(&queue);
+            Ok(())
+        }
+        AdminCommand::Resolve { room_id } => {
+            print_resolution(&repo.force_resolve_vote(room_id).await?);
+            Ok(())
+        }
+        AdminCommand::DeleteWord { room_id, turn } => {
+            let record = repo.delete_word(room_id, turn).await?;
+            println!("deleted turn {}: {} ({})", record.turn, record.word, record.reading);
+            Ok(())
+        }
+        AdminCommand::Migrate { .. }
+        | AdminCommand::Backup { .. }
+        | AdminCommand::Snapshot { .. }
+        | AdminCommand::Restore { .. } => unreachable!(),
+    }
+}
+
+async fn rooms(repo: &Repository) -> Result<()> {
+    println!("{:<20} {:<9} {:>7} {:>6}  LAST WORD", "ROOM", "STATUS", "MEMBERS", "WORDS");
+    for room_id in repo.get_rooms().await? {
+        let status = repo.get_room_status(room_id).await?;
+        let members = repo.get_members(room_id).await?.len();
+        let words = repo.get_words(room_id).await?.len();
+        let last = repo
+            .get_last_word(room_id)
+            .await?
+            .map(|record| format!("{} ({})", record.word, record.reading))
+            .unwrap_or_default();
+        println!("{:<20} {:<9} {:>7} {:>6}  {}", room_id, status.as_str(), members, words, last);
+    }
+    Ok(())
+}
+
+async fn vote(repo: &Repository, room_id: u64) -> Result<()> {
+    let Some(vote) = repo.get_vote_state(room_id).await? else {
+        println!("room {} has no turn in progress", room_id);
+        return Ok(());
+    };
+    let users = |ids: &[u64]| ids.iter().map(|&id| user_label(id)).collect::<Vec<_>>().join(", ");
+    println!("turn: {}", user_label(vote.user_id));
+    match (&vote.word, &vote.reading) {
+        (Some(word), Some(reading)) => println!("word: {} ({})", word, reading),
+        (Some(word), None) => println!("word: {}", word),
+        _ => println!("word: (waiting for a submission)"),
+    }
+    println!("good: {}", users(&vote.good));
+    println!("bad:  {}", users(&vote.bad));
+    println!("none: {}", users(&vote.none));
+    if let Some(updated_at) = vote.updated_at {
+        println!("updated at: {}", updated_at);
+    }
+    Ok(())
+}
+
+fn print_queue(queue: &[u64]) {
+    if queue.is_empty() {
+        println!("(no members)");
+    }
+    for (i, &user_id) in queue.iter().enumerate() {
+        println!("{}. {}", i + 1, user_label(user_id));
+    }
+}
+
+fn print_resolution(resolution: &VoteResolution) {
+    match resolution {
+        VoteResolution::Pending => println!("the vote is still pending"),
+        VoteResolution::Accepted { user_id, word, turn, next_user_id, .. } => println!(
+            "accepted {} by {} as turn {}; next is {}",
+            word,
+            user_label(*user_id),
+            turn,
+            user_label(*next_user_id)
+        ),
+        VoteResolution::GameOver { user_id, word, turn, .. } => {
+            println!("accepted {} as turn {}; the game is over and {} lost", word, turn, user_label(*user_id))
+        }
+        VoteResolution::Rejected { user_id, word, .. } => {
+            println!("rejected {}; the turn returns to {}", word, user_label(*user_id))
+        }
+    }
+}
+
+/// ユーザーIDの表示（コンピューターは名前で表示します）
+fn user_label(user_id: u64) -> String {
+    if is_computer(user_id) { mention(user_id) } else { user_id.to_string() }
+}
+
+/// ---
+/// バックアップのファイルでデータベースを置き換えます。
+/// バックアップのスキーマがこのバイナリより新しい場合や、ファイルが壊れている場合は置き換えません。
+/// ---
+fn restore(backup: &Path, db_path: &Path) -> Result<()> {
+    let version = restore_snapshot(backup, db_path).with_context(|| format!("cannot restore {}", backup.display()))?;
+    println!("restored {} from {} (schema version {})", db_path.display(), backup.display(), version);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+    use shiritori::database::backup::list_snapshots;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_args() -> Result<()> {
+        assert_eq!(parse_args(&args("rooms"))?, (None, AdminCommand::Rooms));
+        assert_eq!(
+            parse_args(&args("--db test.db delete-word 10 3"))?,
+            (Some("test.db".to_string()), AdminCommand::DeleteWord { room_id: 10, turn: 3 })
+        );
+        assert_eq!(parse_args(&args("migrate --dry-run"))?, (None, AdminCommand::Migrate { dry_run: true }));
+        assert_eq!(
+            parse_args(&args("snapshot backups --keep 3"))?,
+            (None, AdminCommand::Snapshot { dir: PathBuf::from("backups"), keep: 3 })
+        );
+        assert_eq!(
+            parse_args(&args("snapshot backups"))?,
+            (None, AdminCommand::Snapshot { dir: PathBuf::from("backups"), keep: DEFAULT_RETENTION })
+        );
+        assert!(parse_args(&args("queue abc")).is_err(), "不正なルームIDが受け付けられました。");
+        assert!(parse_args(&args("unknown")).is_err(), "不明なコマンドが受け付けられました。");
+        assert!(parse_args(&[]).is_err(), "コマンドなしが受け付けられました。");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("shiritori-admin-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let db_path = dir.join("game.db");
+        let backup = dir.join("backup.db");
+        let db_str = db_path.to_string_lossy().into_owned();
+
+        run(Some(db_str.clone()), AdminCommand::Migrate { dry_run: false }).await?;
+        let repo = Repository::new(DataBase::new(&db_str).await?)?;
+        repo.create_room(1).await?;
+        run(Some(db_str.clone()), AdminCommand::Backup { path: backup.clone() }).await?;
+
+        run(Some(db_str.clone()), AdminCommand::Snapshot { dir: dir.join("snapshots"), keep: 1 }).await?;
+        assert_eq!(list_snapshots(&dir.join("snapshots"))?.len(), 1, "スナップショットが作成されていません。");
+
+        repo.create_room(2).await?;
+        drop(repo);
+        run(Some(db_str.clone()), AdminCommand::Restore { path: backup.clone() }).await?;
+        let repo = Repository::new(DataBase::new(&db_str).await?)?;
+        assert_eq!(repo.get_rooms().await?, vec![1], "バックアップの内容に戻っていません。");
+
+        // 新しいスキーマのバックアップは復元しない
+        let conn = Connection::open(&backup)?;
+        conn.pragma_update(None, "user_version", latest_version() + 1)?;
+        drop(conn);
+        assert!(restore(&backup, &db_path).is_err(), "新しいスキーマのバックアップが復元されました。");
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
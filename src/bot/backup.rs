@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+
+use crate::{
+    bot::bot_context::BotContext,
+    database::{backup::Snapshot, repository::RepoError},
+};
+
+/// ---
+/// 定期的にデータベースのスナップショットを作成するバックグラウンドタスクを起動します。
+/// バックアップが設定されていないか、間隔が指定されていない場合は起動せずにNoneを返します。
+/// 終了処理が始まると停止します。
+/// ---
+pub fn spawn(ctx: Arc<BotContext>) -> Option<JoinHandle<()>> {
+    let interval = ctx.config.backup()?.interval?;
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // 起動直後の1回目は作成しない
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let Some(_guard) = ctx.gate.enter() else {
+                break;
+            };
+            match run_once(&ctx).await {
+                Some(Ok(snapshot)) => println!("wrote snapshot {}", snapshot.path.display()),
+                Some(Err(e)) => eprintln!("Failed to write snapshot: {:?}", e),
+                None => {}
+            }
+        }
+    }))
+}
+
+/// ---
+/// スナップショットを1つ作成し、保存数を超えた古い世代を削除します。
+/// バックアップが設定されていない場合はNoneを返します。
+/// ---
+pub async fn run_once(ctx: &BotContext) -> Option<Result<Snapshot, RepoError>> {
+    let policy = ctx.config.backup()?;
+    Some(ctx.repo.take_snapshot(&policy.dir, policy.retention).await)
+}
@@ -1,4 +1,4 @@
-use crate::{bot::config::BotConfig, database::repository::Repository};
+use crate::{bot::{config::BotConfig, shutdown::ShutdownGate}, database::repository::Repository};
 
 use std::sync::Arc;
 
@@ -7,4 +7,6 @@ use std::sync::Arc;
 pub struct BotContext {
     pub config: Arc<BotConfig>,
     pub repo: Arc<Repository>,
-}
\ No newline at end of file
+    /// 終了処理中は新しいイベントを受け付けない
+    pub gate: Arc<ShutdownGate>,
+}
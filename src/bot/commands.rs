@@ -0,0 +1,1325 @@
+use serenity::all::{CommandOptionType, CreateCommand, CreateCommandOption, Permissions};
+
+use crate::{
+    archive::{ArchiveFormat, RoomArchive},
+    bot::{
+        bot_context::BotContext,
+        game::{describe_error, mention, word_label},
+    },
+    bot::backup,
+    database::{
+        backup::{list_snapshots, Snapshot},
+        repository::{RepoError, RoomStatus, StatsScope},
+    },
+    rules::{
+        chain::LongVowelRule,
+        computer::{Difficulty, COMPUTER_USER_ID},
+        config::{ConfigOverrides, DictionaryMode, Language, RoomConfig},
+        score::{LeaderboardMetric, PlayerStats, TimeWindow},
+        script::ScriptSet,
+        terminal::NEndingRule,
+        timeout::{TimeLimits, TurnTimeoutAction},
+        vote::{VotePolicy, VoteRule},
+    },
+};
+
+pub const COMMAND_NAME: &str = "shiritori";
+pub const LEADERBOARD_COMMAND_NAME: &str = "leaderboard";
+pub const STATS_COMMAND_NAME: &str = "stats";
+pub const BACKUP_COMMAND_NAME: &str = "backup";
+
+/// ---
+/// サブコマンドを持たない（コマンド名をそのままサブコマンド名として扱う）コマンド
+/// ---
+pub const STANDALONE_COMMAND_NAMES: [&str; 3] = [LEADERBOARD_COMMAND_NAME, STATS_COMMAND_NAME, BACKUP_COMMAND_NAME];
+
+/// 履歴の1ページあたりの件数
+const HISTORY_PAGE_SIZE: u64 = 10;
+
+/// ランキングに表示する人数
+const LEADERBOARD_SIZE: usize = 10;
+
+/// ---
+/// スラッシュコマンドの引数の値
+/// serenityの型から変換して使います。
+/// ---
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgValue {
+    Integer(i64),
+    String(String),
+    Boolean(bool),
+    /// ユーザーID
+    User(u64),
+    /// 添付ファイル（ダウンロード済みの内容）
+    Attachment { name: String, data: Vec<u8> },
+}
+
+/// ---
+/// 設定を変更する単位、または成績を集計する単位
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConfigScope {
+    /// このチャンネルのルーム
+    #[default]
+    Room,
+    /// サーバー（ルームの設定がない項目の既定値）
+    Guild,
+}
+
+/// ---
+/// /shiritori のサブコマンド
+/// ---
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShiritoriCommand {
+    /// ゲームを開始（終了済みなら再開始）し、実行者を参加させます
+    Start { n_ending: Option<NEndingRule> },
+    Join,
+    Leave,
+    Queue,
+    /// pageは1始まり
+    History { page: u64 },
+    End,
+    /// 投票の確定ルールを変更します
+    Vote { rule: VoteRule },
+    /// 制限時間を変更します
+    /// 指定されなかった項目は変更せず、秒数の0は無制限を表します
+    Timeout {
+        vote_secs: Option<u32>,
+        turn_secs: Option<u32>,
+        action: Option<TurnTimeoutAction>,
+    },
+    /// 設定を表示・変更します
+    /// resetがtrueの場合は、patchを反映する前に保存された設定を消します
+    Config {
+        scope: ConfigScope,
+        patch: ConfigOverrides,
+        reset: bool,
+    },
+    /// コンピューターを参加させるか、強さを変更します
+    /// removeがtrueの場合はコンピューターを退出させます
+    Computer { level: Option<Difficulty>, remove: bool },
+    /// 成績のランキングを表示します（/leaderboard）
+    Leaderboard {
+        scope: ConfigScope,
+        window: TimeWindow,
+        metric: LeaderboardMetric,
+    },
+    /// ルームの履歴をファイルに書き出します
+    Export { format: ArchiveFormat },
+    /// 書き出した履歴のファイルから、このチャンネルのルームを作成します
+    Import { file_name: String, data: Vec<u8> },
+    /// ユーザーの成績を表示します（/stats）
+    /// user_idがNoneの場合は実行者の成績を表示します
+    Stats {
+        user_id: Option<u64>,
+        scope: ConfigScope,
+        window: TimeWindow,
+    },
+    /// データベースのスナップショットを作成します（/backup、Botの管理者のみ）
+    /// listがtrueの場合は作成せずに、作成済みのスナップショットを表示します
+    Backup { list: bool },
+}
+
+impl ShiritoriCommand {
+    /// ---
+    /// サブコマンド名と引数からコマンドを組み立てます。
+    /// 不正な引数の場合はユーザー向けのメッセージを返します。
+    /// ---
+    pub fn parse(subcommand: &str, args: &[(String, ArgValue)]) -> Result<Self, String> {
+        let arg = |name: &str| find_arg(args, name);
+
+        match subcommand {
+            "start" => Ok(ShiritoriCommand::Start { n_ending: parse_n_ending(args)? }),
+            "join" => Ok(ShiritoriCommand::Join),
+            "leave" => Ok(ShiritoriCommand::Leave),
+            "queue" => Ok(ShiritoriCommand::Queue),
+            "history" => {
+                let page = match arg("page") {
+                    None => 1,
+                    Some(ArgValue::Integer(page)) if *page >= 1 => *page as u64,
+                    Some(v) => return Err(format!("ページ番号は1以上の整数で指定してください: {:?}", v)),
+                };
+                Ok(ShiritoriCommand::History { page })
+            }
+            "end" => Ok(ShiritoriCommand::End),
+            "vote" => match parse_vote_rule(args)? {
+                Some(rule) => Ok(ShiritoriCommand::Vote { rule }),
+                None => Err("policyを指定してください。".to_string()),
+            },
+            "timeout" => Ok(ShiritoriCommand::Timeout {
+                vote_secs: parse_secs(args, "vote")?,
+                turn_secs: parse_secs(args, "turn")?,
+                action: parse_turn_timeout_action(args, "action")?,
+            }),
+            "config" => {
+                let scope = parse_scope(args)?;
+                let reset = match arg("reset") {
+                    None => false,
+                    Some(ArgValue::Boolean(reset)) => *reset,
+                    Some(v) => return Err(format!("resetの値が不正です: {:?}", v)),
+                };
+                let min_word_length = match arg("min_length") {
+                    None => None,
+                    Some(ArgValue::Integer(v)) if *v >= 0 && *v <= u32::MAX as i64 => Some(*v as u32),
+                    Some(v) => return Err(format!("min_lengthは0以上の整数で指定してください: {:?}", v)),
+                };
+                let allowed_scripts = match arg("scripts") {
+                    None => None,
+                    Some(ArgValue::String(s)) if s == "all" => Some(ScriptSet::all()),
+                    Some(ArgValue::String(s)) => Some(ScriptSet::parse(s).ok_or_else(|| {
+                        format!(
+                            "不明な文字の種類です: {}\nhiragana, katakana, kanji, latin, digit をカンマ区切りで指定するか、all を指定してください。",
+                            s
+                        )
+                    })?),
+                    Some(v) => return Err(format!("scriptsの値が不正です: {:?}", v)),
+                };
+                let language = match arg("language") {
+                    None => None,
+                    Some(ArgValue::String(s)) => {
+                        Some(Language::parse(s).ok_or_else(|| format!("不明な言語です: {}", s))?)
+                    }
+                    Some(v) => return Err(format!("languageの値が不正です: {:?}", v)),
+                };
+                let dictionary_mode = match arg("dictionary") {
+                    None => None,
+                    Some(ArgValue::String(s)) => {
+                        Some(DictionaryMode::parse(s).ok_or_else(|| format!("不明な辞書の扱いです: {}", s))?)
+                    }
+                    Some(v) => return Err(format!("dictionaryの値が不正です: {:?}", v)),
+                };
+                let long_vowel = match arg("long_vowel") {
+                    None => None,
+                    Some(ArgValue::String(s)) => {
+                        Some(LongVowelRule::parse(s).ok_or_else(|| format!("不明な長音の扱いです: {}", s))?)
+                    }
+                    Some(v) => return Err(format!("long_vowelの値が不正です: {:?}", v)),
+                };
+                if arg("policy").is_none() && (arg("threshold").is_some() || arg("quorum").is_some()) {
+                    return Err("threshold・quorumを変更するにはpolicyも指定してください。".to_string());
+                }
+
+                let patch = ConfigOverrides {
+                    n_ending: parse_n_ending(args)?,
+                    vote_rule: parse_vote_rule(args)?,
+                    vote_timeout: parse_secs(args, "vote_timeout")?,
+                    turn_timeout: parse_secs(args, "turn_timeout")?,
+                    turn_timeout_action: parse_turn_timeout_action(args, "turn_action")?,
+                    min_word_length,
+                    allowed_scripts,
+                    language,
+                    dictionary_mode,
+                    long_vowel,
+                    expand_small_kana: parse_bool(args, "small_kana")?,
+                    ignore_dakuten: parse_bool(args, "dakuten")?,
+                };
+                Ok(ShiritoriCommand::Config { scope, patch, reset })
+            }
+            "computer" => {
+                let level = match arg("level") {
+                    None => None,
+                    Some(ArgValue::String(s)) => {
+                        Some(Difficulty::parse(s).ok_or_else(|| format!("不明なコンピューターの強さです: {}", s))?)
+                    }
+                    Some(v) => return Err(format!("levelの値が不正です: {:?}", v)),
+                };
+                let remove = match arg("remove") {
+                    None => false,
+                    Some(ArgValue::Boolean(remove)) => *remove,
+                    Some(v) => return Err(format!("removeの値が不正です: {:?}", v)),
+                };
+                Ok(ShiritoriCommand::Computer { level, remove })
+            }
+            "export" => {
+                let format = match arg("format") {
+                    None => ArchiveFormat::Json,
+                    Some(ArgValue::String(s)) => {
+                        ArchiveFormat::parse(s).ok_or_else(|| format!("不明なファイル形式です: {}", s))?
+                    }
+                    Some(v) => return Err(format!("formatの値が不正です: {:?}", v)),
+                };
+                Ok(ShiritoriCommand::Export { format })
+            }
+            "import" => match arg("file") {
+                Some(ArgValue::Attachment { name, data }) => {
+                    Ok(ShiritoriCommand::Import { file_name: name.clone(), data: data.clone() })
+                }
+                None => Err("取り込むファイルを指定してください。".to_string()),
+                Some(v) => Err(format!("fileの値が不正です: {:?}", v)),
+            },
+            LEADERBOARD_COMMAND_NAME => {
+                let metric = match arg("metric") {
+                    None => LeaderboardMetric::default(),
+                    Some(ArgValue::String(s)) => {
+                        LeaderboardMetric::parse(s).ok_or_else(|| format!("不明なランキングの種類です: {}", s))?
+                    }
+                    Some(v) => return Err(format!("metricの値が不正です: {:?}", v)),
+                };
+                Ok(ShiritoriCommand::Leaderboard { scope: parse_scope(args)?, window: parse_window(args)?, metric })
+            }
+            STATS_COMMAND_NAME => {
+                let user_id = match arg("user") {
+                    None => None,
+                    Some(ArgValue::User(user_id)) => Some(*user_id),
+                    Some(v) => return Err(format!("userの値が不正です: {:?}", v)),
+                };
+                Ok(ShiritoriCommand::Stats { user_id, scope: parse_scope(args)?, window: parse_window(args)? })
+            }
+            BACKUP_COMMAND_NAME => match arg("action") {
+                None => Ok(ShiritoriCommand::Backup { list: false }),
+                Some(ArgValue::String(s)) if s == "create" => Ok(ShiritoriCommand::Backup { list: false }),
+                Some(ArgValue::String(s)) if s == "list" => Ok(ShiritoriCommand::Backup { list: true }),
+                Some(v) => Err(format!("actionの値が不正です: {:?}", v)),
+            },
+            _ => Err(format!("不明なコマンドです: {}", subcommand)),
+        }
+    }
+}
+
+fn find_arg<'a>(args: &'a [(String, ArgValue)], name: &str) -> Option<&'a ArgValue> {
+    args.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+}
+
+/// scopeの引数を読み込みます（省略時はこのチャンネル）
+fn parse_scope(args: &[(String, ArgValue)]) -> Result<ConfigScope, String> {
+    match find_arg(args, "scope") {
+        None => Ok(ConfigScope::Room),
+        Some(ArgValue::String(s)) if s == "room" => Ok(ConfigScope::Room),
+        Some(ArgValue::String(s)) if s == "server" => Ok(ConfigScope::Guild),
+        Some(v) => Err(format!("scopeの値が不正です: {:?}", v)),
+    }
+}
+
+/// periodの引数を読み込みます（省略時はすべての期間）
+fn parse_window(args: &[(String, ArgValue)]) -> Result<TimeWindow, String> {
+    match find_arg(args, "period") {
+        None => Ok(TimeWindow::default()),
+        Some(ArgValue::String(s)) => TimeWindow::parse(s).ok_or_else(|| format!("不明な期間です: {}", s)),
+        Some(v) => Err(format!("periodの値が不正です: {:?}", v)),
+    }
+}
+
+fn parse_n_ending(args: &[(String, ArgValue)]) -> Result<Option<NEndingRule>, String> {
+    match find_arg(args, "n_ending") {
+        None => Ok(None),
+        Some(ArgValue::String(s)) => NEndingRule::parse(s)
+            .map(Some)
+            .ok_or_else(|| format!("不明な「ん」ルールです: {}", s)),
+        Some(v) => Err(format!("n_endingの値が不正です: {:?}", v)),
+    }
+}
+
+/// policy・threshold・quorumから投票ルールを組み立てます（policyがなければNone）
+fn parse_vote_rule(args: &[(String, ArgValue)]) -> Result<Option<VoteRule>, String> {
+    let threshold = match find_arg(args, "threshold") {
+        None => None,
+        Some(ArgValue::Integer(v)) if *v >= 1 => Some(*v as u32),
+        Some(v) => return Err(format!("thresholdは1以上の整数で指定してください: {:?}", v)),
+    };
+    let policy = match find_arg(args, "policy") {
+        None => return Ok(None),
+        Some(ArgValue::String(s)) => VotePolicy::parse(s, threshold).ok_or_else(|| match s.as_str() {
+            "supermajority" => "特別多数には1〜100の割合(%)をthresholdで指定してください。".to_string(),
+            "no_objection" => "異議なし承認には秒数をthresholdで指定してください。".to_string(),
+            _ => format!("不明な投票ルールです: {}", s),
+        })?,
+        Some(v) => return Err(format!("policyの値が不正です: {:?}", v)),
+    };
+    let quorum = match find_arg(args, "quorum") {
+        None => 0,
+        Some(ArgValue::Integer(v)) if *v >= 0 => *v as u32,
+        Some(v) => return Err(format!("quorumは0以上の整数で指定してください: {:?}", v)),
+    };
+    Ok(Some(VoteRule { policy, quorum }))
+}
+
+/// 秒数の引数を読み込みます（0は無制限）
+fn parse_secs(args: &[(String, ArgValue)], name: &str) -> Result<Option<u32>, String> {
+    match find_arg(args, name) {
+        None => Ok(None),
+        Some(ArgValue::Integer(v)) if *v >= 0 && *v <= u32::MAX as i64 => Ok(Some(*v as u32)),
+        Some(v) => Err(format!("{}は0以上の秒数で指定してください: {:?}", name, v)),
+    }
+}
+
+fn parse_bool(args: &[(String, ArgValue)], name: &str) -> Result<Option<bool>, String> {
+    match find_arg(args, name) {
+        None => Ok(None),
+        Some(ArgValue::Boolean(v)) => Ok(Some(*v)),
+        Some(v) => Err(format!("{}の値が不正です: {:?}", name, v)),
+    }
+}
+
+fn parse_turn_timeout_action(args: &[(String, ArgValue)], name: &str) -> Result<Option<TurnTimeoutAction>, String> {
+    match find_arg(args, name) {
+        None => Ok(None),
+        Some(ArgValue::String(s)) => TurnTimeoutAction::parse(s)
+            .map(Some)
+            .ok_or_else(|| format!("不明な時間切れの扱いです: {}", s)),
+        Some(v) => Err(format!("{}の値が不正です: {:?}", name, v)),
+    }
+}
+
+/// ---
+/// コマンドへの返信内容
+/// ---
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandReply {
+    pub content: String,
+    /// 実行者にのみ表示する
+    pub ephemeral: bool,
+    /// 添付するファイル
+    pub attachment: Option<ReplyAttachment>,
+}
+
+/// ---
+/// 返信に添付するファイル
+/// ---
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplyAttachment {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+impl CommandReply {
+    pub fn public(content: impl Into<String>) -> Self {
+        Self { content: content.into(), ephemeral: false, attachment: None }
+    }
+
+    pub fn ephemeral(content: impl Into<String>) -> Self {
+        Self { content: content.into(), ephemeral: true, attachment: None }
+    }
+
+    /// ファイルを添付します
+    pub fn with_attachment(mut self, name: impl Into<String>, data: Vec<u8>) -> Self {
+        self.attachment = Some(ReplyAttachment { name: name.into(), data });
+        self
+    }
+
+    pub fn error(e: &RepoError) -> Self {
+        Self::ephemeral(describe_error(e))
+    }
+}
+
+/// ---
+/// 登録するアプリケーションコマンドを作成します。
+/// ---
+pub fn create_commands() -> Vec<CreateCommand> {
+    let start = CreateCommandOption::new(CommandOptionType::SubCommand, "start", "このチャンネルでしりとりを開始します")
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "n_ending", "「ん」で終わる単語の扱い")
+                .add_string_choice("出した人の負け", NEndingRule::Lose.as_str())
+                .add_string_choice("「ん」から続ける", NEndingRule::Continue.as_str()),
+        );
+    let history = CreateCommandOption::new(CommandOptionType::SubCommand, "history", "既出単語の履歴を表示します")
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "page", "ページ番号（1が最新）").min_int_value(1),
+        );
+    let vote = CreateCommandOption::new(CommandOptionType::SubCommand, "vote", "単語を承認する投票のルールを変更します")
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "policy", "承認の条件")
+                .required(true)
+                .add_string_choice("過半数", VotePolicy::Majority.as_str())
+                .add_string_choice("特別多数（thresholdで割合%を指定）", "supermajority")
+                .add_string_choice("全員一致", VotePolicy::Unanimous.as_str())
+                .add_string_choice("異議なし（thresholdで秒数を指定）", "no_objection"),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "threshold", "特別多数の割合(%)または異議なしの秒数")
+                .min_int_value(1),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "quorum", "承認に必要な最低投票数").min_int_value(0),
+        );
+    let timeout = CreateCommandOption::new(CommandOptionType::SubCommand, "timeout", "投票と手番の制限時間を変更します")
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "vote", "投票を締め切るまでの秒数（0で無制限）")
+                .min_int_value(0),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "turn", "単語を投稿するまでの秒数（0で無制限）")
+                .min_int_value(0),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "action", "手番の時間切れの扱い")
+                .add_string_choice("手番を飛ばす", TurnTimeoutAction::Skip.as_str())
+                .add_string_choice("脱落させる", TurnTimeoutAction::Eliminate.as_str()),
+        );
+    let config = CreateCommandOption::new(CommandOptionType::SubCommand, "config", "ゲームの設定を表示・変更します")
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "scope", "変更する設定（省略時はこのチャンネル、サーバーはサーバー管理の権限が必要）")
+                .add_string_choice("このチャンネル", "room")
+                .add_string_choice("サーバー全体の既定値", "server"),
+        )
+        .add_sub_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "reset",
+            "保存された設定を消して上位の設定に戻す",
+        ))
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "n_ending", "「ん」で終わる単語の扱い")
+                .add_string_choice("出した人の負け", NEndingRule::Lose.as_str())
+                .add_string_choice("「ん」から続ける", NEndingRule::Continue.as_str()),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "policy", "承認の条件")
+                .add_string_choice("過半数", VotePolicy::Majority.as_str())
+                .add_string_choice("特別多数（thresholdで割合%を指定）", "supermajority")
+                .add_string_choice("全員一致", VotePolicy::Unanimous.as_str())
+                .add_string_choice("異議なし（thresholdで秒数を指定）", "no_objection"),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "threshold", "特別多数の割合(%)または異議なしの秒数")
+                .min_int_value(1),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "quorum", "承認に必要な最低投票数").min_int_value(0),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "vote_timeout", "投票を締め切るまでの秒数（0で無制限）")
+                .min_int_value(0),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "turn_timeout", "単語を投稿するまでの秒数（0で無制限）")
+                .min_int_value(0),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "turn_action", "手番の時間切れの扱い")
+                .add_string_choice("手番を飛ばす", TurnTimeoutAction::Skip.as_str())
+                .add_string_choice("脱落させる", TurnTimeoutAction::Eliminate.as_str()),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "min_length", "単語の最低文字数（0で制限なし）")
+                .min_int_value(0),
+        )
+        .add_sub_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "scripts",
+            "使える文字（hiragana,katakana,kanji,latin,digit のカンマ区切り、allで制限なし）",
+        ))
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "language", "言語")
+                .add_string_choice(Language::Japanese.label(), Language::Japanese.as_str())
+                .add_string_choice(Language::English.label(), Language::English.as_str()),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "dictionary", "辞書に載っていない単語の扱い")
+                .add_string_choice(DictionaryMode::Required.label(), DictionaryMode::Required.as_str())
+                .add_string_choice(DictionaryMode::Advisory.label(), DictionaryMode::Advisory.as_str())
+                .add_string_choice(DictionaryMode::Off.label(), DictionaryMode::Off.as_str()),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "long_vowel", "長音「ー」で終わる単語の次の文字")
+                .add_string_choice(LongVowelRule::Vowel.label(), LongVowelRule::Vowel.as_str())
+                .add_string_choice(LongVowelRule::Skip.label(), LongVowelRule::Skip.as_str()),
+        )
+        .add_sub_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "small_kana",
+            "小書き仮名を通常の仮名として続ける（いしゃ → や）。falseで拗音から続ける（いしゃ → しゃ）",
+        ))
+        .add_sub_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "dakuten",
+            "濁音・半濁音と清音を同じ文字として扱う（か ＝ が ＝ ぱ）",
+        ));
+
+    let mut level = CreateCommandOption::new(CommandOptionType::String, "level", "コンピューターの強さ（省略時はふつう）");
+    for difficulty in Difficulty::ALL {
+        level = level.add_string_choice(difficulty.label(), difficulty.as_str());
+    }
+    let computer = CreateCommandOption::new(CommandOptionType::SubCommand, "computer", "コンピューターを参加させます")
+        .add_sub_option(level)
+        .add_sub_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "remove",
+            "コンピューターを退出させる",
+        ));
+
+    let export = ArchiveFormat::ALL.into_iter().fold(
+        CreateCommandOption::new(CommandOptionType::String, "format", "ファイル形式（省略時はJSON）"),
+        |option, format| option.add_string_choice(format.as_str().to_uppercase(), format.as_str()),
+    );
+    let export = CreateCommandOption::new(CommandOptionType::SubCommand, "export", "このチャンネルの履歴をファイルに書き出します")
+        .add_sub_option(export);
+    let import = CreateCommandOption::new(CommandOptionType::SubCommand, "import", "書き出した履歴からゲームを作成します")
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::Attachment, "file", "書き出した履歴のファイル（.jsonまたは.csv）")
+                .required(true),
+        );
+
+    // /leaderboard と /stats で共通の集計範囲・期間
+    let stats_scope = || {
+        CreateCommandOption::new(CommandOptionType::String, "scope", "集計する範囲（省略時はこのチャンネル）")
+            .add_string_choice("このチャンネル", "room")
+            .add_string_choice("サーバー全体", "server")
+    };
+    let period = || {
+        TimeWindow::ALL.into_iter().fold(
+            CreateCommandOption::new(CommandOptionType::String, "period", "集計する期間（省略時はすべての期間）"),
+            |option, window| option.add_string_choice(window.label(), window.as_str()),
+        )
+    };
+    let metric = LeaderboardMetric::ALL.into_iter().fold(
+        CreateCommandOption::new(CommandOptionType::String, "metric", "並び順（省略時は承認された単語数）"),
+        |option, metric| option.add_string_choice(metric.label(), metric.as_str()),
+    );
+
+    vec![
+        CreateCommand::new(LEADERBOARD_COMMAND_NAME)
+            .description("しりとりの成績のランキングを表示します")
+            .add_option(stats_scope())
+            .add_option(period())
+            .add_option(metric),
+        CreateCommand::new(STATS_COMMAND_NAME)
+            .description("しりとりの成績を表示します")
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::User,
+                "user",
+                "成績を表示するユーザー（省略時は自分）",
+            ))
+            .add_option(stats_scope())
+            .add_option(period()),
+        CreateCommand::new(BACKUP_COMMAND_NAME)
+            .description("データベースのスナップショットを作成します（Botの管理者のみ）")
+            .default_member_permissions(Permissions::ADMINISTRATOR)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "action", "操作（省略時は作成）")
+                    .add_string_choice("作成する", "create")
+                    .add_string_choice("一覧を表示する", "list"),
+            ),
+        CreateCommand::new(COMMAND_NAME)
+            .description("しりとりのルームを操作します")
+            .add_option(start)
+            .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "join", "ゲームに参加します"))
+            .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "leave", "ゲームから退出します"))
+            .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "queue", "手番の順番を表示します"))
+            .add_option(history)
+            .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "end", "ゲームを終了します"))
+            .add_option(vote)
+            .add_option(timeout)
+            .add_option(config)
+            .add_option(computer)
+            .add_option(export)
+            .add_option(import),
+    ]
+}
+
+/// ---
+/// コマンドを実行し、返信内容を返します。
+/// ルームIDはチャンネルIDと同じです。guild_idはサーバー外（DMなど）ではNoneです。
+/// manage_guildは実行者がサーバーの管理権限（MANAGE_GUILD）を持っているかです。
+/// ---
+pub async fn execute(
+    ctx: &BotContext,
+    guild_id: Option<u64>,
+    room_id: u64,
+    user_id: u64,
+    manage_guild: bool,
+    command: ShiritoriCommand,
+) -> CommandReply {
+    let result = match command {
+        ShiritoriCommand::Start { n_ending } => start(ctx, guild_id, room_id, user_id, n_ending).await,
+        ShiritoriCommand::Join => join(ctx, room_id, user_id).await,
+        ShiritoriCommand::Leave => leave(ctx, room_id, user_id).await,
+        ShiritoriCommand::Queue => queue(ctx, room_id).await,
+        ShiritoriCommand::History { page } => history(ctx, room_id, page).await,
+        ShiritoriCommand::End => end(ctx, room_id, user_id).await,
+        ShiritoriCommand::Vote { rule } => vote_rule(ctx, room_id, user_id, manage_guild, rule).await,
+        ShiritoriCommand::Timeout { vote_secs, turn_secs, action } => {
+            time_limits(ctx, room_id, user_id, manage_guild, vote_secs, turn_secs, action).await
+        }
+        ShiritoriCommand::Config { scope, patch, reset } => {
+            let changed = reset || !patch.is_empty();
+            match check_config_permission(ctx, room_id, user_id, manage_guild, scope, changed).await {
+                Ok(None) => config(ctx, guild_id, room_id, scope, patch, reset).await,
+                Ok(Some(reply)) => Ok(reply),
+                Err(e) => Err(e),
+            }
+        }
+        ShiritoriCommand::Computer { level, remove } => computer(ctx, room_id, level, remove).await,
+        ShiritoriCommand::Export { format } => export(ctx, room_id, format).await,
+        ShiritoriCommand::Import { file_name, data } => import(ctx, guild_id, room_id, user_id, &file_name, &data).await,
+        ShiritoriCommand::Leaderboard { scope, window, metric } => {
+            leaderboard(ctx, guild_id, room_id, scope, window, metric).await
+        }
+        ShiritoriCommand::Stats { user_id: target, scope, window } => {
+            stats(ctx, guild_id, room_id, target.unwrap_or(user_id), scope, window).await
+        }
+        ShiritoriCommand::Backup { list } => backup(ctx, user_id, list).await,
+    };
+
+    result.unwrap_or_else(|e| CommandReply::error(&e))
+}
+
+async fn start(
+    ctx: &BotContext,
+    guild_id: Option<u64>,
+    room_id: u64,
+    user_id: u64,
+    n_ending: Option<NEndingRule>,
+) -> Result<CommandReply, RepoError> {
+    match ctx.repo.create_room(room_id).await {
+        Ok(()) => {}
+        Err(RepoError::RoomAlreadyExists) => match ctx.repo.get_room_status(room_id).await? {
+            RoomStatus::Finished => ctx.repo.restart_game(room_id).await?,
+            RoomStatus::Active | RoomStatus::Paused => {
+                return Ok(CommandReply::ephemeral("このチャンネルではすでにゲームが行われています。"));
+            }
+        },
+        Err(e) => return Err(e),
+    }
+
+    // サーバーの設定を引き継ぐ
+    if let Some(guild_id) = guild_id {
+        ctx.repo.set_room_guild(room_id, guild_id).await?;
+    }
+    if let Some(rule) = n_ending {
+        ctx.repo.set_n_ending_rule(room_id, rule).await?;
+    }
+    match ctx.repo.add_user(user_id, room_id).await {
+        Ok(_) | Err(RepoError::UserAlreadyExists) => {}
+        Err(e) => return Err(e),
+    }
+
+    Ok(CommandReply::public(format!(
+        "しりとりを開始しました！ <@{}> から最初の単語を投稿してください。\n参加するには `/{} join` を実行してください。",
+        user_id, COMMAND_NAME
+    )))
+}
+
+async fn join(ctx: &BotContext, room_id: u64, user_id: u64) -> Result<CommandReply, RepoError> {
+    ctx.repo.add_user(user_id, room_id).await?;
+    Ok(CommandReply::public(format!("<@{}> がしりとりに参加しました。", user_id)))
+}
+
+async fn leave(ctx: &BotContext, room_id: u64, user_id: u64) -> Result<CommandReply, RepoError> {
+    ctx.repo.get_room_status(room_id).await?;
+    ctx.repo.remove_user(room_id, user_id).await?;
+    Ok(CommandReply::public(format!("<@{}> がしりとりから退出しました。", user_id)))
+}
+
+async fn queue(ctx: &BotContext, room_id: u64) -> Result<CommandReply, RepoError> {
+    // 手番のユーザーから順に並ぶ
+    let members = match ctx.repo.get_queue(room_id).await {
+        Err(RepoError::BrokenChain) => {
+            eprintln!("Broken turn order in room {}, repairing", room_id);
+            ctx.repo.repair_queue(room_id).await?
+        }
+        result => result?,
+    };
+    if members.is_empty() {
+        return Ok(CommandReply::ephemeral("参加者がいません。"));
+    }
+    let current = ctx.repo.get_vote_state(room_id).await?.map(|vote| vote.user_id);
+
+    let lines: Vec<String> = members
+        .iter()
+        .enumerate()
+        .map(|(i, id)| {
+            let mark = if Some(*id) == current { "（手番）" } else { "" };
+            format!("{}. {}{}", i + 1, mention(*id), mark)
+        })
+        .collect();
+    Ok(CommandReply::ephemeral(format!("手番の順番:\n{}", lines.join("\n"))))
+}
+
+async fn history(ctx: &BotContext, room_id: u64, page: u64) -> Result<CommandReply, RepoError> {
+    let records = ctx
+        .repo
+        .get_history_page(room_id, page.saturating_sub(1), HISTORY_PAGE_SIZE)
+        .await?;
+    if records.is_empty() {
+        return Ok(CommandReply::ephemeral(if page == 1 {
+            "まだ単語がありません。".to_string()
+        } else {
+            format!("{}ページ目に単語はありません。", page)
+        }));
+    }
+
+    let lines: Vec<String> = records
+        .iter()
+        .map(|r| match r.user_id {
+            Some(user_id) => format!("{}. {}（{}）", r.turn, word_label(&r.word, &r.reading), mention(user_id)),
+            None => format!("{}. {}", r.turn, word_label(&r.word, &r.reading)),
+        })
+        .collect();
+    Ok(CommandReply::ephemeral(format!("既出単語（{}ページ目）:\n{}", page, lines.join("\n"))))
+}
+
+async fn end(ctx: &BotContext, room_id: u64, user_id: u64) -> Result<CommandReply, RepoError> {
+    if !ctx.repo.get_members(room_id).await?.contains(&user_id) {
+        return Err(RepoError::UserNotFound);
+    }
+    ctx.repo.finish_game(room_id, None).await?;
+    Ok(CommandReply::public(format!("<@{}> がゲームを終了しました。", user_id)))
+}
+
+async fn vote_rule(
+    ctx: &BotContext,
+    room_id: u64,
+    user_id: u64,
+    manage_guild: bool,
+    rule: VoteRule,
+) -> Result<CommandReply, RepoError> {
+    // 投票ルールはルームの設定の一部
+    if let Some(reply) = check_config_permission(ctx, room_id, user_id, manage_guild, ConfigScope::Room, true).await? {
+        return Ok(reply);
+    }
+    ctx.repo.set_vote_rule(room_id, rule).await?;
+    Ok(CommandReply::public(format!("投票ルールを「{}」に変更しました。", describe_vote_rule(&rule))))
+}
+
+/// 投票ルールの説明文を返します
+fn describe_vote_rule(rule: &VoteRule) -> String {
+    let policy = match rule.policy {
+        VotePolicy::Majority => "過半数の賛成で承認".to_string(),
+        VotePolicy::Supermajority { percent } => format!("{}%以上の賛成で承認", percent),
+        VotePolicy::Unanimous => "全員の賛成で承認".to_string(),
+        VotePolicy::NoObjection { seconds } => format!("{}秒以内に反対がなければ承認", seconds),
+    };
+    if rule.quorum > 0 {
+        format!("{}（最低{}票）", policy, rule.quorum)
+    } else {
+        policy
+    }
+}
+
+async fn time_limits(
+    ctx: &BotContext,
+    room_id: u64,
+    user_id: u64,
+    manage_guild: bool,
+    vote_secs: Option<u32>,
+    turn_secs: Option<u32>,
+    action: Option<TurnTimeoutAction>,
+) -> Result<CommandReply, RepoError> {
+    // 制限時間はルームの設定の一部
+    if let Some(reply) = check_config_permission(ctx, room_id, user_id, manage_guild, ConfigScope::Room, true).await? {
+        return Ok(reply);
+    }
+    let mut limits = ctx.repo.get_time_limits(room_id).await?;
+    if let Some(secs) = vote_secs {
+        limits.vote_secs = Some(secs).filter(|&secs| secs > 0);
+    }
+    if let Some(secs) = turn_secs {
+        limits.turn_secs = Some(secs).filter(|&secs| secs > 0);
+    }
+    if let Some(action) = action {
+        limits.on_turn_timeout = action;
+    }
+    ctx.repo.set_time_limits(room_id, limits).await?;
+
+    Ok(CommandReply::public(format!("制限時間を変更しました。\n{}", describe_time_limits(&limits))))
+}
+
+/// 制限時間の説明文を返します
+fn describe_time_limits(limits: &TimeLimits) -> String {
+    let limit = |secs: Option<u32>| match secs {
+        Some(secs) => format!("{}秒", secs),
+        None => "無制限".to_string(),
+    };
+    let action = match limits.on_turn_timeout {
+        TurnTimeoutAction::Skip => "手番を飛ばす",
+        TurnTimeoutAction::Eliminate => "脱落させる",
+    };
+    format!(
+        "投票: {}\n手番: {}（時間切れで{}）",
+        limit(limits.vote_secs),
+        limit(limits.turn_secs),
+        action
+    )
+}
+
+/// ---
+/// 設定を変更できるかを確かめ、変更できない場合は返信内容を返します。
+/// サーバーの設定は全てのルームに影響するため、サーバーの管理権限かBotの管理者であることを求めます。
+/// ルームの設定（vote・timeoutで変更する投票ルール・制限時間を含む）はそのルームの参加者も変更できます。
+/// 表示するだけの場合は誰でも実行できます。
+/// ---
+async fn check_config_permission(
+    ctx: &BotContext,
+    room_id: u64,
+    user_id: u64,
+    manage_guild: bool,
+    scope: ConfigScope,
+    changed: bool,
+) -> Result<Option<CommandReply>, RepoError> {
+    if !changed || manage_guild || ctx.config.is_admin(user_id) {
+        return Ok(None);
+    }
+    match scope {
+        ConfigScope::Guild => Ok(Some(CommandReply::ephemeral(
+            "サーバーの設定は、サーバーの管理権限を持つユーザーかBotの管理者のみ変更できます。",
+        ))),
+        ConfigScope::Room => {
+            let is_member = match ctx.repo.get_members(room_id).await {
+                Ok(members) => members.contains(&user_id),
+                Err(RepoError::RoomNotFound) => false,
+                Err(e) => return Err(e),
+            };
+            if is_member {
+                Ok(None)
+            } else {
+                Ok(Some(CommandReply::ephemeral("このチャンネルの設定は、しりとりの参加者のみ変更できます。")))
+            }
+        }
+    }
+}
+
+async fn config(
+    ctx: &BotContext,
+    guild_id: Option<u64>,
+    room_id: u64,
+    scope: ConfigScope,
+    patch: ConfigOverrides,
+    reset: bool,
+) -> Result<CommandReply, RepoError> {
+    let changed = reset || !patch.is_empty();
+    let (title, config) = match scope {
+        ConfigScope::Room => {
+            if changed {
+                if let Some(guild_id) = guild_id {
+                    ctx.repo.set_room_guild(room_id, guild_id).await?;
+                }
+                if reset {
+                    ctx.repo.reset_room_settings(room_id).await?;
+                }
+                ctx.repo.update_room_settings(room_id, patch).await?;
+            }
+            ("このチャンネルの設定", ctx.repo.get_room_config(room_id).await?)
+        }
+        ConfigScope::Guild => {
+            let Some(guild_id) = guild_id else {
+                return Ok(CommandReply::ephemeral("サーバーの設定はサーバー内でのみ変更できます。"));
+            };
+            if reset {
+                ctx.repo.reset_guild_settings(guild_id).await?;
+            }
+            if !patch.is_empty() {
+                ctx.repo.update_guild_settings(guild_id, patch).await?;
+            }
+            let mut config = RoomConfig::default();
+            ctx.repo.get_guild_settings(guild_id).await?.apply(&mut config);
+            ("サーバーの既定の設定", config)
+        }
+    };
+
+    let content = format!("{}:\n{}", title, describe_config(&config));
+    if changed {
+        Ok(CommandReply::public(format!("設定を変更しました。\n{}", content)))
+    } else {
+        Ok(CommandReply::ephemeral(content))
+    }
+}
+
+async fn computer(
+    ctx: &BotContext,
+    room_id: u64,
+    level: Option<Difficulty>,
+    remove: bool,
+) -> Result<CommandReply, RepoError> {
+    if remove {
+        return match ctx.repo.remove_user(room_id, COMPUTER_USER_ID).await {
+            Ok(()) => Ok(CommandReply::public(format!("{} がしりとりから退出しました。", mention(COMPUTER_USER_ID)))),
+            Err(RepoError::UserNotFound) => Ok(CommandReply::ephemeral("コンピューターは参加していません。")),
+            Err(e) => Err(e),
+        };
+    }
+
+    let level = level.unwrap_or_default();
+    match ctx.repo.add_computer(room_id, level).await {
+        Ok(()) => Ok(CommandReply::public(format!(
+            "{}（{}）がしりとりに参加しました。",
+            mention(COMPUTER_USER_ID),
+            level.label()
+        ))),
+        Err(RepoError::UserAlreadyExists) => {
+            ctx.repo.set_computer_level(room_id, level).await?;
+            Ok(CommandReply::public(format!("コンピューターの強さを「{}」に変更しました。", level.label())))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn export(ctx: &BotContext, room_id: u64, format: ArchiveFormat) -> Result<CommandReply, RepoError> {
+    let archive = ctx.repo.export_room(room_id).await?;
+    let text = archive.encode(format)?;
+    Ok(CommandReply::ephemeral(format!("このチャンネルの履歴を書き出しました（{}語）。", archive.words.len()))
+        .with_attachment(format!("shiritori-{}.{}", room_id, format.as_str()), text.into_bytes()))
+}
+
+async fn import(
+    ctx: &BotContext,
+    guild_id: Option<u64>,
+    room_id: u64,
+    user_id: u64,
+    file_name: &str,
+    data: &[u8],
+) -> Result<CommandReply, RepoError> {
+    let Some(format) = ArchiveFormat::from_file_name(file_name) else {
+        return Ok(CommandReply::ephemeral("拡張子が.jsonまたは.csvのファイルを指定してください。"));
+    };
+    let archive = match RoomArchive::decode(format, data) {
+        Ok(archive) => archive,
+        Err(e) => return Ok(CommandReply::ephemeral(format!("履歴を読み込めません: {:#}", e))),
+    };
+    let status = archive.status;
+    let count = match ctx.repo.import_room(room_id, archive).await {
+        Err(RepoError::RoomAlreadyExists) => {
+            return Ok(CommandReply::ephemeral("このチャンネルにはすでにゲームがあるため、履歴を取り込めません。"));
+        }
+        result => result?,
+    };
+
+    // サーバーの設定を引き継ぎ、実行者を参加させる
+    if let Some(guild_id) = guild_id {
+        ctx.repo.set_room_guild(room_id, guild_id).await?;
+    }
+    ctx.repo.add_user(user_id, room_id).await?;
+
+    let next = match status {
+        RoomStatus::Finished => format!("ゲームは終了しています。`/{} start` で新しいゲームを始められます。", COMMAND_NAME),
+        RoomStatus::Active | RoomStatus::Paused => format!("参加するには `/{} join` を実行してください。", COMMAND_NAME),
+    };
+    Ok(CommandReply::public(format!("<@{}> が履歴を取り込みました（{}語）。\n{}", user_id, count, next)))
+}
+
+/// ---
+/// 成績を集計する範囲と、その表示名を返します。
+/// サーバー外でサーバー全体が指定された場合はNoneを返します。
+/// ---
+fn stats_scope(guild_id: Option<u64>, room_id: u64, scope: ConfigScope) -> Option<(StatsScope, &'static str)> {
+    match scope {
+        ConfigScope::Room => Some((StatsScope::Room(room_id), "このチャンネル")),
+        ConfigScope::Guild => guild_id.map(|guild_id| (StatsScope::Guild(guild_id), "サーバー全体")),
+    }
+}
+
+async fn leaderboard(
+    ctx: &BotContext,
+    guild_id: Option<u64>,
+    room_id: u64,
+    scope: ConfigScope,
+    window: TimeWindow,
+    metric: LeaderboardMetric,
+) -> Result<CommandReply, RepoError> {
+    let Some((scope, scope_label)) = stats_scope(guild_id, room_id, scope) else {
+        return Ok(CommandReply::ephemeral("サーバー全体の成績はサーバー内でのみ表示できます。"));
+    };
+    let ranked = metric.rank(ctx.repo.get_player_stats(scope, window).await?);
+    let title = format!("🏆 ランキング（{}・{}・{}）", scope_label, window.label(), metric.label());
+    if ranked.is_empty() {
+        return Ok(CommandReply::ephemeral(format!("{}\nまだ記録がありません。", title)));
+    }
+
+    let lines: Vec<String> = ranked
+        .iter()
+        .take(LEADERBOARD_SIZE)
+        .enumerate()
+        .map(|(i, (value, stats))| {
+            let value = match metric {
+                LeaderboardMetric::Words => format!("{}語", value),
+                LeaderboardMetric::Wins => format!("{}勝", value),
+                LeaderboardMetric::Longest => {
+                    format!("{}（{}文字）", stats.longest_word.as_deref().unwrap_or_default(), value)
+                }
+                LeaderboardMetric::Fastest => format!("{}秒", value),
+                LeaderboardMetric::Streak => format!("{}連続", value),
+            };
+            format!("{}. {} {}", i + 1, mention(stats.user_id), value)
+        })
+        .collect();
+    Ok(CommandReply::ephemeral(format!("{}\n{}", title, lines.join("\n"))))
+}
+
+async fn stats(
+    ctx: &BotContext,
+    guild_id: Option<u64>,
+    room_id: u64,
+    user_id: u64,
+    scope: ConfigScope,
+    window: TimeWindow,
+) -> Result<CommandReply, RepoError> {
+    let Some((scope, scope_label)) = stats_scope(guild_id, room_id, scope) else {
+        return Ok(CommandReply::ephemeral("サーバー全体の成績はサーバー内でのみ表示できます。"));
+    };
+    let stats = ctx.repo.get_user_stats(scope, window, user_id).await?;
+    Ok(CommandReply::ephemeral(format!(
+        "{} の成績（{}・{}）:\n{}",
+        mention(user_id),
+        scope_label,
+        window.label(),
+        describe_stats(&stats)
+    )))
+}
+
+async fn backup(ctx: &BotContext, user_id: u64, list: bool) -> Result<CommandReply, RepoError> {
+    if !ctx.config.is_admin(user_id) {
+        return Ok(CommandReply::ephemeral("このコマンドはBotの管理者のみ実行できます。"));
+    }
+    let Some(policy) = ctx.config.backup() else {
+        return Ok(CommandReply::ephemeral("バックアップが設定されていません（BACKUP_DIR）。"));
+    };
+
+    if list {
+        let snapshots = list_snapshots(&policy.dir)?;
+        if snapshots.is_empty() {
+            return Ok(CommandReply::ephemeral("スナップショットはまだありません。"));
+        }
+        let lines: Vec<String> = snapshots.iter().map(describe_snapshot).collect();
+        return Ok(CommandReply::ephemeral(format!(
+            "スナップショット（新しい順、最大{}個を保存）:\n{}",
+            policy.retention,
+            lines.join("\n")
+        )));
+    }
+
+    match backup::run_once(ctx).await {
+        Some(snapshot) => Ok(CommandReply::ephemeral(format!(
+            "スナップショットを作成しました: {}",
+            describe_snapshot(&snapshot?)
+        ))),
+        None => Ok(CommandReply::ephemeral("バックアップが設定されていません（BACKUP_DIR）。")),
+    }
+}
+
+/// スナップショットの説明（ファイル名・作成日時・大きさ）を返します
+fn describe_snapshot(snapshot: &Snapshot) -> String {
+    let name = snapshot.path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    format!(
+        "`{}` {} UTC（{:.1} KB）",
+        name,
+        snapshot.created_at.format("%Y-%m-%d %H:%M:%S"),
+        snapshot.size as f64 / 1024.0
+    )
+}
+
+/// 成績の説明文を返します
+fn describe_stats(stats: &PlayerStats) -> String {
+    let longest = match &stats.longest_word {
+        Some(word) => format!("{}（{}文字）", word, stats.longest_length),
+        None => "なし".to_string(),
+    };
+    let fastest = match stats.fastest_secs {
+        Some(secs) => format!("{}秒", secs),
+        None => "なし".to_string(),
+    };
+    format!(
+        "承認された単語: {}\n否決された単語: {}\n勝ち: {} / 負け: {}\n最長の単語: {}\n最速の回答: {}\n最長連続承認: {}",
+        stats.accepted, stats.rejected, stats.won, stats.lost, longest, fastest, stats.best_streak
+    )
+}
+
+/// ゲーム設定の説明文を返します
+fn describe_config(config: &RoomConfig) -> String {
+    let n_ending = match config.n_ending {
+        NEndingRule::Lose => "出した人の負け",
+        NEndingRule::Continue => "「ん」から続ける",
+    };
+    let min_length = match config.min_word_length {
+        0 => "制限なし".to_string(),
+        length => format!("{}文字", length),
+    };
+    let rules = &config.chain_rules;
+    let small_kana = if rules.expand_small_kana { "通常の仮名として続ける" } else { "拗音から続ける" };
+    let dakuten = if rules.ignore_dakuten { "清音と同じに扱う" } else { "区別する" };
+    format!(
+        "「ん」で終わる単語: {}\n投票ルール: {}\n{}\n最低文字数: {}\n使える文字: {}\n言語: {}\n辞書: {}\n長音: {}\n小書き仮名: {}\n濁音・半濁音: {}",
+        n_ending,
+        describe_vote_rule(&config.vote_rule),
+        describe_time_limits(&config.time_limits),
+        min_length,
+        config.allowed_scripts.label(),
+        config.language.label(),
+        config.dictionary_mode.label(),
+        rules.long_vowel.label(),
+        small_kana,
+        dakuten
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(ShiritoriCommand::parse("join", &[]), Ok(ShiritoriCommand::Join));
+        assert_eq!(ShiritoriCommand::parse("start", &[]), Ok(ShiritoriCommand::Start { n_ending: None }));
+        assert_eq!(
+            ShiritoriCommand::parse("start", &[("n_ending".into(), ArgValue::String("continue".into()))]),
+            Ok(ShiritoriCommand::Start { n_ending: Some(NEndingRule::Continue) })
+        );
+        assert_eq!(ShiritoriCommand::parse("history", &[]), Ok(ShiritoriCommand::History { page: 1 }));
+        assert_eq!(
+            ShiritoriCommand::parse("history", &[("page".into(), ArgValue::Integer(3))]),
+            Ok(ShiritoriCommand::History { page: 3 })
+        );
+    }
+
+    #[test]
+    fn test_parse_stats_commands() {
+        assert_eq!(
+            ShiritoriCommand::parse(LEADERBOARD_COMMAND_NAME, &[]),
+            Ok(ShiritoriCommand::Leaderboard {
+                scope: ConfigScope::Room,
+                window: TimeWindow::All,
+                metric: LeaderboardMetric::Words,
+            })
+        );
+        assert_eq!(
+            ShiritoriCommand::parse(
+                STATS_COMMAND_NAME,
+                &[
+                    ("user".into(), ArgValue::User(42)),
+                    ("scope".into(), ArgValue::String("server".into())),
+                    ("period".into(), ArgValue::String("week".into())),
+                ]
+            ),
+            Ok(ShiritoriCommand::Stats { user_id: Some(42), scope: ConfigScope::Guild, window: TimeWindow::Week })
+        );
+        assert!(
+            ShiritoriCommand::parse(LEADERBOARD_COMMAND_NAME, &[("metric".into(), ArgValue::String("score".into()))]).is_err(),
+            "不明なランキングの種類が受け付けられました。"
+        );
+    }
+
+    #[test]
+    fn test_parse_backup_command() {
+        assert_eq!(ShiritoriCommand::parse(BACKUP_COMMAND_NAME, &[]), Ok(ShiritoriCommand::Backup { list: false }));
+        assert_eq!(
+            ShiritoriCommand::parse(BACKUP_COMMAND_NAME, &[("action".into(), ArgValue::String("list".into()))]),
+            Ok(ShiritoriCommand::Backup { list: true })
+        );
+        assert!(
+            ShiritoriCommand::parse(BACKUP_COMMAND_NAME, &[("action".into(), ArgValue::String("restore".into()))]).is_err(),
+            "不明な操作が受け付けられました。"
+        );
+    }
+
+    #[test]
+    fn test_parse_vote_command() {
+        assert_eq!(
+            ShiritoriCommand::parse("vote", &[("policy".into(), ArgValue::String("unanimous".into()))]),
+            Ok(ShiritoriCommand::Vote { rule: VoteRule { policy: VotePolicy::Unanimous, quorum: 0 } })
+        );
+        assert_eq!(
+            ShiritoriCommand::parse(
+                "vote",
+                &[
+                    ("policy".into(), ArgValue::String("supermajority".into())),
+                    ("threshold".into(), ArgValue::Integer(60)),
+                    ("quorum".into(), ArgValue::Integer(2)),
+                ]
+            ),
+            Ok(ShiritoriCommand::Vote {
+                rule: VoteRule { policy: VotePolicy::Supermajority { percent: 60 }, quorum: 2 }
+            })
+        );
+        assert!(
+            ShiritoriCommand::parse("vote", &[("policy".into(), ArgValue::String("no_objection".into()))]).is_err(),
+            "秒数のない異議なし承認が受け付けられました。"
+        );
+    }
+
+    #[test]
+    fn test_parse_timeout_command() {
+        assert_eq!(
+            ShiritoriCommand::parse("timeout", &[]),
+            Ok(ShiritoriCommand::Timeout { vote_secs: None, turn_secs: None, action: None })
+        );
+        assert_eq!(
+            ShiritoriCommand::parse(
+                "timeout",
+                &[
+                    ("vote".into(), ArgValue::Integer(0)),
+                    ("turn".into(), ArgValue::Integer(90)),
+                    ("action".into(), ArgValue::String("eliminate".into())),
+                ]
+            ),
+            Ok(ShiritoriCommand::Timeout {
+                vote_secs: Some(0),
+                turn_secs: Some(90),
+                action: Some(TurnTimeoutAction::Eliminate),
+            })
+        );
+        assert!(
+            ShiritoriCommand::parse("timeout", &[("turn".into(), ArgValue::Integer(-1))]).is_err(),
+            "負の秒数が受け付けられました。"
+        );
+    }
+
+    #[test]
+    fn test_parse_config_command() {
+        assert_eq!(
+            ShiritoriCommand::parse("config", &[]),
+            Ok(ShiritoriCommand::Config { scope: ConfigScope::Room, patch: ConfigOverrides::default(), reset: false })
+        );
+        assert_eq!(
+            ShiritoriCommand::parse(
+                "config",
+                &[
+                    ("scope".into(), ArgValue::String("server".into())),
+                    ("reset".into(), ArgValue::Boolean(true)),
+                    ("policy".into(), ArgValue::String("unanimous".into())),
+                    ("turn_timeout".into(), ArgValue::Integer(0)),
+                    ("min_length".into(), ArgValue::Integer(2)),
+                    ("scripts".into(), ArgValue::String("hiragana,katakana".into())),
+                    ("language".into(), ArgValue::String("ja".into())),
+                    ("dictionary".into(), ArgValue::String("advisory".into())),
+                ]
+            ),
+            Ok(ShiritoriCommand::Config {
+                scope: ConfigScope::Guild,
+                patch: ConfigOverrides {
+                    vote_rule: Some(VoteRule { policy: VotePolicy::Unanimous, quorum: 0 }),
+                    turn_timeout: Some(0),
+                    min_word_length: Some(2),
+                    allowed_scripts: ScriptSet::parse("hiragana,katakana"),
+                    language: Some(Language::Japanese),
+                    dictionary_mode: Some(DictionaryMode::Advisory),
+                    ..Default::default()
+                },
+                reset: true,
+            })
+        );
+        assert!(
+            ShiritoriCommand::parse("config", &[("scripts".into(), ArgValue::String("hangul".into()))]).is_err(),
+            "不明な文字の種類が受け付けられました。"
+        );
+        assert!(
+            ShiritoriCommand::parse("config", &[("quorum".into(), ArgValue::Integer(2))]).is_err(),
+            "policyのないquorumが受け付けられました。"
+        );
+    }
+
+    #[test]
+    fn test_parse_computer_command() {
+        assert_eq!(
+            ShiritoriCommand::parse("computer", &[]),
+            Ok(ShiritoriCommand::Computer { level: None, remove: false })
+        );
+        assert_eq!(
+            ShiritoriCommand::parse("computer", &[("level".into(), ArgValue::String("hard".into()))]),
+            Ok(ShiritoriCommand::Computer { level: Some(Difficulty::Hard), remove: false })
+        );
+        assert!(
+            ShiritoriCommand::parse("computer", &[("level".into(), ArgValue::String("expert".into()))]).is_err(),
+            "不明なコンピューターの強さが受け付けられました。"
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_commands() {
+        assert!(ShiritoriCommand::parse("unknown", &[]).is_err(), "不明なサブコマンドが受け付けられました。");
+        assert!(
+            ShiritoriCommand::parse("history", &[("page".into(), ArgValue::Integer(0))]).is_err(),
+            "0ページ目が受け付けられました。"
+        );
+        assert!(
+            ShiritoriCommand::parse("start", &[("n_ending".into(), ArgValue::String("invalid".into()))]).is_err(),
+            "不明な「ん」ルールが受け付けられました。"
+        );
+    }
+}
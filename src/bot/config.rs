@@ -1,37 +1,120 @@
 #![allow(dead_code)]
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::Context;
 use serenity::all::GatewayIntents;
 
+use crate::database::backup::{BackupPolicy, DEFAULT_RETENTION};
+
+/// 制限時間を確認する間隔の既定値
+const DEFAULT_SCHEDULER_INTERVAL: Duration = Duration::from_secs(5);
+/// スナップショットを作成する間隔の既定値
+const DEFAULT_BACKUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
 #[derive(Debug, Clone)]
 pub struct BotConfig {
     token: String,
     gateway_intents: GatewayIntents,
     db_path: String,
-    init_sql_path: String,
+    channel_ids: Vec<u64>,
+    scheduler_interval: Duration,
+    /// スナップショットの設定（Noneの場合はバックアップしない）
+    backup: Option<BackupPolicy>,
+    /// /backupなどの管理用コマンドを実行できるユーザー
+    admin_ids: Vec<u64>,
 }
 
 impl BotConfig {
-    pub fn new(token: String, db_path: String, gateway_intents: GatewayIntents, init_sql_path: String) -> Self {
+    pub fn new(token: String, db_path: String, gateway_intents: GatewayIntents, channel_ids: Vec<u64>) -> Self {
         Self {
-            token, db_path, gateway_intents, init_sql_path
+            token, db_path, gateway_intents, channel_ids,
+            scheduler_interval: DEFAULT_SCHEDULER_INTERVAL,
+            backup: None,
+            admin_ids: Vec::new(),
         }
     }
+
+    /// スナップショットの設定を変更します
+    pub fn with_backup(mut self, backup: Option<BackupPolicy>) -> Self {
+        self.backup = backup;
+        self
+    }
+
+    /// 管理用コマンドを実行できるユーザーを変更します
+    pub fn with_admin_ids(mut self, admin_ids: Vec<u64>) -> Self {
+        self.admin_ids = admin_ids;
+        self
+    }
     
-    pub fn from_env() -> Result<Self, std::env::VarError> {
+    pub fn from_env() -> anyhow::Result<Self> {
         const ENV_TOKEN: &str = "BOT_TOKEN";
         const ENV_DBPATH: &str = "DB_PATH";
-        const ENV_INIT_SQL_PATH: &str = "INIT_SQL";
+        const ENV_CHANNEL_IDS: &str = "CHANNEL_IDS";
+        const ENV_SCHEDULER_INTERVAL: &str = "SCHEDULER_INTERVAL_SECS";
+        const ENV_ADMIN_USER_IDS: &str = "ADMIN_USER_IDS";
+        const ENV_BACKUP_DIR: &str = "BACKUP_DIR";
+        const ENV_BACKUP_INTERVAL: &str = "BACKUP_INTERVAL_SECS";
+        const ENV_BACKUP_RETENTION: &str = "BACKUP_RETENTION";
         dotenv::dotenv().ok();
-        let token = std::env::var(ENV_TOKEN)?;
-        let db_path = std::env::var(ENV_DBPATH)?;
-        let init_sql_path = std::env::var(ENV_INIT_SQL_PATH)?;
-        let gateway_intents = GatewayIntents::privileged();
+        let token = std::env::var(ENV_TOKEN).context(ENV_TOKEN)?;
+        let db_path = std::env::var(ENV_DBPATH).context(ENV_DBPATH)?;
+        // しりとりを行うチャンネル（カンマ区切り、省略可）
+        let channel_ids = match std::env::var(ENV_CHANNEL_IDS) {
+            Ok(ids) => parse_id_list(&ids).context(ENV_CHANNEL_IDS)?,
+            Err(std::env::VarError::NotPresent) => Vec::new(),
+            Err(e) => return Err(e).context(ENV_CHANNEL_IDS),
+        };
+        // 制限時間を確認する間隔（秒、省略可）
+        let scheduler_interval = match std::env::var(ENV_SCHEDULER_INTERVAL) {
+            Ok(secs) => {
+                let secs = secs.trim().parse::<u64>().context(ENV_SCHEDULER_INTERVAL)?;
+                anyhow::ensure!(secs > 0, "{} must be greater than 0", ENV_SCHEDULER_INTERVAL);
+                Duration::from_secs(secs)
+            }
+            Err(std::env::VarError::NotPresent) => DEFAULT_SCHEDULER_INTERVAL,
+            Err(e) => return Err(e).context(ENV_SCHEDULER_INTERVAL),
+        };
+        // 管理用コマンドを実行できるユーザー（カンマ区切り、省略可）
+        let admin_ids = match std::env::var(ENV_ADMIN_USER_IDS) {
+            Ok(ids) => parse_id_list(&ids).context(ENV_ADMIN_USER_IDS)?,
+            Err(std::env::VarError::NotPresent) => Vec::new(),
+            Err(e) => return Err(e).context(ENV_ADMIN_USER_IDS),
+        };
+        // スナップショットを置くディレクトリ（省略時はバックアップしない）
+        // 間隔（秒）が0の場合は定期的には作成せず、/backupでのみ作成する
+        let backup = match std::env::var(ENV_BACKUP_DIR) {
+            Ok(dir) => {
+                let interval = match std::env::var(ENV_BACKUP_INTERVAL) {
+                    Ok(secs) => {
+                        let secs = secs.trim().parse::<u64>().context(ENV_BACKUP_INTERVAL)?;
+                        (secs > 0).then(|| Duration::from_secs(secs))
+                    }
+                    Err(std::env::VarError::NotPresent) => Some(DEFAULT_BACKUP_INTERVAL),
+                    Err(e) => return Err(e).context(ENV_BACKUP_INTERVAL),
+                };
+                let retention = match std::env::var(ENV_BACKUP_RETENTION) {
+                    Ok(count) => count.trim().parse::<usize>().context(ENV_BACKUP_RETENTION)?,
+                    Err(std::env::VarError::NotPresent) => DEFAULT_RETENTION,
+                    Err(e) => return Err(e).context(ENV_BACKUP_RETENTION),
+                };
+                Some(BackupPolicy { dir: PathBuf::from(dir), interval, retention })
+            }
+            Err(std::env::VarError::NotPresent) => None,
+            Err(e) => return Err(e).context(ENV_BACKUP_DIR),
+        };
+        let gateway_intents = GatewayIntents::GUILDS
+            | GatewayIntents::GUILD_MESSAGES
+            | GatewayIntents::MESSAGE_CONTENT;
         
         Ok(
             Self {
                 token,
                 db_path,
-                init_sql_path,
-                gateway_intents
+                gateway_intents,
+                channel_ids,
+                scheduler_interval,
+                backup,
+                admin_ids,
             }
         )
     }
@@ -41,14 +124,34 @@ impl BotConfig {
     }
 
     pub fn gateway_intents(&self) -> GatewayIntents {
-        self.gateway_intents.clone()
+        self.gateway_intents
     }
 
     pub fn db_path(&self) -> String {
         self.db_path.clone()
     }
-    
-    pub fn init_sql_path(&self) -> String {
-        self.init_sql_path.clone()
+
+    pub fn channel_ids(&self) -> Vec<u64> {
+        self.channel_ids.clone()
+    }
+
+    pub fn scheduler_interval(&self) -> Duration {
+        self.scheduler_interval
+    }
+
+    pub fn backup(&self) -> Option<&BackupPolicy> {
+        self.backup.as_ref()
     }
-}
\ No newline at end of file
+
+    pub fn is_admin(&self, user_id: u64) -> bool {
+        self.admin_ids.contains(&user_id)
+    }
+}
+
+fn parse_id_list(s: &str) -> anyhow::Result<Vec<u64>> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| id.parse::<u64>().with_context(|| format!("invalid id: {}", id)))
+        .collect()
+}
@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use crate::{
+    bot::{
+        bot_context::BotContext,
+        commands::{self, ArgValue, CommandReply, ShiritoriCommand},
+        game::{self, SubmitOutcome, VoteChoice, VoteReply},
+        gateway::{InteractionReply, Outbound, OutboundMessage},
+    },
+    database::repository::{RepoError, Vote},
+};
+
+/// ---
+/// 受信したイベントをゲームの処理へ振り分け、結果を送信します。
+/// serenityの型に依存しないため、テスト用の疑似Gatewayからも同じ処理を呼び出せます。
+/// 終了処理が始まった後のイベントは無視します。
+/// ---
+#[derive(Clone)]
+pub struct Dispatcher {
+    ctx: Arc<BotContext>,
+    outbound: Arc<dyn Outbound>,
+}
+
+impl Dispatcher {
+    pub fn new(ctx: Arc<BotContext>, outbound: Arc<dyn Outbound>) -> Self {
+        Self { ctx, outbound }
+    }
+
+    /// 設定されたチャンネルのルームを用意します
+    pub async fn on_ready(&self) {
+        for channel_id in self.ctx.config.channel_ids() {
+            match self.ctx.repo.create_room(channel_id).await {
+                Ok(()) | Err(RepoError::RoomAlreadyExists) => {}
+                Err(e) => eprintln!("Failed to create room for channel {}: {:?}", channel_id, e),
+            }
+        }
+    }
+
+    /// ---
+    /// チャンネルへの発言を単語の提出として処理します。
+    /// Botの発言は呼び出し側で除いてください。
+    /// ---
+    pub async fn on_message(&self, channel_id: u64, user_id: u64, message_id: u64, content: &str) {
+        // 終了処理中は新しい単語を受け付けない
+        let Some(_guard) = self.ctx.gate.enter() else {
+            return;
+        };
+
+        // ルームIDはチャンネルIDと同じ
+        let room_id = channel_id;
+        // ゲームと関係のない発言は、書き込み用の接続を使わずに読み取りだけで除く
+        match self.ctx.repo.is_awaiting_word(room_id, user_id).await {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => {
+                eprintln!("Failed to check turn in room {}: {:?}", room_id, e);
+                return;
+            }
+        }
+        match game::submit_word(&self.ctx, room_id, user_id, content).await {
+            Some(SubmitOutcome::VoteOpened(vote)) => post_vote(&self.ctx, self.outbound.as_ref(), &vote).await,
+            Some(SubmitOutcome::Resolved(resolution)) => {
+                let rules = game::room_chain_rules(&self.ctx, room_id).await;
+                let content = game::resolution_content(&resolution, &rules);
+                self.reply(channel_id, message_id, content).await;
+            }
+            Some(SubmitOutcome::Reply(reply)) => self.reply(channel_id, message_id, reply).await,
+            // 単語として扱わなかった発言では手番は変わらない
+            None => return,
+        }
+        play_computer_turn(&self.ctx, self.outbound.as_ref(), room_id).await;
+    }
+
+    /// ---
+    /// /shiritori のサブコマンド（/leaderboard・/statsはコマンド名）を実行し、応答を返します。
+    /// 終了処理中はNoneを返します（応答しません）。
+    /// ---
+    pub async fn on_command(
+        &self,
+        guild_id: Option<u64>,
+        channel_id: u64,
+        user_id: u64,
+        manage_guild: bool,
+        subcommand: Option<&str>,
+        args: &[(String, ArgValue)],
+    ) -> Option<InteractionReply> {
+        let _guard = self.ctx.gate.enter()?;
+
+        let Some(subcommand) = subcommand else {
+            return Some(InteractionReply::Message(CommandReply::ephemeral("サブコマンドを指定してください。")));
+        };
+        let reply = match ShiritoriCommand::parse(subcommand, args) {
+            Ok(cmd) => commands::execute(&self.ctx, guild_id, channel_id, user_id, manage_guild, cmd).await,
+            Err(message) => CommandReply::ephemeral(message),
+        };
+        // 退出などで手番がコンピューターに移った場合
+        play_computer_turn(&self.ctx, self.outbound.as_ref(), channel_id).await;
+        Some(InteractionReply::Message(reply))
+    }
+
+    /// ---
+    /// メッセージのボタン操作を処理し、応答を返します。
+    /// しりとりのボタンでない場合や終了処理中はNoneを返します。
+    /// ---
+    pub async fn on_button(
+        &self,
+        channel_id: u64,
+        user_id: u64,
+        message_id: u64,
+        custom_id: &str,
+    ) -> Option<InteractionReply> {
+        let choice = VoteChoice::from_custom_id(custom_id)?;
+        let _guard = self.ctx.gate.enter()?;
+
+        let reply = game::cast_vote(&self.ctx, channel_id, user_id, message_id, choice).await;
+        let reply = match reply {
+            VoteReply::Update(vote) => {
+                InteractionReply::UpdateMessage(OutboundMessage::vote(game::vote_message(&self.ctx, &vote).await, true))
+            }
+            VoteReply::Resolved(resolution) => {
+                let rules = game::room_chain_rules(&self.ctx, channel_id).await;
+                InteractionReply::UpdateMessage(OutboundMessage::vote(game::resolution_content(&resolution, &rules), false))
+            }
+            VoteReply::Closed => InteractionReply::UpdateMessage(OutboundMessage::close_vote()),
+            VoteReply::Error(message) => InteractionReply::Message(CommandReply::ephemeral(message)),
+        };
+        play_computer_turn(&self.ctx, self.outbound.as_ref(), channel_id).await;
+        Some(reply)
+    }
+
+    async fn reply(&self, channel_id: u64, message_id: u64, content: String) {
+        if let Err(e) = self.outbound.reply(channel_id, message_id, content).await {
+            eprintln!("Failed to reply to message: {:?}", e);
+        }
+    }
+}
+
+/// 投票メッセージを投稿し、投票に記録します
+async fn post_vote(ctx: &BotContext, outbound: &dyn Outbound, vote: &Vote) {
+    // ルームIDはチャンネルIDと同じ
+    let channel_id = vote.room_id;
+    let message = OutboundMessage::vote(game::vote_message(ctx, vote).await, true);
+    match outbound.send_message(channel_id, message).await {
+        Ok(sent_id) => {
+            if let Err(e) = ctx.repo.set_vote_message(vote.room_id, sent_id).await {
+                eprintln!("Failed to record vote message: {:?}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to post vote message: {:?}", e),
+    }
+}
+
+/// ---
+/// 手番がコンピューターであれば単語を提出し、結果をルームのチャンネルに投稿します。
+/// 手番が移りうるイベントの処理後に呼び出します。
+/// ---
+pub async fn play_computer_turn(ctx: &BotContext, outbound: &dyn Outbound, room_id: u64) {
+    let channel_id = room_id;
+    let content = match game::play_computer_turn(ctx, room_id).await {
+        Some(SubmitOutcome::VoteOpened(vote)) => return post_vote(ctx, outbound, &vote).await,
+        Some(SubmitOutcome::Resolved(resolution)) => {
+            game::resolution_content(&resolution, &game::room_chain_rules(ctx, room_id).await)
+        }
+        Some(SubmitOutcome::Reply(content)) => content,
+        None => return,
+    };
+    if let Err(e) = outbound.send_message(channel_id, OutboundMessage::text(content)).await {
+        eprintln!("Failed to post computer turn: {:?}", e);
+    }
+}
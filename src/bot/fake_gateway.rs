@@ -0,0 +1,665 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use anyhow::{anyhow, Result};
+use serenity::{all::GatewayIntents, async_trait};
+
+use crate::{
+    bot::{
+        bot_context::BotContext,
+        commands::ArgValue,
+        config::BotConfig,
+        dispatcher::Dispatcher,
+        gateway::{InteractionReply, Outbound, OutboundMessage},
+        scheduler,
+        shutdown::ShutdownGate,
+    },
+    database::{db::DataBase, migration::MigrationMode, repository::Repository},
+};
+
+/// ---
+/// 疑似Gatewayに記録されたメッセージ
+/// ---
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeMessage {
+    pub id: u64,
+    pub channel_id: u64,
+    /// 投稿したユーザー（Botの投稿はNone）
+    pub author: Option<u64>,
+    pub content: Option<String>,
+    /// 投票ボタンが付いている場合は、ボタンを押せるかどうか
+    pub vote_buttons: Option<bool>,
+    /// 返信先のメッセージ
+    pub reply_to: Option<u64>,
+}
+
+/// ---
+/// 送信内容をメモリに記録するOutbound
+/// ---
+#[derive(Default)]
+pub struct FakeOutbound {
+    next_id: AtomicU64,
+    messages: Mutex<Vec<FakeMessage>>,
+}
+
+impl FakeOutbound {
+    fn push(&self, channel_id: u64, author: Option<u64>, message: OutboundMessage, reply_to: Option<u64>) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        self.messages.lock().unwrap().push(FakeMessage {
+            id,
+            channel_id,
+            author,
+            content: message.content,
+            vote_buttons: message.vote_buttons,
+            reply_to,
+        });
+        id
+    }
+
+    fn apply_edit(&self, channel_id: u64, message_id: u64, edit: OutboundMessage) -> Result<()> {
+        let mut messages = self.messages.lock().unwrap();
+        let message = messages
+            .iter_mut()
+            .find(|m| m.channel_id == channel_id && m.id == message_id)
+            .ok_or_else(|| anyhow!("unknown message: {}", message_id))?;
+        if edit.content.is_some() {
+            message.content = edit.content;
+        }
+        if edit.vote_buttons.is_some() {
+            message.vote_buttons = edit.vote_buttons;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Outbound for FakeOutbound {
+    async fn send_message(&self, channel_id: u64, message: OutboundMessage) -> Result<u64> {
+        Ok(self.push(channel_id, None, message, None))
+    }
+
+    async fn reply(&self, channel_id: u64, message_id: u64, content: String) -> Result<u64> {
+        Ok(self.push(channel_id, None, OutboundMessage::text(content), Some(message_id)))
+    }
+
+    async fn edit_message(&self, channel_id: u64, message_id: u64, message: OutboundMessage) -> Result<()> {
+        self.apply_edit(channel_id, message_id, message)
+    }
+}
+
+/// 疑似Gatewayのコマンドを実行するサーバー
+pub const FAKE_GUILD_ID: u64 = 1;
+
+/// ---
+/// Discordに接続せずにBotを動かす疑似Gateway
+/// 発言・スラッシュコマンド・ボタン操作をDispatcherへ渡し、Botの送信内容を記録します。
+/// データベースはメモリ上に作成します。
+/// ---
+pub struct FakeGateway {
+    ctx: Arc<BotContext>,
+    outbound: Arc<FakeOutbound>,
+    dispatcher: Dispatcher,
+}
+
+impl FakeGateway {
+    pub async fn new(channel_ids: Vec<u64>) -> Result<Self> {
+        Self::with_config(BotConfig::new("token".to_string(), ":memory:".to_string(), GatewayIntents::empty(), channel_ids)).await
+    }
+
+    /// 設定を指定して疑似Gatewayを作成します（データベースのパスは無視され、常にメモリ上に作成します）
+    pub async fn with_config(config: BotConfig) -> Result<Self> {
+        let db = DataBase::new(":memory:").await?;
+        db.migrate(MigrationMode::Apply).await?;
+        let ctx = Arc::new(BotContext {
+            config: Arc::new(config),
+            repo: Arc::new(Repository::new(db)?),
+            gate: Arc::new(ShutdownGate::default()),
+        });
+        let outbound = Arc::new(FakeOutbound::default());
+        let dispatcher = Dispatcher::new(ctx.clone(), outbound.clone());
+        dispatcher.on_ready().await;
+
+        Ok(Self { ctx, outbound, dispatcher })
+    }
+
+    pub fn ctx(&self) -> &BotContext {
+        &self.ctx
+    }
+
+    /// ユーザーとしてチャンネルに発言し、発言のメッセージIDを返します
+    pub async fn say(&self, channel_id: u64, user_id: u64, content: &str) -> u64 {
+        let message_id = self.outbound.push(channel_id, Some(user_id), OutboundMessage::text(content), None);
+        self.dispatcher.on_message(channel_id, user_id, message_id, content).await;
+        message_id
+    }
+
+    /// ユーザーとしてFAKE_GUILD_IDのサーバーで /shiritori のサブコマンドを実行します
+    pub async fn command(
+        &self,
+        channel_id: u64,
+        user_id: u64,
+        subcommand: &str,
+        args: &[(&str, ArgValue)],
+    ) -> Option<InteractionReply> {
+        self.command_with_permission(channel_id, user_id, false, subcommand, args).await
+    }
+
+    /// サーバーの管理権限（MANAGE_GUILD）を持つユーザーとして /shiritori のサブコマンドを実行します
+    pub async fn command_as_manager(
+        &self,
+        channel_id: u64,
+        user_id: u64,
+        subcommand: &str,
+        args: &[(&str, ArgValue)],
+    ) -> Option<InteractionReply> {
+        self.command_with_permission(channel_id, user_id, true, subcommand, args).await
+    }
+
+    async fn command_with_permission(
+        &self,
+        channel_id: u64,
+        user_id: u64,
+        manage_guild: bool,
+        subcommand: &str,
+        args: &[(&str, ArgValue)],
+    ) -> Option<InteractionReply> {
+        let args: Vec<(String, ArgValue)> = args.iter().map(|(name, value)| (name.to_string(), value.clone())).collect();
+        self.dispatcher
+            .on_command(Some(FAKE_GUILD_ID), channel_id, user_id, manage_guild, Some(subcommand), &args)
+            .await
+    }
+
+    /// ---
+    /// ユーザーとしてメッセージのボタンを押します。
+    /// メッセージを更新する応答は、記録したメッセージに反映します。
+    /// ---
+    pub async fn click(&self, channel_id: u64, user_id: u64, message_id: u64, custom_id: &str) -> Option<InteractionReply> {
+        let reply = self.dispatcher.on_button(channel_id, user_id, message_id, custom_id).await;
+        if let Some(InteractionReply::UpdateMessage(update)) = &reply {
+            self.outbound
+                .apply_edit(channel_id, message_id, update.clone())
+                .expect("押したメッセージが記録されていません");
+        }
+        reply
+    }
+
+    /// 制限時間の処理を1回実行します
+    pub async fn run_scheduler(&self) {
+        scheduler::run_once(&self.ctx, self.outbound.as_ref()).await;
+    }
+
+    /// チャンネルのメッセージを投稿順に取得します
+    pub fn messages(&self, channel_id: u64) -> Vec<FakeMessage> {
+        let messages = self.outbound.messages.lock().unwrap();
+        messages.iter().filter(|m| m.channel_id == channel_id).cloned().collect()
+    }
+
+    /// チャンネルのBotによる最新の投稿を取得します
+    pub fn last_bot_message(&self, channel_id: u64) -> Option<FakeMessage> {
+        self.messages(channel_id).into_iter().rev().find(|m| m.author.is_none())
+    }
+
+    /// メッセージをIDで取得します
+    pub fn message(&self, message_id: u64) -> Option<FakeMessage> {
+        let messages = self.outbound.messages.lock().unwrap();
+        messages.iter().find(|m| m.id == message_id).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bot::{commands::CommandReply, game::VoteChoice},
+        database::{
+            backup::{list_snapshots, BackupPolicy},
+            repository::RoomStatus,
+        },
+        dictionary::{DictionaryEntry, DictionaryFormat},
+        rules::{
+            computer::COMPUTER_USER_ID,
+            vote::{VotePolicy, VoteRule},
+        },
+    };
+
+    const CHANNEL: u64 = 1000;
+    const ALICE: u64 = 1;
+    const BOB: u64 = 2;
+    const CAROL: u64 = 3;
+
+    fn content(reply: &Option<InteractionReply>) -> String {
+        match reply {
+            Some(InteractionReply::Message(CommandReply { content, .. })) => content.clone(),
+            Some(InteractionReply::UpdateMessage(message)) => message.content.clone().unwrap_or_default(),
+            None => String::new(),
+        }
+    }
+
+    /// 単語を投稿し、開かれた投票メッセージを返します
+    async fn submit(gateway: &FakeGateway, user_id: u64, word: &str) -> FakeMessage {
+        gateway.say(CHANNEL, user_id, word).await;
+        let message = gateway.last_bot_message(CHANNEL).expect("投票メッセージが投稿されていません。");
+        assert_eq!(message.vote_buttons, Some(true), "投票ボタンが有効ではありません。\nmessage: {:?}", message);
+        assert!(
+            message.content.as_deref().unwrap_or_default().contains(word),
+            "投票メッセージに単語が含まれていません。\nmessage: {:?}",
+            message
+        );
+        message
+    }
+
+    #[tokio::test]
+    async fn test_full_game() -> anyhow::Result<()> {
+        let gateway = FakeGateway::new(Vec::new()).await?;
+
+        let reply = gateway.command(CHANNEL, ALICE, "start", &[]).await;
+        assert!(content(&reply).contains("開始"), "開始の応答がありません。\nreply: {:?}", reply);
+        let reply = gateway.command(CHANNEL, BOB, "join", &[]).await;
+        assert!(content(&reply).contains("参加"), "参加の応答がありません。\nreply: {:?}", reply);
+
+        // 参加者以外の発言は会話として無視される
+        gateway.say(CHANNEL, CAROL, "らっぱ").await;
+        assert!(gateway.last_bot_message(CHANNEL).is_none(), "参加者以外の発言に応答しました。");
+
+        let vote = submit(&gateway, ALICE, "しりとり").await;
+        let reply = gateway.click(CHANNEL, BOB, vote.id, VoteChoice::Good.custom_id()).await;
+        assert!(content(&reply).contains("承認"), "単語が承認されませんでした。\nreply: {:?}", reply);
+        assert_eq!(gateway.message(vote.id).unwrap().vote_buttons, Some(false), "確定した投票のボタンが無効になっていません。");
+
+        // 前の単語から続かない単語には返信で知らせる
+        gateway.say(CHANNEL, BOB, "みかん").await;
+        let notice = gateway.last_bot_message(CHANNEL).unwrap();
+        assert!(notice.reply_to.is_some(), "接続エラーが返信になっていません。\nmessage: {:?}", notice);
+        assert!(
+            notice.content.as_deref().unwrap_or_default().contains("「り」"),
+            "次の文字が案内されていません。\nmessage: {:?}",
+            notice
+        );
+
+        let vote = submit(&gateway, BOB, "りんご").await;
+        gateway.click(CHANNEL, ALICE, vote.id, VoteChoice::Good.custom_id()).await;
+
+        // 手番ではないユーザーの発言も無視される
+        let before = gateway.messages(CHANNEL).len();
+        gateway.say(CHANNEL, BOB, "ごま").await;
+        assert_eq!(gateway.messages(CHANNEL).len(), before + 1, "手番以外の発言に応答しました。");
+
+        let vote = submit(&gateway, ALICE, "ごはん").await;
+        let reply = gateway.click(CHANNEL, BOB, vote.id, VoteChoice::Good.custom_id()).await;
+        assert!(content(&reply).contains("負け"), "ゲームが終了しませんでした。\nreply: {:?}", reply);
+
+        assert_eq!(gateway.ctx().repo.get_room_status(CHANNEL).await?, RoomStatus::Finished);
+        assert_eq!(
+            gateway.ctx().repo.get_words(CHANNEL).await?,
+            vec!["しりとり", "りんご", "ごはん"],
+            "承認された単語が記録されていません。"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reject_and_stale_vote() -> anyhow::Result<()> {
+        let gateway = FakeGateway::new(Vec::new()).await?;
+        gateway.command(CHANNEL, ALICE, "start", &[]).await;
+        gateway.command(CHANNEL, BOB, "join", &[]).await;
+
+        let vote = submit(&gateway, ALICE, "しりとり").await;
+
+        // 参加者以外と投稿者自身は投票できない
+        let reply = gateway.click(CHANNEL, CAROL, vote.id, VoteChoice::Good.custom_id()).await;
+        assert!(
+            matches!(&reply, Some(InteractionReply::Message(CommandReply { ephemeral: true, .. }))),
+            "参加者以外の投票が拒否されませんでした。\nreply: {:?}",
+            reply
+        );
+        let reply = gateway.click(CHANNEL, ALICE, vote.id, VoteChoice::Good.custom_id()).await;
+        assert!(content(&reply).contains("自分"), "自分への投票が拒否されませんでした。\nreply: {:?}", reply);
+
+        let reply = gateway.click(CHANNEL, BOB, vote.id, VoteChoice::Bad.custom_id()).await;
+        assert!(content(&reply).contains("否決"), "単語が否決されませんでした。\nreply: {:?}", reply);
+
+        // 終了した投票のボタンは無効化するだけ
+        let reply = gateway.click(CHANNEL, BOB, vote.id, VoteChoice::Good.custom_id()).await;
+        assert_eq!(reply, Some(InteractionReply::UpdateMessage(OutboundMessage::close_vote())));
+
+        // 否決後は同じユーザーが出し直せる
+        submit(&gateway, ALICE, "しりとり").await;
+
+        // しりとり以外のボタンには応答しない
+        assert!(gateway.click(CHANNEL, BOB, vote.id, "other_button").await.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_closes_vote() -> anyhow::Result<()> {
+        let gateway = FakeGateway::new(Vec::new()).await?;
+        gateway.command(CHANNEL, ALICE, "start", &[]).await;
+        gateway.command(CHANNEL, BOB, "join", &[]).await;
+        gateway
+            .command(CHANNEL, ALICE, "vote", &[("policy", ArgValue::String("no_objection".to_string())), ("threshold", ArgValue::Integer(1))])
+            .await;
+
+        let vote = submit(&gateway, ALICE, "しりとり").await;
+        gateway.run_scheduler().await;
+        assert_eq!(gateway.message(vote.id).unwrap().vote_buttons, Some(true), "異議の受付中に投票が締め切られました。");
+
+        tokio::time::sleep(std::time::Duration::from_millis(2100)).await;
+        gateway.run_scheduler().await;
+        assert_eq!(gateway.message(vote.id).unwrap().vote_buttons, Some(false), "締め切った投票のボタンが無効になっていません。");
+        let notice = gateway.last_bot_message(CHANNEL).unwrap();
+        assert!(
+            notice.content.as_deref().unwrap_or_default().contains("承認"),
+            "締め切りの結果が通知されていません。\nmessage: {:?}",
+            notice
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_config_command() -> anyhow::Result<()> {
+        let gateway = FakeGateway::new(Vec::new()).await?;
+        let reply = gateway
+            .command_as_manager(CHANNEL, ALICE, "config", &[("scope", ArgValue::String("server".to_string())), ("min_length", ArgValue::Integer(3))])
+            .await;
+        assert!(content(&reply).contains("3文字"), "サーバーの設定が表示されていません。\nreply: {:?}", reply);
+
+        // サーバーの設定は開始したルームに引き継がれる
+        gateway.command(CHANNEL, ALICE, "start", &[]).await;
+        gateway.command(CHANNEL, BOB, "join", &[]).await;
+        gateway.say(CHANNEL, ALICE, "いす").await;
+        let notice = gateway.last_bot_message(CHANNEL).unwrap();
+        assert!(
+            notice.content.as_deref().unwrap_or_default().contains("3文字以上"),
+            "最低文字数の違反が通知されていません。\nmessage: {:?}",
+            notice
+        );
+
+        // ルームの設定で上書きする
+        let reply = gateway
+            .command(CHANNEL, ALICE, "config", &[("min_length", ArgValue::Integer(0)), ("scripts", ArgValue::String("katakana".to_string()))])
+            .await;
+        assert!(
+            matches!(&reply, Some(InteractionReply::Message(CommandReply { ephemeral: false, .. }))),
+            "設定の変更が公開されていません。\nreply: {:?}",
+            reply
+        );
+        gateway.say(CHANNEL, ALICE, "いす").await;
+        let notice = gateway.last_bot_message(CHANNEL).unwrap();
+        assert!(
+            notice.content.as_deref().unwrap_or_default().contains("カタカナ"),
+            "文字の種類の違反が通知されていません。\nmessage: {:?}",
+            notice
+        );
+        submit(&gateway, ALICE, "イス").await;
+
+        // 変更しない場合は実行者にだけ表示する
+        let reply = gateway.command(CHANNEL, BOB, "config", &[]).await;
+        assert!(
+            matches!(&reply, Some(InteractionReply::Message(CommandReply { ephemeral: true, .. }))),
+            "設定の表示が実行者のみになっていません。\nreply: {:?}",
+            reply
+        );
+
+        // 単語の接続の規則もルームごとに変更できる
+        let reply = gateway
+            .command(CHANNEL, ALICE, "config", &[("dakuten", ArgValue::Boolean(true)), ("long_vowel", ArgValue::String("skip".to_string()))])
+            .await;
+        assert!(
+            content(&reply).contains("濁音・半濁音: 清音と同じに扱う") && content(&reply).contains("長音: 長音の前の文字から続ける"),
+            "接続の規則の変更が表示されていません。\nreply: {:?}",
+            reply
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_config_requires_permission() -> anyhow::Result<()> {
+        let gateway = FakeGateway::new(Vec::new()).await?;
+        gateway.command(CHANNEL, ALICE, "start", &[]).await;
+
+        // サーバーの設定はルームの参加者でも変更できない
+        let server = [("scope", ArgValue::String("server".to_string())), ("min_length", ArgValue::Integer(3))];
+        let reply = gateway.command(CHANNEL, ALICE, "config", &server).await;
+        assert!(content(&reply).contains("管理権限"), "権限のないユーザーがサーバーの設定を変更できました。\nreply: {:?}", reply);
+        assert_eq!(gateway.ctx().repo.get_guild_settings(FAKE_GUILD_ID).await?.min_word_length, None, "サーバーの設定が変更されました。");
+
+        // ルームの設定は参加者以外は変更できない
+        let room = [("min_length", ArgValue::Integer(4))];
+        let reply = gateway.command(CHANNEL, CAROL, "config", &room).await;
+        assert!(content(&reply).contains("参加者のみ"), "参加者以外がルームの設定を変更できました。\nreply: {:?}", reply);
+        assert_eq!(gateway.ctx().repo.get_room_config(CHANNEL).await?.min_word_length, 0, "ルームの設定が変更されました。");
+
+        // 表示するだけなら誰でも実行できる
+        let reply = gateway.command(CHANNEL, CAROL, "config", &[]).await;
+        assert!(content(&reply).contains("このチャンネルの設定"), "設定が表示されていません。\nreply: {:?}", reply);
+
+        // 参加者はルームの設定を、管理権限を持つユーザーはサーバーの設定を変更できる
+        gateway.command(CHANNEL, ALICE, "config", &room).await;
+        assert_eq!(gateway.ctx().repo.get_room_config(CHANNEL).await?.min_word_length, 4, "参加者がルームの設定を変更できません。");
+        gateway.command_as_manager(CHANNEL, CAROL, "config", &server).await;
+        assert_eq!(gateway.ctx().repo.get_guild_settings(FAKE_GUILD_ID).await?.min_word_length, Some(3), "管理権限で設定を変更できません。");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_vote_and_timeout_require_membership() -> anyhow::Result<()> {
+        let gateway = FakeGateway::new(Vec::new()).await?;
+        gateway.command(CHANNEL, ALICE, "start", &[]).await;
+
+        // 投票ルール・制限時間も、参加者以外は変更できない
+        let vote = [("policy", ArgValue::String("unanimous".to_string()))];
+        let reply = gateway.command(CHANNEL, CAROL, "vote", &vote).await;
+        assert!(content(&reply).contains("参加者のみ"), "参加者以外が投票ルールを変更できました。\nreply: {:?}", reply);
+        assert_eq!(gateway.ctx().repo.get_vote_rule(CHANNEL).await?, VoteRule::default(), "投票ルールが変更されました。");
+
+        let timeout = [("turn", ArgValue::Integer(30))];
+        let reply = gateway.command(CHANNEL, CAROL, "timeout", &timeout).await;
+        assert!(content(&reply).contains("参加者のみ"), "参加者以外が制限時間を変更できました。\nreply: {:?}", reply);
+        assert_eq!(gateway.ctx().repo.get_time_limits(CHANNEL).await?.turn_secs, None, "制限時間が変更されました。");
+
+        // 参加者と、管理権限を持つユーザーは変更できる
+        gateway.command(CHANNEL, ALICE, "vote", &vote).await;
+        assert_eq!(
+            gateway.ctx().repo.get_vote_rule(CHANNEL).await?.policy,
+            VotePolicy::Unanimous,
+            "参加者が投票ルールを変更できません。"
+        );
+        gateway.command_as_manager(CHANNEL, CAROL, "timeout", &timeout).await;
+        assert_eq!(gateway.ctx().repo.get_time_limits(CHANNEL).await?.turn_secs, Some(30), "管理権限で制限時間を変更できません。");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dictionary_advisory() -> anyhow::Result<()> {
+        let gateway = FakeGateway::new(Vec::new()).await?;
+        gateway
+            .ctx()
+            .repo
+            .load_dictionary("test", DictionaryFormat::Plain, vec![DictionaryEntry::new("尻取り", "しりとり")])
+            .await?;
+        gateway.command(CHANNEL, ALICE, "start", &[]).await;
+        gateway.command(CHANNEL, BOB, "join", &[]).await;
+        gateway.command(CHANNEL, ALICE, "config", &[("dictionary", ArgValue::String("advisory".to_string()))]).await;
+
+        let vote = submit(&gateway, ALICE, "しりとり").await;
+        assert!(
+            vote.content.as_deref().unwrap_or_default().contains("辞書に載っています"),
+            "辞書に載っていることが表示されていません。\nmessage: {:?}",
+            vote
+        );
+        gateway.click(CHANNEL, BOB, vote.id, VoteChoice::Good.custom_id()).await;
+
+        let vote = submit(&gateway, BOB, "りんごあめ").await;
+        assert!(
+            vote.content.as_deref().unwrap_or_default().contains("辞書に載っていません"),
+            "辞書に載っていないことが表示されていません。\nmessage: {:?}",
+            vote
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_computer_opponent() -> anyhow::Result<()> {
+        let gateway = FakeGateway::new(Vec::new()).await?;
+        gateway
+            .ctx()
+            .repo
+            .load_dictionary(
+                "test",
+                DictionaryFormat::Plain,
+                vec![DictionaryEntry::new("栗鼠", "りす"), DictionaryEntry::new("すいか", "すいか")],
+            )
+            .await?;
+        gateway.command(CHANNEL, ALICE, "start", &[]).await;
+        let reply = gateway.command(CHANNEL, ALICE, "computer", &[("level", ArgValue::String("easy".to_string()))]).await;
+        assert!(content(&reply).contains("コンピューター"), "コンピューターが参加していません。\nreply: {:?}", reply);
+
+        // コンピューターが賛成してそのまま承認され、コンピューターが続けて単語を提出する
+        gateway.say(CHANNEL, ALICE, "しりとり").await;
+        let vote = gateway.last_bot_message(CHANNEL).unwrap();
+        assert_eq!(vote.vote_buttons, Some(true), "コンピューターの単語の投票が開かれていません。\nmessage: {:?}", vote);
+        assert!(
+            vote.content.as_deref().unwrap_or_default().contains("栗鼠(りす)"),
+            "コンピューターが辞書の単語を出していません。\nmessage: {:?}",
+            vote
+        );
+
+        // コンピューターの単語も投票で決まる
+        let reply = gateway.click(CHANNEL, ALICE, vote.id, VoteChoice::Good.custom_id()).await;
+        assert!(content(&reply).contains("承認"), "コンピューターの単語が承認されませんでした。\nreply: {:?}", reply);
+
+        // 続けられる単語がなければコンピューターの負け
+        gateway.say(CHANNEL, ALICE, "すいか").await;
+        let notice = gateway.last_bot_message(CHANNEL).unwrap();
+        assert!(
+            notice.content.as_deref().unwrap_or_default().contains("負けを認めました"),
+            "コンピューターが負けを認めていません。\nmessage: {:?}",
+            notice
+        );
+        let result = gateway.ctx().repo.get_game_result(CHANNEL).await?.unwrap();
+        assert_eq!(result.loser_id, Some(COMPUTER_USER_ID));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_export_and_import() -> anyhow::Result<()> {
+        let gateway = FakeGateway::new(Vec::new()).await?;
+        gateway.command(CHANNEL, ALICE, "start", &[]).await;
+        gateway.command(CHANNEL, BOB, "join", &[]).await;
+        let vote = submit(&gateway, ALICE, "しりとり").await;
+        gateway.click(CHANNEL, BOB, vote.id, VoteChoice::Good.custom_id()).await;
+
+        for format in ["json", "csv"] {
+            let reply = gateway.command(CHANNEL, ALICE, "export", &[("format", ArgValue::String(format.to_string()))]).await;
+            let Some(InteractionReply::Message(CommandReply { attachment: Some(file), .. })) = reply else {
+                panic!("履歴のファイルが添付されていません。\nreply: {:?}", reply);
+            };
+            assert!(file.name.ends_with(format), "ファイル名の拡張子が形式と一致しません: {}", file.name);
+
+            // 別のチャンネルに取り込むと、同じ単語から続けられる
+            let channel = CHANNEL + 1 + format.len() as u64;
+            let file = ArgValue::Attachment { name: file.name, data: file.data };
+            let reply = gateway.command(channel, BOB, "import", &[("file", file.clone())]).await;
+            assert!(content(&reply).contains("1語"), "履歴が取り込まれていません。\nreply: {:?}", reply);
+            assert_eq!(gateway.ctx().repo.get_words(channel).await?, vec!["しりとり"]);
+            gateway.say(channel, BOB, "りんご").await;
+            assert_eq!(
+                gateway.ctx().repo.get_words(channel).await?,
+                vec!["しりとり", "りんご"],
+                "取り込んだルームで続けられません。"
+            );
+
+            let reply = gateway.command(channel, BOB, "import", &[("file", file)]).await;
+            assert!(content(&reply).contains("すでにゲームがある"), "既存のルームに取り込まれました。\nreply: {:?}", reply);
+        }
+
+        let reply = gateway
+            .command(CHANNEL, ALICE, "import", &[("file", ArgValue::Attachment { name: "room.txt".to_string(), data: Vec::new() })])
+            .await;
+        assert!(content(&reply).contains(".json"), "不明な形式のファイルが受け付けられました。\nreply: {:?}", reply);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_leaderboard_and_stats() -> anyhow::Result<()> {
+        let gateway = FakeGateway::new(Vec::new()).await?;
+        gateway.command(CHANNEL, ALICE, "start", &[]).await;
+        gateway.command(CHANNEL, BOB, "join", &[]).await;
+
+        let reply = gateway.command(CHANNEL, ALICE, "leaderboard", &[]).await;
+        assert!(content(&reply).contains("まだ記録がありません"), "記録がないことが表示されていません。\nreply: {:?}", reply);
+
+        let vote = submit(&gateway, ALICE, "しりとり").await;
+        gateway.click(CHANNEL, BOB, vote.id, VoteChoice::Good.custom_id()).await;
+        let vote = submit(&gateway, BOB, "りんご").await;
+        gateway.click(CHANNEL, ALICE, vote.id, VoteChoice::Bad.custom_id()).await;
+
+        let reply = gateway
+            .command(CHANNEL, BOB, "leaderboard", &[("scope", ArgValue::String("server".to_string()))])
+            .await;
+        let text = content(&reply);
+        assert!(text.contains("サーバー全体"), "集計範囲が表示されていません。\nreply: {:?}", reply);
+        assert!(text.contains(&format!("1. <@{}> 1語", ALICE)), "ランキングが表示されていません。\nreply: {:?}", reply);
+        assert!(!text.contains(&format!("<@{}>", BOB)), "承認された単語のないユーザーが表示されました。\nreply: {:?}", reply);
+
+        let reply = gateway
+            .command(CHANNEL, ALICE, "stats", &[("user", ArgValue::User(BOB)), ("period", ArgValue::String("day".to_string()))])
+            .await;
+        let text = content(&reply);
+        assert!(text.contains(&format!("<@{}> の成績", BOB)), "指定したユーザーの成績が表示されていません。\nreply: {:?}", reply);
+        assert!(text.contains("否決された単語: 1"), "否決された単語が数えられていません。\nreply: {:?}", reply);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_backup_command() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("shiritori-backup-command-{}", std::process::id()));
+        let config = BotConfig::new("token".to_string(), ":memory:".to_string(), GatewayIntents::empty(), Vec::new())
+            .with_admin_ids(vec![ALICE])
+            .with_backup(Some(BackupPolicy { dir: dir.clone(), interval: None, retention: 2 }));
+        let gateway = FakeGateway::with_config(config).await?;
+        gateway.command(CHANNEL, ALICE, "start", &[]).await;
+
+        let reply = gateway.command(CHANNEL, BOB, "backup", &[]).await;
+        assert!(content(&reply).contains("管理者のみ"), "管理者以外がバックアップを作成できました。\nreply: {:?}", reply);
+        assert!(list_snapshots(&dir)?.is_empty(), "管理者以外の操作でスナップショットが作成されました。");
+
+        let reply = gateway.command(CHANNEL, ALICE, "backup", &[]).await;
+        assert!(content(&reply).contains("作成しました"), "スナップショットが作成されていません。\nreply: {:?}", reply);
+        let snapshots = list_snapshots(&dir)?;
+        assert_eq!(snapshots.len(), 1, "スナップショットの数が一致しません。");
+
+        let reply = gateway.command(CHANNEL, ALICE, "backup", &[("action", ArgValue::String("list".to_string()))]).await;
+        let name = snapshots[0].path.file_name().unwrap().to_string_lossy().to_string();
+        assert!(content(&reply).contains(&name), "スナップショットの一覧が表示されていません。\nreply: {:?}", reply);
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ignore_events_after_shutdown() -> anyhow::Result<()> {
+        let gateway = FakeGateway::new(Vec::new()).await?;
+        gateway.command(CHANNEL, ALICE, "start", &[]).await;
+        gateway.ctx().gate.close().await;
+
+        gateway.say(CHANNEL, ALICE, "しりとり").await;
+        assert!(gateway.last_bot_message(CHANNEL).is_none(), "終了処理中の発言に応答しました。");
+        assert!(gateway.command(CHANNEL, BOB, "join", &[]).await.is_none(), "終了処理中のコマンドに応答しました。");
+        assert!(gateway.ctx().repo.get_words(CHANNEL).await?.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,383 @@
+use crate::{
+    bot::bot_context::BotContext,
+    database::repository::{RepoError, RoomStatus, TimeoutEvent, Vote, VoteResolution, VoteTally},
+    dictionary::normalize_reading,
+    rules::{
+        chain::{tail_unit, ChainRules},
+        computer::{is_computer, COMPUTER_USER_ID},
+        config::DictionaryMode,
+        reading::WordInput,
+    },
+};
+
+/// ---
+/// 単語提出の結果
+/// ---
+#[derive(Debug)]
+pub enum SubmitOutcome {
+    /// 投票を開始した（投票メッセージを投稿する）
+    VoteOpened(Vote),
+    /// 投票できる参加者がおらず、そのまま結果が確定した
+    Resolved(VoteResolution),
+    /// 提出者に返信する
+    Reply(String),
+}
+
+/// ---
+/// 投票ボタンの種類
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoteChoice {
+    Good,
+    Bad,
+}
+
+impl VoteChoice {
+    /// room_members.stateの値
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VoteChoice::Good => "good",
+            VoteChoice::Bad => "bad",
+        }
+    }
+
+    /// ボタンのcustom_id
+    pub fn custom_id(&self) -> &'static str {
+        match self {
+            VoteChoice::Good => "shiritori_vote_good",
+            VoteChoice::Bad => "shiritori_vote_bad",
+        }
+    }
+
+    pub fn from_custom_id(custom_id: &str) -> Option<Self> {
+        [VoteChoice::Good, VoteChoice::Bad]
+            .into_iter()
+            .find(|choice| choice.custom_id() == custom_id)
+    }
+}
+
+/// ---
+/// 投票ボタンを押した結果
+/// ---
+#[derive(Debug)]
+pub enum VoteReply {
+    /// 投票メッセージを最新の集計で更新する
+    Update(Vote),
+    /// 投票の結果が確定した（ボタンを無効化して結果を表示する）
+    Resolved(VoteResolution),
+    /// 投票はすでに終了している（ボタンを無効化する）
+    Closed,
+    /// 投票者にのみエラーを表示する
+    Error(String),
+}
+
+/// ---
+/// ルームへの発言を単語の提出として処理します。
+/// 手番ではないユーザーの発言など、ゲームに関係しない発言にはNoneを返します。
+/// ---
+pub async fn submit_word(ctx: &BotContext, room_id: u64, user_id: u64, content: &str) -> Option<SubmitOutcome> {
+    let word = content.trim();
+    if word.is_empty() {
+        return None;
+    }
+
+    match ctx.repo.add_vote_state(room_id, user_id, word).await {
+        Ok(()) => match ctx.repo.resolve_vote(room_id).await {
+            Ok(VoteResolution::Pending) => match ctx.repo.get_vote_state(room_id).await {
+                Ok(Some(vote)) => Some(SubmitOutcome::VoteOpened(vote)),
+                Ok(None) => Some(SubmitOutcome::Reply(describe_error(&RepoError::VoteNotExists))),
+                Err(e) => Some(SubmitOutcome::Reply(describe_error(&e))),
+            },
+            Ok(resolution) => Some(SubmitOutcome::Resolved(resolution)),
+            Err(e) => Some(SubmitOutcome::Reply(describe_error(&e))),
+        },
+        // 手番以外・参加者以外の発言や、ゲーム外の発言は通常の会話として扱う
+        Err(
+            RepoError::RoomNotFound
+            | RepoError::UserNotFound
+            | RepoError::NotFirstUser
+            | RepoError::VoteInProgress
+            | RepoError::GameNotActive,
+        ) => None,
+        Err(RepoError::ChainMismatch) => Some(SubmitOutcome::Reply(chain_mismatch_message(ctx, room_id).await)),
+        Err(RepoError::ReadingRequired) => {
+            let surface = WordInput::parse(word).surface;
+            Some(SubmitOutcome::Reply(format!(
+                "「{}」のよみが分かりません。「{}(よみ)」の形式でよみを付けて投稿してください。",
+                surface, surface
+            )))
+        }
+        Err(e @ (RepoError::WordTooShort | RepoError::ScriptNotAllowed)) => {
+            Some(SubmitOutcome::Reply(restriction_message(ctx, room_id, &e).await))
+        }
+        Err(e) => Some(SubmitOutcome::Reply(describe_error(&e))),
+    }
+}
+
+/// ---
+/// 手番がコンピューターであれば、辞書から単語を選んで提出します。
+/// 続けられる単語がない場合は、コンピューターの負けとしてゲームを終了します。
+/// コンピューターの手番でない場合や、人間の参加者がいない場合はNoneを返します。
+/// ---
+pub async fn play_computer_turn(ctx: &BotContext, room_id: u64) -> Option<SubmitOutcome> {
+    match ctx.repo.get_vote_state(room_id).await {
+        Ok(Some(vote)) if is_computer(vote.user_id) && vote.word.is_none() => {}
+        Ok(_) => return None,
+        Err(e) => {
+            eprintln!("Failed to load vote state: {:?}", e);
+            return None;
+        }
+    }
+    match ctx.repo.get_room_status(room_id).await {
+        Ok(RoomStatus::Active) => {}
+        Ok(_) => return None,
+        Err(e) => {
+            eprintln!("Failed to load room status: {:?}", e);
+            return None;
+        }
+    }
+    // コンピューターだけでは続けない
+    match ctx.repo.get_members(room_id).await {
+        Ok(members) if members.iter().any(|&id| !is_computer(id)) => {}
+        Ok(_) => return None,
+        Err(e) => {
+            eprintln!("Failed to load members: {:?}", e);
+            return None;
+        }
+    }
+
+    match ctx.repo.choose_computer_word(room_id).await {
+        Ok(Some(entry)) => submit_word(ctx, room_id, COMPUTER_USER_ID, &word_label(&entry.surface, &entry.reading)).await,
+        Ok(None) => match ctx.repo.finish_game(room_id, Some(COMPUTER_USER_ID)).await {
+            Ok(()) => Some(SubmitOutcome::Reply(format!(
+                "{} は続けられる単語が見つからず、負けを認めました！ゲームを終了しました。",
+                mention(COMPUTER_USER_ID)
+            ))),
+            Err(e) => Some(SubmitOutcome::Reply(describe_error(&e))),
+        },
+        Err(e) => {
+            eprintln!("Failed to choose computer word: {:?}", e);
+            None
+        }
+    }
+}
+
+/// ---
+/// 投票メッセージのボタンによる投票を処理します。
+/// message_idが進行中の投票のメッセージでない場合は終了済みとして扱います。
+/// ---
+pub async fn cast_vote(ctx: &BotContext, room_id: u64, user_id: u64, message_id: u64, choice: VoteChoice) -> VoteReply {
+    let vote = match ctx.repo.get_vote_state(room_id).await {
+        Ok(Some(vote)) if vote.word.is_some() && vote.message_id == Some(message_id) => vote,
+        Ok(_) => return VoteReply::Closed,
+        Err(e) => return VoteReply::Error(describe_error(&e)),
+    };
+    if vote.user_id == user_id {
+        return VoteReply::Error("自分の単語には投票できません。".to_string());
+    }
+
+    match ctx.repo.vote(room_id, user_id, choice.as_str()).await {
+        Ok(()) => {}
+        Err(RepoError::UserNotFound) => return VoteReply::Error("しりとりの参加者のみ投票できます。".to_string()),
+        Err(RepoError::VoteNotExists) => return VoteReply::Closed,
+        Err(e) => return VoteReply::Error(describe_error(&e)),
+    }
+
+    match ctx.repo.resolve_vote(room_id).await {
+        Ok(VoteResolution::Pending) => {}
+        Ok(resolution) => return VoteReply::Resolved(resolution),
+        Err(RepoError::VoteNotExists | RepoError::GameNotActive) => return VoteReply::Closed,
+        Err(e) => return VoteReply::Error(describe_error(&e)),
+    }
+
+    match ctx.repo.get_vote_state(room_id).await {
+        Ok(Some(vote)) => VoteReply::Update(vote),
+        Ok(None) => VoteReply::Closed,
+        Err(e) => VoteReply::Error(describe_error(&e)),
+    }
+}
+
+/// ---
+/// 投票メッセージの本文を作成します。
+/// ---
+pub fn vote_content(vote: &Vote) -> String {
+    let word = vote.word.as_deref().unwrap_or_default();
+    format!(
+        "{} の単語「{}」に投票してください。\n{}",
+        mention(vote.user_id),
+        word_label(word, vote.reading.as_deref().unwrap_or(word)),
+        tally_content(&VoteTally::from(vote))
+    )
+}
+
+/// ---
+/// メッセージに表示する参加者の名前を返します。
+/// コンピューターはDiscordのユーザーではないため、メンションではなく名前で表示します。
+/// ---
+pub fn mention(user_id: u64) -> String {
+    if is_computer(user_id) {
+        "🤖コンピューター".to_string()
+    } else {
+        format!("<@{}>", user_id)
+    }
+}
+
+/// ---
+/// 単語の表示用の文字列を返します。
+/// よみが表記から分からない場合（漢字を含むなど）は `表記(よみ)` の形式にします。
+/// ---
+pub fn word_label(surface: &str, reading: &str) -> String {
+    if normalize_reading(surface) == reading {
+        surface.to_string()
+    } else {
+        format!("{}({})", surface, reading)
+    }
+}
+
+/// ---
+/// 投票メッセージの本文を、ルームの設定に応じた補足を付けて作成します。
+/// 辞書の扱いが「投票時に表示」の場合は、単語が辞書に載っているかどうかを添えます。
+/// ---
+pub async fn vote_message(ctx: &BotContext, vote: &Vote) -> String {
+    let content = vote_content(vote);
+    let Some(word) = vote.word.as_deref() else {
+        return content;
+    };
+    let reading = vote.reading.as_deref().unwrap_or(word);
+    match ctx.repo.get_room_config(vote.room_id).await {
+        Ok(config) if config.dictionary_mode == DictionaryMode::Advisory => {}
+        Ok(_) => return content,
+        Err(e) => {
+            eprintln!("Failed to load room config: {:?}", e);
+            return content;
+        }
+    }
+    match ctx.repo.is_in_dictionary(word, reading).await {
+        Ok(true) => format!("{}\n📖 辞書に載っています", content),
+        Ok(false) => format!("{}\n📖 辞書に載っていません", content),
+        Err(e) => {
+            eprintln!("Failed to look up dictionary: {:?}", e);
+            content
+        }
+    }
+}
+
+/// ---
+/// 確定した投票の結果を表示する本文を作成します。
+/// ---
+pub fn resolution_content(resolution: &VoteResolution, rules: &ChainRules) -> String {
+    match resolution {
+        VoteResolution::Pending => "投票中です。".to_string(),
+        VoteResolution::Accepted { word, reading, tally, next_user_id, .. } => {
+            let next = match tail_unit(reading, rules) {
+                Some(tail) => format!("「{}」から始まる単語を投稿してください。", tail),
+                None => "単語を投稿してください。".to_string(),
+            };
+            format!(
+                "「{}」が承認されました（{}）。\n次は {} の番です。{}",
+                word_label(word, reading),
+                tally_content(tally),
+                mention(*next_user_id),
+                next
+            )
+        }
+        VoteResolution::GameOver { user_id, word, reading, tally, .. } => format!(
+            "「{}」が承認されました（{}）。\n{} の負けです！ゲームを終了しました。",
+            word_label(word, reading),
+            tally_content(tally),
+            mention(*user_id)
+        ),
+        VoteResolution::Rejected { user_id, word, reading, tally } => format!(
+            "「{}」は否決されました（{}）。\n{} はもう一度単語を投稿してください。",
+            word_label(word, reading),
+            tally_content(tally),
+            mention(*user_id)
+        ),
+    }
+}
+
+/// ---
+/// 制限時間の経過を知らせる本文を作成します。
+/// ---
+pub fn timeout_content(event: &TimeoutEvent, rules: &ChainRules) -> String {
+    match event {
+        TimeoutEvent::VoteResolved { resolution, .. } => {
+            format!("⏰ 投票が締め切られました。\n{}", resolution_content(resolution, rules))
+        }
+        TimeoutEvent::TurnSkipped { user_id, next_user_id, .. } if user_id == next_user_id => format!(
+            "⏰ {} の制限時間が過ぎました。もう一度単語を投稿してください。",
+            mention(*user_id)
+        ),
+        TimeoutEvent::TurnSkipped { user_id, next_user_id, .. } => format!(
+            "⏰ {} の制限時間が過ぎたため手番を飛ばしました。\n次は {} の番です。",
+            mention(*user_id),
+            mention(*next_user_id)
+        ),
+        TimeoutEvent::PlayerEliminated { user_id, next_user_id, .. } => {
+            let next = match next_user_id {
+                Some(next_user_id) => format!("次は {} の番です。", mention(*next_user_id)),
+                None => "参加者がいなくなりました。".to_string(),
+            };
+            format!(
+                "⏰ {} は制限時間内に単語を投稿しなかったため脱落しました。\n{}",
+                mention(*user_id),
+                next
+            )
+        }
+    }
+}
+
+fn tally_content(tally: &VoteTally) -> String {
+    format!("👍 {}　👎 {}", tally.good, tally.bad)
+}
+
+/// ルームの接続判定ルールを返します（設定を読み込めない場合は既定のルール）
+pub async fn room_chain_rules(ctx: &BotContext, room_id: u64) -> ChainRules {
+    match ctx.repo.get_room_config(room_id).await {
+        Ok(config) => config.chain_rules,
+        Err(e) => {
+            eprintln!("Failed to load room config: {:?}", e);
+            ChainRules::default()
+        }
+    }
+}
+
+/// 接続エラー時に、次に始めるべき文字を含めたメッセージを返します
+async fn chain_mismatch_message(ctx: &BotContext, room_id: u64) -> String {
+    let rules = room_chain_rules(ctx, room_id).await;
+    let tail = match ctx.repo.get_last_word(room_id).await {
+        Ok(Some(last)) => tail_unit(&last.reading, &rules),
+        _ => None,
+    };
+
+    match tail {
+        Some(tail) => format!("前の単語から続いていません。「{}」から始まる単語を出してください。", tail),
+        None => describe_error(&RepoError::ChainMismatch),
+    }
+}
+
+/// 文字数・文字の種類の制限に違反したときに、ルームの設定を含めたメッセージを返します
+async fn restriction_message(ctx: &BotContext, room_id: u64, e: &RepoError) -> String {
+    let Ok(config) = ctx.repo.get_room_config(room_id).await else {
+        return describe_error(e);
+    };
+    match e {
+        RepoError::WordTooShort => format!("{}文字以上の単語を出してください。", config.min_word_length),
+        RepoError::ScriptNotAllowed => format!("使える文字は{}です。", config.allowed_scripts.label()),
+        _ => describe_error(e),
+    }
+}
+
+/// ---
+/// RepoErrorをユーザー向けのメッセージに変換します。
+/// 内部エラーの詳細はログにのみ出力します。
+/// ---
+pub fn describe_error(e: &RepoError) -> String {
+    match e {
+        RepoError::Database(_) | RepoError::JoinError(_) | RepoError::Other(_) => {
+            eprintln!("Internal error: {:?}", e);
+            "内部エラーが発生しました。しばらくしてから再度お試しください。".to_string()
+        }
+        _ => e.to_string(),
+    }
+}
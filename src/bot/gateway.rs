@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use serenity::{
+    all::{
+        ButtonStyle, ChannelId, CreateActionRow, CreateAttachment, CreateButton, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateMessage, EditMessage, Http, MessageId,
+    },
+    async_trait,
+};
+
+use crate::bot::{commands::CommandReply, game::VoteChoice};
+
+/// ---
+/// 投稿・編集するメッセージの内容
+/// Noneの項目は編集時に変更しません。
+/// ---
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutboundMessage {
+    pub content: Option<String>,
+    /// 投票ボタンを付ける場合は、ボタンを押せるかどうか
+    pub vote_buttons: Option<bool>,
+}
+
+impl OutboundMessage {
+    pub fn text(content: impl Into<String>) -> Self {
+        Self { content: Some(content.into()), vote_buttons: None }
+    }
+
+    pub fn vote(content: impl Into<String>, enabled: bool) -> Self {
+        Self { content: Some(content.into()), vote_buttons: Some(enabled) }
+    }
+
+    /// 本文はそのままで、投票ボタンを押せないようにします
+    pub fn close_vote() -> Self {
+        Self { content: None, vote_buttons: Some(false) }
+    }
+}
+
+/// ---
+/// スラッシュコマンド・ボタン操作への応答
+/// ---
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InteractionReply {
+    /// 新しいメッセージで応答する
+    Message(CommandReply),
+    /// 操作されたメッセージを更新する
+    UpdateMessage(OutboundMessage),
+}
+
+/// ---
+/// Botからチャンネルへの送信
+/// Discord以外（テスト用の疑似Gatewayなど）に差し替えられるようにしています。
+/// ---
+#[async_trait]
+pub trait Outbound: Send + Sync {
+    /// チャンネルにメッセージを投稿し、投稿したメッセージのIDを返します
+    async fn send_message(&self, channel_id: u64, message: OutboundMessage) -> Result<u64>;
+
+    /// メッセージに返信し、返信のメッセージIDを返します
+    async fn reply(&self, channel_id: u64, message_id: u64, content: String) -> Result<u64>;
+
+    /// 投稿済みのメッセージを編集します
+    async fn edit_message(&self, channel_id: u64, message_id: u64, message: OutboundMessage) -> Result<()>;
+}
+
+/// ---
+/// serenityのHTTPクライアントによる送信
+/// ---
+pub struct SerenityOutbound {
+    http: Arc<Http>,
+}
+
+impl SerenityOutbound {
+    pub fn new(http: Arc<Http>) -> Self {
+        Self { http }
+    }
+}
+
+#[async_trait]
+impl Outbound for SerenityOutbound {
+    async fn send_message(&self, channel_id: u64, message: OutboundMessage) -> Result<u64> {
+        let mut create = CreateMessage::new();
+        if let Some(content) = message.content {
+            create = create.content(content);
+        }
+        if let Some(enabled) = message.vote_buttons {
+            create = create.components(vote_buttons(enabled));
+        }
+        let sent = ChannelId::new(channel_id).send_message(&self.http, create).await?;
+        Ok(sent.id.get())
+    }
+
+    async fn reply(&self, channel_id: u64, message_id: u64, content: String) -> Result<u64> {
+        let create = CreateMessage::new()
+            .content(content)
+            .reference_message((ChannelId::new(channel_id), MessageId::new(message_id)));
+        let sent = ChannelId::new(channel_id).send_message(&self.http, create).await?;
+        Ok(sent.id.get())
+    }
+
+    async fn edit_message(&self, channel_id: u64, message_id: u64, message: OutboundMessage) -> Result<()> {
+        let mut edit = EditMessage::new();
+        if let Some(content) = message.content {
+            edit = edit.content(content);
+        }
+        if let Some(enabled) = message.vote_buttons {
+            edit = edit.components(vote_buttons(enabled));
+        }
+        ChannelId::new(channel_id)
+            .edit_message(&self.http, MessageId::new(message_id), edit)
+            .await?;
+        Ok(())
+    }
+}
+
+/// 応答をserenityの型に変換します
+pub fn to_interaction_response(reply: InteractionReply) -> CreateInteractionResponse {
+    match reply {
+        InteractionReply::Message(reply) => {
+            let mut message = CreateInteractionResponseMessage::new()
+                .content(reply.content)
+                .ephemeral(reply.ephemeral);
+            if let Some(attachment) = reply.attachment {
+                message = message.add_file(CreateAttachment::bytes(attachment.data, attachment.name));
+            }
+            CreateInteractionResponse::Message(message)
+        }
+        InteractionReply::UpdateMessage(message) => {
+            let mut update = CreateInteractionResponseMessage::new();
+            if let Some(content) = message.content {
+                update = update.content(content);
+            }
+            if let Some(enabled) = message.vote_buttons {
+                update = update.components(vote_buttons(enabled));
+            }
+            CreateInteractionResponse::UpdateMessage(update)
+        }
+    }
+}
+
+/// 投票ボタンを作成します
+pub fn vote_buttons(enabled: bool) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(VoteChoice::Good.custom_id())
+            .label("Good")
+            .emoji('👍')
+            .style(ButtonStyle::Success)
+            .disabled(!enabled),
+        CreateButton::new(VoteChoice::Bad.custom_id())
+            .label("Bad")
+            .emoji('👎')
+            .style(ButtonStyle::Danger)
+            .disabled(!enabled),
+    ])]
+}
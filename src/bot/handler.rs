@@ -1,16 +1,132 @@
 use std::sync::Arc;
 
-use serenity::{all::{Context, EventHandler, Ready}, async_trait};
-use crate::bot::bot_context::BotContext;
+use serenity::{
+    all::{Command, Context, EventHandler, Interaction, Message, Ready, ResolvedOption, ResolvedValue},
+    async_trait,
+};
+use crate::bot::{
+    bot_context::BotContext,
+    commands::{self, ArgValue},
+    dispatcher::Dispatcher,
+    gateway::{self, SerenityOutbound},
+};
 
+/// ---
+/// serenityのイベントをDispatcherへ渡すアダプタ
+/// ---
 #[allow(dead_code)]
 pub struct Handler {
     pub ctx: Arc<BotContext>,
 }
 
+impl Handler {
+    fn dispatcher(&self, ctx: &Context) -> Dispatcher {
+        Dispatcher::new(self.ctx.clone(), Arc::new(SerenityOutbound::new(ctx.http.clone())))
+    }
+}
+
 #[async_trait]
 impl EventHandler for Handler {
-    async fn ready(&self, _: Context, _ready: Ready) {
+    async fn ready(&self, ctx: Context, _ready: Ready) {
+        self.dispatcher(&ctx).on_ready().await;
+
+        if let Err(e) = Command::set_global_commands(&ctx.http, commands::create_commands()).await {
+            eprintln!("Failed to register application commands: {:?}", e);
+        }
         println!("ready for handle");
     }
-}
\ No newline at end of file
+
+    async fn message(&self, ctx: Context, msg: Message) {
+        if msg.author.bot {
+            return;
+        }
+        self.dispatcher(&ctx)
+            .on_message(msg.channel_id.get(), msg.author.id.get(), msg.id.get(), &msg.content)
+            .await;
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let dispatcher = self.dispatcher(&ctx);
+        match interaction {
+            Interaction::Command(command)
+                if command.data.name == commands::COMMAND_NAME
+                    || commands::STANDALONE_COMMAND_NAMES.contains(&command.data.name.as_str()) =>
+            {
+                let options = command.data.options();
+                let (subcommand, args) = if command.data.name == commands::COMMAND_NAME {
+                    match options.first() {
+                        Some(ResolvedOption { name, value: ResolvedValue::SubCommand(sub_options), .. }) => {
+                            (Some(*name), to_args(sub_options).await)
+                        }
+                        _ => (None, Vec::new()),
+                    }
+                } else {
+                    (Some(command.data.name.as_str()), to_args(&options).await)
+                };
+                let Some(reply) = dispatcher
+                    .on_command(
+                        command.guild_id.map(|id| id.get()),
+                        command.channel_id.get(),
+                        command.user.id.get(),
+                        command
+                            .member
+                            .as_ref()
+                            .and_then(|member| member.permissions)
+                            .is_some_and(|permissions| permissions.manage_guild()),
+                        subcommand,
+                        &args,
+                    )
+                    .await
+                else {
+                    return;
+                };
+                if let Err(e) = command.create_response(&ctx.http, gateway::to_interaction_response(reply)).await {
+                    eprintln!("Failed to respond to command: {:?}", e);
+                }
+            }
+            Interaction::Component(component) => {
+                let Some(reply) = dispatcher
+                    .on_button(
+                        component.channel_id.get(),
+                        component.user.id.get(),
+                        component.message.id.get(),
+                        &component.data.custom_id,
+                    )
+                    .await
+                else {
+                    return;
+                };
+                if let Err(e) = component.create_response(&ctx.http, gateway::to_interaction_response(reply)).await {
+                    eprintln!("Failed to respond to vote: {:?}", e);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// ---
+/// サブコマンドの引数をserenityの型から変換します。
+/// 添付ファイルはここでダウンロードし、ダウンロードできなかったものは引数から除きます。
+/// ---
+async fn to_args(options: &[ResolvedOption<'_>]) -> Vec<(String, ArgValue)> {
+    let mut args = Vec::new();
+    for option in options {
+        let value = match option.value {
+            ResolvedValue::Integer(i) => ArgValue::Integer(i),
+            ResolvedValue::String(s) => ArgValue::String(s.to_string()),
+            ResolvedValue::Boolean(b) => ArgValue::Boolean(b),
+            ResolvedValue::User(user, _) => ArgValue::User(user.id.get()),
+            ResolvedValue::Attachment(attachment) => match attachment.download().await {
+                Ok(data) => ArgValue::Attachment { name: attachment.filename.clone(), data },
+                Err(e) => {
+                    eprintln!("Failed to download attachment {}: {:?}", attachment.filename, e);
+                    continue;
+                }
+            },
+            _ => continue,
+        };
+        args.push((option.name.to_string(), value));
+    }
+    args
+}
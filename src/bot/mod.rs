@@ -1,4 +1,13 @@
 pub mod shiritori_bot;
 pub mod bot_context;
 pub mod handler;
-pub mod config;
\ No newline at end of file
+pub mod config;
+pub mod game;
+pub mod commands;
+pub mod scheduler;
+pub mod backup;
+pub mod shutdown;
+pub mod gateway;
+pub mod dispatcher;
+#[cfg(test)]
+pub mod fake_gateway;
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+
+use crate::{
+    bot::{
+        bot_context::BotContext,
+        dispatcher, game,
+        gateway::{Outbound, OutboundMessage},
+    },
+    database::repository::TimeoutEvent,
+};
+
+/// ---
+/// 投票・手番の制限時間を定期的に処理するバックグラウンドタスクを起動します。
+/// 処理した結果はルームのチャンネルに通知します。終了処理が始まると停止します。
+/// ---
+pub fn spawn(ctx: Arc<BotContext>, outbound: Arc<dyn Outbound>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(ctx.config.scheduler_interval());
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let Some(_guard) = ctx.gate.enter() else {
+                break;
+            };
+            run_once(&ctx, outbound.as_ref()).await;
+        }
+    })
+}
+
+/// 制限時間を過ぎた投票・手番を1回処理します
+pub async fn run_once(ctx: &BotContext, outbound: &dyn Outbound) {
+    let (events, failures) = match ctx.repo.process_timeouts().await {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Failed to process timeouts: {:?}", e);
+            return;
+        }
+    };
+    // 処理に失敗したルームは変更が取り消されているため、次の周期でやり直す
+    for (room_id, e) in failures {
+        eprintln!("Failed to process timeouts in room {}: {:?}", room_id, e);
+    }
+
+    for event in events {
+        notify(ctx, outbound, &event).await;
+        // 時間切れで手番がコンピューターに移った場合
+        dispatcher::play_computer_turn(ctx, outbound, event_room_id(&event)).await;
+    }
+}
+
+fn event_room_id(event: &TimeoutEvent) -> u64 {
+    match event {
+        TimeoutEvent::VoteResolved { room_id, .. }
+        | TimeoutEvent::TurnSkipped { room_id, .. }
+        | TimeoutEvent::PlayerEliminated { room_id, .. } => *room_id,
+    }
+}
+
+async fn notify(ctx: &BotContext, outbound: &dyn Outbound, event: &TimeoutEvent) {
+    let room_id = event_room_id(event);
+    // ルームIDはチャンネルIDと同じ
+    let channel_id = room_id;
+
+    // 確定した投票のボタンを押せないようにする
+    if let TimeoutEvent::VoteResolved { message_id: Some(message_id), .. } = event
+        && let Err(e) = outbound.edit_message(channel_id, *message_id, OutboundMessage::close_vote()).await
+    {
+        eprintln!("Failed to close vote message: {:?}", e);
+    }
+
+    let content = game::timeout_content(event, &game::room_chain_rules(ctx, room_id).await);
+    if let Err(e) = outbound.send_message(channel_id, OutboundMessage::text(content)).await {
+        eprintln!("Failed to post timeout notice: {:?}", e);
+    }
+}
@@ -1,46 +1,133 @@
-use std::{fs::{self}, sync::Arc};
+use std::{sync::Arc, time::Duration};
 
-use anyhow::Result;
-use serenity::{Client};
+use anyhow::{anyhow, Result};
+use serenity::{all::ShardManager, Client};
+use tokio::task::JoinHandle;
 
-use crate::{bot::{bot_context::BotContext, config::BotConfig, handler::Handler}, database::{db::DataBase, repository::Repository}};
+use crate::{bot::{backup, bot_context::BotContext, config::BotConfig, gateway::{Outbound, SerenityOutbound}, handler::Handler, scheduler, shutdown::ShutdownGate}, database::{db::DataBase, migration::MigrationMode, repository::Repository}};
+
+/// 処理中のイベントの完了を待つ最大時間
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
 
 #[allow(dead_code)]
 pub struct Bot {
-    client: Arc<Client>,
-    ctx: Arc<BotContext>
+    /// start前のクライアント（startでGatewayのタスクへ移ります）
+    client: Option<Client>,
+    ctx: Arc<BotContext>,
+    outbound: Arc<dyn Outbound>,
+    shard_manager: Arc<ShardManager>,
+    gateway: Option<JoinHandle<serenity::Result<()>>>,
+    scheduler: Option<JoinHandle<()>>,
+    backup: Option<JoinHandle<()>>,
 }
 
 impl Bot {
     pub async fn new(config: BotConfig) -> Result<Self> {
         let db_path = &config.db_path();
-        let init_sql = &fs::read_to_string(config.init_sql_path())?;
-        let db = DataBase::new(db_path, Some(init_sql)).await?;
+        let db = DataBase::new(db_path).await?;
+        let report = db.migrate(MigrationMode::Apply).await?;
+        if !report.applied.is_empty() {
+            println!("migrated database schema: version {} -> {}", report.from_version, report.to_version);
+        }
         let repository = Repository::new(db)?;
 
         let ctx = BotContext {
             config: Arc::new(config.clone()),
-            repo: Arc::new(repository)
+            repo: Arc::new(repository),
+            gate: Arc::new(ShutdownGate::default()),
         };
         let arc_ctx = Arc::new(ctx);
         
         let handler = Handler { ctx: arc_ctx.clone() };
         
-        let mut clinet = Client::builder(config.token(), config.gateway_intents())
+        let client = Client::builder(config.token(), config.gateway_intents())
             .event_handler(handler)
             .await?;
-        clinet.start().await?;
-        let arc_client = Arc::new(clinet);
 
         Ok(
             Self {
-                ctx: arc_ctx.clone(),
-                client: arc_client.clone()
+                ctx: arc_ctx,
+                outbound: Arc::new(SerenityOutbound::new(client.http.clone())),
+                shard_manager: client.shard_manager.clone(),
+                client: Some(client),
+                gateway: None,
+                scheduler: None,
+                backup: None,
             }
         )
     }
 
-    fn shutdown(&self) {
-        //TODO: 安全な処理を今後実装
+    /// ---
+    /// Discordへの接続と、制限時間を処理する・スナップショットを作成するバックグラウンドタスクを開始します。
+    /// 接続はバックグラウンドで続き、終了はwait_gatewayで待てます。
+    /// ---
+    pub fn start(&mut self) -> Result<()> {
+        let mut client = self.client.take().ok_or_else(|| anyhow!("bot is already started"))?;
+        self.scheduler = Some(scheduler::spawn(self.ctx.clone(), self.outbound.clone()));
+        self.backup = backup::spawn(self.ctx.clone());
+        self.gateway = Some(tokio::spawn(async move { client.start().await }));
+        Ok(())
+    }
+
+    /// ---
+    /// Gatewayへの接続が終了するまで待ちます。
+    /// 開始していない場合は戻りません。
+    /// ---
+    pub async fn wait_gateway(&mut self) -> Result<()> {
+        let Some(gateway) = self.gateway.as_mut() else {
+            return std::future::pending().await;
+        };
+        let result = gateway.await;
+        self.gateway = None;
+        Ok(result??)
     }
-}
\ No newline at end of file
+
+    /// ---
+    /// Botを安全に終了します。
+    /// 1. 新しいイベントの受け付けを止め、処理中のイベント（データベース処理を含む）の完了を待つ
+    /// 2. 制限時間・スナップショットのタスクを止め、期限を過ぎた投票・手番を最後に処理して通知する
+    /// 3. Gatewayの接続を閉じる
+    /// 4. データベースの内容をファイルへ書き戻す
+    /// 
+    /// 途中で失敗しても残りの手順は実行し、最初のエラーを返します。
+    /// ---
+    pub async fn shutdown(&mut self) -> Result<()> {
+        let mut first_error: Option<anyhow::Error> = None;
+
+        if tokio::time::timeout(DRAIN_TIMEOUT, self.ctx.gate.close()).await.is_err() {
+            eprintln!("Timed out waiting for in-flight events");
+            first_error.get_or_insert(anyhow!("timed out waiting for in-flight events"));
+        }
+
+        for task in [self.scheduler.take(), self.backup.take()].into_iter().flatten() {
+            task.abort();
+            let _ = task.await;
+        }
+        scheduler::run_once(&self.ctx, self.outbound.as_ref()).await;
+
+        self.shard_manager.shutdown_all().await;
+        if let Some(gateway) = self.gateway.take() {
+            match gateway.await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    eprintln!("Gateway stopped with error: {:?}", e);
+                    first_error.get_or_insert(e.into());
+                }
+                Err(e) => {
+                    eprintln!("Gateway task failed: {:?}", e);
+                    first_error.get_or_insert(e.into());
+                }
+            }
+        }
+
+        if let Err(e) = self.ctx.repo.checkpoint().await {
+            eprintln!("Failed to checkpoint database: {:?}", e);
+            first_error.get_or_insert(e.into());
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
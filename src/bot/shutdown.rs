@@ -0,0 +1,99 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use tokio::sync::Notify;
+
+/// ---
+/// 終了処理のためのゲート
+/// 終了処理が始まると新しいイベントを受け付けなくなり、処理中のイベントがすべて終わるまで待てます。
+/// ---
+#[derive(Debug, Default)]
+pub struct ShutdownGate {
+    closed: AtomicBool,
+    active: AtomicUsize,
+    idle: Notify,
+}
+
+/// 処理中のイベントを表すガード
+/// dropされるとイベントの処理が終わったとみなします。
+#[derive(Debug)]
+pub struct ActiveGuard<'a> {
+    gate: &'a ShutdownGate,
+}
+
+impl ShutdownGate {
+    /// ---
+    /// イベントの処理を開始します。
+    /// 終了処理中の場合はNoneを返すため、イベントを処理せずに戻ってください。
+    /// ---
+    pub fn enter(&self) -> Option<ActiveGuard<'_>> {
+        // 先に数えてから確認することで、closeとの間で取りこぼしが起きないようにする
+        self.active.fetch_add(1, Ordering::SeqCst);
+        if self.closed.load(Ordering::SeqCst) {
+            self.leave();
+            return None;
+        }
+        Some(ActiveGuard { gate: self })
+    }
+
+    /// ---
+    /// 新しいイベントの受け付けを止め、処理中のイベントがすべて終わるまで待ちます。
+    /// ---
+    pub async fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        loop {
+            let mut notified = std::pin::pin!(self.idle.notified());
+            notified.as_mut().enable();
+            if self.active.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    fn leave(&self) {
+        if self.active.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.idle.notify_waiters();
+        }
+    }
+}
+
+impl Drop for ActiveGuard<'_> {
+    fn drop(&mut self) {
+        self.gate.leave();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_close_waits_for_active() {
+        let gate = Arc::new(ShutdownGate::default());
+        let guard = gate.enter().expect("終了処理前にイベントが拒否されました。");
+
+        let closing = tokio::spawn({
+            let gate = gate.clone();
+            async move { gate.close().await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(gate.enter().is_none(), "終了処理中に新しいイベントが受け付けられました。");
+        assert!(!closing.is_finished(), "処理中のイベントを待たずにcloseが完了しました。");
+
+        drop(guard);
+        tokio::time::timeout(Duration::from_secs(1), closing)
+            .await
+            .expect("処理中のイベントの完了後もcloseが完了しませんでした。")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_close_without_active() {
+        let gate = ShutdownGate::default();
+        tokio::time::timeout(Duration::from_secs(1), gate.close())
+            .await
+            .expect("処理中のイベントがないのにcloseが完了しませんでした。");
+    }
+}
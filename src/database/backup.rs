@@ -0,0 +1,310 @@
+// src/database/backup.rs
+// SQLiteのオンラインバックアップによるスナップショットの作成・世代管理・復元
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{NaiveDateTime, Utc};
+use rusqlite::{
+    backup::{Backup, StepResult},
+    ffi, Connection, OpenFlags,
+};
+
+use crate::database::{
+    db::{DataBase, DatabaseError, Result, MAX_BUSY_RETRIES},
+    migration::latest_version,
+};
+
+/// スナップショットのファイル名の接頭辞・接尾辞（shiritori-YYYYMMDD-HHMMSS.db）
+const SNAPSHOT_PREFIX: &str = "shiritori-";
+const SNAPSHOT_SUFFIX: &str = ".db";
+const SNAPSHOT_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// 残しておくスナップショットの既定の数
+pub const DEFAULT_RETENTION: usize = 7;
+
+/// ---
+/// 定期的なスナップショットの設定
+/// ---
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupPolicy {
+    /// スナップショットを置くディレクトリ
+    pub dir: PathBuf,
+    /// スナップショットを作成する間隔（Noneの場合は/backupなどで手動で作成します）
+    pub interval: Option<Duration>,
+    /// 残しておくスナップショットの数（古いものから削除します）
+    pub retention: usize,
+}
+
+/// ---
+/// 作成済みのスナップショット
+/// ---
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    /// 作成日時（UTC、ファイル名から読み取ります）
+    pub created_at: NaiveDateTime,
+    /// ファイルのバイト数
+    pub size: u64,
+}
+
+impl DataBase {
+    /// ---
+    /// SQLiteのオンラインバックアップで、データベースの内容を別のファイルに一貫した状態で書き出します。
+    /// 読み取り用の接続で行うため、書き込みを止めません。
+    /// 一時ファイルに書き出してから置き換えるため、書き出し先が途中の状態になることはありません。
+    /// ---
+    pub async fn backup_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+
+        self.with_reader(move |conn| {
+            let mut temp = path.clone().into_os_string();
+            temp.push(".tmp");
+            let temp = PathBuf::from(temp);
+
+            let result = (|| -> Result<()> {
+                let mut dest = Connection::open(&temp)?;
+                copy_database(conn, &mut dest)?;
+                dest.close().map_err(|(_, e)| e)?;
+                std::fs::rename(&temp, &path)?;
+                Ok(())
+            })();
+            if result.is_err() {
+                let _ = std::fs::remove_file(&temp);
+            }
+            result
+        })
+        .await
+    }
+
+    /// ---
+    /// dirにスナップショットを作成し、retentionより古い世代を削除します。
+    /// retentionが0の場合は削除しません。
+    /// ---
+    pub async fn take_snapshot(&self, dir: &Path, retention: usize) -> Result<Snapshot> {
+        std::fs::create_dir_all(dir)?;
+        let created_at = Utc::now().naive_utc();
+        let path = dir.join(snapshot_file_name(created_at));
+        self.backup_to(&path).await?;
+
+        if retention > 0 {
+            prune_snapshots(dir, retention)?;
+        }
+        Ok(Snapshot {
+            size: std::fs::metadata(&path)?.len(),
+            // ファイル名の精度（秒）にそろえる
+            created_at: parse_snapshot_name(&snapshot_file_name(created_at)).unwrap_or(created_at),
+            path,
+        })
+    }
+}
+
+/// ---
+/// fromの全ページをtoへ写します。
+/// 1回のステップで全ページを写すため、途中で他の接続から書き込まれても一貫した内容になります。
+/// ---
+fn copy_database(from: &Connection, to: &mut Connection) -> Result<()> {
+    let backup = Backup::new(from, to)?;
+    let mut retries = 0;
+    loop {
+        match backup.step(-1)? {
+            StepResult::Done => return Ok(()),
+            StepResult::More => {}
+            // ロックが取れない場合は少し待ってやり直す
+            _ if retries < MAX_BUSY_RETRIES => {
+                retries += 1;
+                std::thread::sleep(Duration::from_millis(20) * retries);
+            }
+            _ => {
+                return Err(rusqlite::Error::SqliteFailure(ffi::Error::new(ffi::SQLITE_BUSY), None).into());
+            }
+        }
+    }
+}
+
+fn snapshot_file_name(created_at: NaiveDateTime) -> String {
+    format!("{}{}{}", SNAPSHOT_PREFIX, created_at.format(SNAPSHOT_TIME_FORMAT), SNAPSHOT_SUFFIX)
+}
+
+/// スナップショットのファイル名から作成日時を読み取ります（スナップショットでなければNone）
+fn parse_snapshot_name(name: &str) -> Option<NaiveDateTime> {
+    let time = name.strip_prefix(SNAPSHOT_PREFIX)?.strip_suffix(SNAPSHOT_SUFFIX)?;
+    NaiveDateTime::parse_from_str(time, SNAPSHOT_TIME_FORMAT).ok()
+}
+
+/// ---
+/// dirにあるスナップショットを新しい順に返します。
+/// ディレクトリがない場合は空を返します。
+/// ---
+pub fn list_snapshots(dir: &Path) -> Result<Vec<Snapshot>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut snapshots = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let Some(created_at) = entry.file_name().to_str().and_then(parse_snapshot_name) else {
+            continue;
+        };
+        snapshots.push(Snapshot {
+            path: entry.path(),
+            created_at,
+            size: entry.metadata()?.len(),
+        });
+    }
+    snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.created_at));
+    Ok(snapshots)
+}
+
+/// ---
+/// 新しいものからretention個を残して、古いスナップショットを削除します。
+/// 削除したファイルのパスを返します。
+/// ---
+pub fn prune_snapshots(dir: &Path, retention: usize) -> Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    for snapshot in list_snapshots(dir)?.into_iter().skip(retention) {
+        std::fs::remove_file(&snapshot.path)?;
+        removed.push(snapshot.path);
+    }
+    Ok(removed)
+}
+
+/// ---
+/// スナップショットが復元できるかを確かめ、スキーマのversionを返します。
+/// 壊れている場合、しりとりのデータベースでない場合、このバイナリより新しいスキーマの場合はエラーを返します。
+/// ---
+pub fn validate_snapshot(path: &Path) -> Result<u32> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let check: String = conn.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+    if check != "ok" {
+        return Err(DatabaseError::InvalidSnapshot(format!("{}: {}", path.display(), check)));
+    }
+
+    let version: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version == 0 {
+        return Err(DatabaseError::InvalidSnapshot(format!(
+            "{}: スキーマのversionが記録されていません",
+            path.display()
+        )));
+    }
+    let supported = latest_version();
+    if version > supported {
+        return Err(DatabaseError::SchemaTooNew { database: version, supported });
+    }
+    Ok(version)
+}
+
+/// ---
+/// スナップショットを検証してから、db_pathのデータベースの内容を置き換え、スキーマのversionを返します。
+/// 置き換えもオンラインバックアップで行うため、WALのファイルも整合した状態になります。
+/// Botを止めてから実行してください（古いスキーマの場合は次の起動時に移行されます）。
+/// ---
+pub fn restore_snapshot(snapshot: &Path, db_path: &Path) -> Result<u32> {
+    let version = validate_snapshot(snapshot)?;
+
+    let source = Connection::open_with_flags(snapshot, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut dest = Connection::open(db_path)?;
+    copy_database(&source, &mut dest)?;
+    dest.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{db::QueryExecutor, migration::MigrationMode};
+
+    /// テスト用のディレクトリ（終了時に削除します）
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("shiritori-backup-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    async fn count_rooms(db: &DataBase) -> Result<Vec<i64>> {
+        db.query("SELECT count(*) FROM rooms", [], |row| row.get(0)).await
+    }
+
+    #[test]
+    fn test_snapshot_name() {
+        let created_at = NaiveDateTime::parse_from_str("2026-10-16 09:30:15", "%Y-%m-%d %H:%M:%S").unwrap();
+        let name = snapshot_file_name(created_at);
+        assert_eq!(name, "shiritori-20261016-093015.db");
+        assert_eq!(parse_snapshot_name(&name), Some(created_at));
+        assert_eq!(parse_snapshot_name("notes.txt"), None, "スナップショットでないファイルが読み取られました。");
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_and_restore() -> anyhow::Result<()> {
+        let dir = TempDir::new("restore");
+        let db_path = dir.0.join("game.db");
+        let db = DataBase::new(db_path.to_str().unwrap()).await?;
+        db.migrate(MigrationMode::Apply).await?;
+        db.execute("INSERT INTO rooms (id) VALUES (1)", []).await?;
+
+        let snapshots = dir.0.join("snapshots");
+        let snapshot = db.take_snapshot(&snapshots, 3).await?;
+        assert_eq!(list_snapshots(&snapshots)?, vec![snapshot.clone()]);
+        assert_eq!(validate_snapshot(&snapshot.path)?, latest_version());
+
+        db.execute("INSERT INTO rooms (id) VALUES (2)", []).await?;
+        drop(db);
+        restore_snapshot(&snapshot.path, &db_path)?;
+        let db = DataBase::new(db_path.to_str().unwrap()).await?;
+        assert_eq!(count_rooms(&db).await?, vec![1], "スナップショットの内容に戻っていません。");
+
+        // 新しいスキーマのスナップショットは復元しない
+        let conn = Connection::open(&snapshot.path)?;
+        conn.pragma_update(None, "user_version", latest_version() + 1)?;
+        drop(conn);
+        assert!(
+            matches!(restore_snapshot(&snapshot.path, &db_path), Err(DatabaseError::SchemaTooNew { .. })),
+            "新しいスキーマのスナップショットが復元されました。"
+        );
+        assert_eq!(count_rooms(&db).await?, vec![1]);
+
+        // しりとりのデータベースでないファイルも復元しない
+        let empty = dir.0.join("empty.db");
+        Connection::open(&empty)?.execute_batch("CREATE TABLE other (id INTEGER);")?;
+        assert!(matches!(validate_snapshot(&empty), Err(DatabaseError::InvalidSnapshot(_))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_prune_snapshots() -> anyhow::Result<()> {
+        let dir = TempDir::new("prune");
+        for name in [
+            "shiritori-20261001-000000.db",
+            "shiritori-20261003-000000.db",
+            "shiritori-20261002-000000.db",
+            "notes.txt",
+        ] {
+            std::fs::write(dir.0.join(name), b"")?;
+        }
+
+        let removed = prune_snapshots(&dir.0, 2)?;
+        assert_eq!(removed, vec![dir.0.join("shiritori-20261001-000000.db")], "古い世代から削除されていません。");
+        let names: Vec<PathBuf> = list_snapshots(&dir.0)?.into_iter().map(|snapshot| snapshot.path).collect();
+        assert_eq!(
+            names,
+            vec![dir.0.join("shiritori-20261003-000000.db"), dir.0.join("shiritori-20261002-000000.db")]
+        );
+        assert!(dir.0.join("notes.txt").exists(), "スナップショットでないファイルが削除されました。");
+        Ok(())
+    }
+}
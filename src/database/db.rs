@@ -1,8 +1,24 @@
-use rusqlite::{Connection, Params};
-use std::sync::Arc;
-use std::{fs::File, io::Write, path::Path};
+use rusqlite::{
+    functions::FunctionFlags, types::ValueRef, CachedStatement, Connection, OpenFlags, Params, Statement, TransactionBehavior,
+};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar};
+use std::time::Duration;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
 use thiserror::Error;
-use tokio::{sync::Mutex, task::JoinError};
+use tokio::{
+    sync::Mutex,
+    task::JoinError,
+};
+
+use crate::rules::{canonical::canonical_key, script::ScriptSet};
 
 #[derive(Debug, Error)]
 pub enum DatabaseError {
@@ -10,18 +26,232 @@ pub enum DatabaseError {
     Sqlite(#[from] rusqlite::Error),
     #[error("非同期タスクエラー: {0}")]
     Join(#[from] JoinError),
+    #[error("データベースのスキーマ(version {database})がこのバイナリ(version {supported})より新しいため起動できません")]
+    SchemaTooNew { database: u32, supported: u32 },
+    #[error("ファイル操作エラー: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("復元できないスナップショットです: {0}")]
+    InvalidSnapshot(String),
 }
 
 pub type Result<T, E = DatabaseError> = std::result::Result<T, E>;
 
+/// 読み取り専用の接続の既定の数
+pub const DEFAULT_READERS: usize = 4;
+/// 他の接続のロックの解放を待つ既定の時間
+pub const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+/// 1つの接続でキャッシュしておくステートメントの既定の数
+pub const DEFAULT_STATEMENT_CACHE_CAPACITY: usize = 32;
+
+/// ---
+/// データベース接続の設定
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataBaseOptions {
+    /// 読み取り専用の接続の数（0の場合は書き込み用の接続で読みます）
+    pub readers: usize,
+    /// 他の接続のロックの解放を待つ時間
+    pub busy_timeout: Duration,
+    /// 1つの接続でキャッシュしておくステートメントの数（0の場合はキャッシュしません）
+    pub statement_cache_capacity: usize,
+}
+
+impl Default for DataBaseOptions {
+    fn default() -> Self {
+        Self {
+            readers: DEFAULT_READERS,
+            busy_timeout: DEFAULT_BUSY_TIMEOUT,
+            statement_cache_capacity: DEFAULT_STATEMENT_CACHE_CAPACITY,
+        }
+    }
+}
+
+/// ---
+/// ステートメントキャッシュの利用状況（全接続の合計）
+/// rusqliteのキャッシュと同じ規則で数えます。使用中のステートメントと同じSQLを取り出した場合は、
+/// キャッシュに戻っていないためミスになります。
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StatementCacheStats {
+    /// キャッシュ済みのステートメントを使った回数
+    pub hits: u64,
+    /// SQLを解析してステートメントを作った回数
+    pub misses: u64,
+}
+
+/// ---
+/// 書き込み用の接続1つと、読み取り専用の接続のプールを持つデータベース
+/// 書き込み（execute・トランザクション）は書き込み用の接続で順番に実行され、
+/// 読み取り（query・TransactionMode::Readのトランザクション）は空いている読み取り用の接続で並行して実行されます。
+/// WALモードのため、読み取りは書き込み中のトランザクションを待たずに、コミット済みの内容を読みます。
+/// ---
 pub struct DataBase {
-    conn: Arc<Mutex<Connection>>,
+    writer: Arc<Mutex<Handle>>,
+    /// インメモリのデータベースは接続ごとに別のデータベースになるため、Noneにして書き込み用の接続で読みます
+    readers: Option<Arc<ReaderPool>>,
+    counters: Arc<CacheCounters>,
+}
+
+#[derive(Default)]
+struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// ---
+/// 接続と、その接続のステートメントキャッシュに入っているSQLの記録
+/// rusqliteのキャッシュは中身を公開しないため、取り出し・戻しのたびに同じLRUの規則でSQLを記録してヒットを数えます。
+/// ---
+pub(super) struct Handle {
+    conn: Connection,
+    statements: StatementTracker,
+}
+
+impl Deref for Handle {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+impl DerefMut for Handle {
+    fn deref_mut(&mut self) -> &mut Connection {
+        &mut self.conn
+    }
+}
+
+struct StatementTracker {
+    /// キャッシュに入っているSQL（最後が最近使ったもの。使用中のステートメントは含みません）
+    recent: RefCell<VecDeque<String>>,
+    capacity: usize,
+    counters: Arc<CacheCounters>,
+}
+
+impl StatementTracker {
+    /// ---
+    /// キャッシュからステートメントを取り出し（なければ作り）、ヒット・ミスを数えます。
+    /// rusqliteと同じく、SQLの前後の空白を除いたものをキーにし、取り出したステートメントは
+    /// 使い終わるまでキャッシュから外します（同じSQLを同時に使うと、2つ目は新しく作られます）。
+    /// ---
+    fn prepare<'conn>(&'conn self, conn: &'conn Connection, sql: &str) -> rusqlite::Result<TrackedStatement<'conn>> {
+        let key = sql.trim();
+        let mut recent = self.recent.borrow_mut();
+        match recent.iter().position(|cached| cached == key) {
+            Some(i) => {
+                recent.remove(i);
+                self.counters.hits.fetch_add(1, Ordering::Relaxed);
+            }
+            None => {
+                self.counters.misses.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        drop(recent);
+        // 解析に失敗したステートメントはキャッシュされない
+        let stmt = conn.prepare_cached(sql)?;
+        Ok(TrackedStatement {
+            stmt,
+            key: key.to_string(),
+            tracker: self,
+        })
+    }
+
+    /// 使い終わったステートメントを最近使ったものとしてキャッシュに戻し、容量を超えた古いものを追い出します
+    fn release(&self, key: String) {
+        let mut recent = self.recent.borrow_mut();
+        if let Some(i) = recent.iter().position(|cached| *cached == key) {
+            recent.remove(i);
+        }
+        recent.push_back(key);
+        while recent.len() > self.capacity {
+            recent.pop_front();
+        }
+    }
+}
+
+/// ---
+/// キャッシュから取り出したステートメント
+/// dropするとrusqliteのキャッシュに戻り、同時にヒットを数えるための記録にも戻します。
+/// ---
+pub struct TrackedStatement<'conn> {
+    stmt: CachedStatement<'conn>,
+    key: String,
+    tracker: &'conn StatementTracker,
+}
+
+impl<'conn> Deref for TrackedStatement<'conn> {
+    type Target = Statement<'conn>;
+
+    fn deref(&self) -> &Statement<'conn> {
+        &self.stmt
+    }
+}
+
+impl<'conn> DerefMut for TrackedStatement<'conn> {
+    fn deref_mut(&mut self) -> &mut Statement<'conn> {
+        &mut self.stmt
+    }
 }
 
-// Connection / Transaction 共通API定義
+impl Drop for TrackedStatement<'_> {
+    fn drop(&mut self) {
+        // この後にstmtがdropされ、rusqliteのキャッシュにも同じ順番で戻る
+        self.tracker.release(std::mem::take(&mut self.key));
+    }
+}
+
+/// ---
+/// 読み取り専用の接続のプール
+/// 書き込み用の接続と同じく、spawn_blockingのスレッドの中で接続が空くまで待ちます
+/// （非同期に待ってからスレッドに渡すと、1回の読み取りでタスクの起床が1回増えるため）。
+/// ---
+struct ReaderPool {
+    /// 空いている接続（最後に返した接続から使い、ページキャッシュが残っている接続を優先します）
+    idle: std::sync::Mutex<Vec<Handle>>,
+    /// 接続が返されたことを待っているスレッドに知らせます
+    returned: Condvar,
+    size: usize,
+}
+
+/// プールから借りた接続（処理が終わる・パニックするとプールに戻ります）
+struct PooledConnection<'pool> {
+    conn: Option<Handle>,
+    pool: &'pool ReaderPool,
+}
+
+impl PooledConnection<'_> {
+    fn conn(&mut self) -> &mut Handle {
+        self.conn.as_mut().expect("接続はdropまで保持されます")
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.idle.lock().unwrap_or_else(|e| e.into_inner()).push(conn);
+            self.pool.returned.notify_one();
+        }
+    }
+}
+
+impl ReaderPool {
+    /// 接続が空くまでスレッドをブロックして待ち、借ります
+    fn acquire_blocking(&self) -> PooledConnection<'_> {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if let Some(conn) = idle.pop() {
+                return PooledConnection { conn: Some(conn), pool: self };
+            }
+            idle = self.returned.wait(idle).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+// 非同期のクエリAPI定義（トランザクションの中では QueryTransaction の同期メソッドを使います）
+// 返すFutureはSendなので、tokio::spawnするタスクの中でも使えます
 pub trait QueryExecutor {
-    async fn execute(&self, sql: &str, params: impl Send + Params + 'static) -> Result<usize>;
-    async fn query<T, F, P>(&self, sql: &str, params: P, f: F) -> Result<Vec<T>>
+    fn execute(&self, sql: &str, params: impl Send + Params + 'static) -> impl Future<Output = Result<usize>> + Send;
+    fn query<T, F, P>(&self, sql: &str, params: P, f: F) -> impl Future<Output = Result<Vec<T>>> + Send
     where
         P: Send + Params + 'static,
         F: FnMut(&rusqlite::Row<'_>) -> rusqlite::Result<T> + Send + 'static,
@@ -30,16 +260,12 @@ pub trait QueryExecutor {
 
 impl QueryExecutor for DataBase {
     async fn execute(&self, sql: &str, params: impl Send + Params + 'static) -> Result<usize> {
-        tokio::task::spawn_blocking({
-            let conn = self.conn.clone();
-            let sql = sql.to_string();
-            move || {
-                let conn = conn.blocking_lock();
-                let count = conn.execute(&sql, params)?;
-                Ok(count)
-            }
+        let sql = sql.to_string();
+        self.with_writer(move |conn| {
+            let count = conn.statements.prepare(&conn.conn, &sql)?.execute(params)?;
+            Ok(count)
         })
-        .await?
+        .await
     }
 
     async fn query<T, F, P>(&self, sql: &str, params: P, f: F) -> Result<Vec<T>>
@@ -48,12 +274,10 @@ impl QueryExecutor for DataBase {
         F: FnMut(&rusqlite::Row<'_>) -> rusqlite::Result<T> + Send + 'static,
         T: Send + 'static,
     {
-        let conn = self.conn.clone();
         let sql = sql.to_string();
 
-        tokio::task::spawn_blocking(move || {
-            let conn = conn.blocking_lock();
-            let mut stmt = conn.prepare(&sql)?;
+        self.with_reader(move |conn| {
+            let mut stmt = conn.statements.prepare(&conn.conn, &sql)?;
             let mut f = f;
             let rows = stmt.query_map(params, |row| f(row))?;
             let mut results = Vec::new();
@@ -62,60 +286,160 @@ impl QueryExecutor for DataBase {
             }
             Ok(results)
         })
-        .await?
+        .await
     }
 }
 
-impl<'a> QueryExecutor for QueryTransaction<'a> {
-    async fn execute(&self, sql: &str, params: impl Send + Params + 'static) -> Result<usize> {
-        let sql = sql.to_string();
-        let count = self.tx.execute(&sql, params)?;
-        Ok(count)
-    }
-
-    async fn query<T, F, P>(&self, sql: &str, params: P, f: F) -> Result<Vec<T>>
-    where
-        P: Send + Params + 'static,
-        F: FnMut(&rusqlite::Row<'_>) -> rusqlite::Result<T> + Send + 'static,
-        T: Send + 'static,
-    {
-        let sql = sql.to_string();
-        let mut f = f;
-
-        let mut stmt = self.tx.prepare(&sql)?;
-        let rows = stmt.query_map(params, |row| f(row))?;
-        let mut results = Vec::new();
-        for row in rows {
-            results.push(row?);
-        }
+/// ---
+/// SQLから使う関数を接続に登録します。
+/// canonical_key(word): 既出判定の正規化キー（スキーマ移行で既存の単語のキーを作るのに使います）
+/// allows_scripts(word, scripts): 表記がscripts（ScriptSet::to_dbの形式）の文字だけでできているか
+/// ---
+fn register_functions(conn: &Connection) -> rusqlite::Result<()> {
+    conn.create_scalar_function(
+        "canonical_key",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let word = ctx.get::<Option<String>>(0)?;
+            Ok(word.map(|word| canonical_key(&word)))
+        },
+    )?;
+    conn.create_scalar_function(
+        "allows_scripts",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let word = ctx.get::<String>(0)?;
+            let scripts = ctx.get::<String>(1)?;
+            Ok(ScriptSet::parse(&scripts).is_some_and(|scripts| scripts.allows(&word)))
+        },
+    )
+}
 
-        Ok(results)
-    }
+/// 接続ごとに別のデータベースになるパス（インメモリ・一時ファイル）かどうか
+fn is_memory_path(path: &str) -> bool {
+    path.is_empty() || path == ":memory:" || path.contains("mode=memory")
 }
 
 #[allow(dead_code)]
 impl DataBase {
     /// 新しいデータベース接続を作成します。
-    pub async fn new(path: &str, init_sql: Option<&str>) -> Result<Self> {
+    /// スキーマの作成・更新は migrate で行います。
+    pub async fn new(path: &str) -> Result<Self> {
+        Self::with_options(path, DataBaseOptions::default()).await
+    }
+
+    /// 読み取り専用の接続の数を指定して、データベース接続を作成します。
+    pub async fn with_readers(path: &str, readers: usize) -> Result<Self> {
+        Self::with_options(path, DataBaseOptions { readers, ..Default::default() }).await
+    }
+
+    /// ---
+    /// 設定を指定して、データベース接続を作成します。
+    /// readersが0の場合やインメモリのデータベースでは、読み取りも書き込み用の接続で行います。
+    /// ---
+    pub async fn with_options(path: &str, options: DataBaseOptions) -> Result<Self> {
         let path_owned = path.to_owned();
-        let init_sql_owned = init_sql.map(|s| s.to_owned());
-
-        let conn = tokio::task::spawn_blocking(move || -> Result<Connection> {
-            let db_exists = Path::new(&path_owned).exists();
-            let conn = Connection::open(&path_owned)?;
-            if !db_exists {
-                if let Some(sql) = init_sql_owned.as_deref() {
-                    conn.execute_batch(sql)?;
-                }
-            }
-            conn.pragma_update(None, "foreign_keys", &"ON")?;
-            Ok(conn)
+        let readers = if is_memory_path(path) { 0 } else { options.readers };
+        let busy_timeout = options.busy_timeout;
+        let capacity = options.statement_cache_capacity;
+
+        let (writer, reader_conns) = tokio::task::spawn_blocking(move || -> Result<(Connection, Vec<Connection>)> {
+            let writer = Connection::open(&path_owned)?;
+            writer.pragma_update(None, "foreign_keys", "ON")?;
+            writer.busy_timeout(busy_timeout)?;
+            // インメモリのデータベースでは"memory"のまま変わらない
+            writer.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+            // WALではコミットごとのfsyncを省いても壊れない（電源断で直前のコミットが失われることはある）
+            writer.pragma_update(None, "synchronous", "NORMAL")?;
+            register_functions(&writer)?;
+
+            // 書き込み用の接続でファイルとWALを用意してから開く
+            let reader_conns = (0..readers)
+                .map(|_| -> Result<Connection> {
+                    let conn = Connection::open_with_flags(
+                        &path_owned,
+                        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+                    )?;
+                    conn.busy_timeout(busy_timeout)?;
+                    register_functions(&conn)?;
+                    Ok(conn)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok((writer, reader_conns))
         })
         .await??;
 
+        let counters = Arc::new(CacheCounters::default());
+        let handle = |conn: Connection| {
+            conn.set_prepared_statement_cache_capacity(capacity);
+            Handle {
+                conn,
+                statements: StatementTracker {
+                    recent: RefCell::new(VecDeque::with_capacity(capacity)),
+                    capacity,
+                    counters: counters.clone(),
+                },
+            }
+        };
+        let readers = (!reader_conns.is_empty()).then(|| {
+            Arc::new(ReaderPool {
+                returned: Condvar::new(),
+                size: reader_conns.len(),
+                idle: std::sync::Mutex::new(reader_conns.into_iter().map(handle).collect()),
+            })
+        });
         Ok(Self {
-            conn: Arc::new(Mutex::new(conn)),
+            writer: Arc::new(Mutex::new(handle(writer))),
+            readers,
+            counters,
+        })
+    }
+
+    /// QueryExecutorで使ったステートメントキャッシュのヒット・ミスの回数を返します
+    pub fn statement_cache_stats(&self) -> StatementCacheStats {
+        StatementCacheStats {
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 読み取り専用の接続の数（0の場合は書き込み用の接続で読みます）
+    pub fn reader_count(&self) -> usize {
+        self.readers.as_ref().map_or(0, |pool| pool.size)
+    }
+
+    /// 書き込み用の接続で処理を実行します（他の書き込みとは順番に実行されます）
+    async fn with_writer<F, T, E>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut Handle) -> Result<T, E> + Send + 'static,
+        T: Send + 'static,
+        E: From<JoinError> + Send + 'static,
+    {
+        let conn = self.writer.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.blocking_lock();
+            f(&mut conn)
         })
+        .await?
+    }
+
+    /// 空いている読み取り専用の接続で処理を実行します
+    pub(super) async fn with_reader<F, T, E>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut Handle) -> Result<T, E> + Send + 'static,
+        T: Send + 'static,
+        E: From<JoinError> + Send + 'static,
+    {
+        let Some(pool) = self.readers.clone() else {
+            return self.with_writer(f).await;
+        };
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.acquire_blocking();
+            f(conn.conn())
+        })
+        .await?
     }
 
     /// SELECT文を実行し、1行だけ結果を取得します
@@ -126,16 +450,10 @@ impl DataBase {
         F: FnOnce(&rusqlite::Row<'_>) -> rusqlite::Result<T> + Send + 'static,
         T: Send + 'static,
     {
-        let conn = self.conn.clone();
         let sql = sql.to_string();
 
-        let result = tokio::task::spawn_blocking(move || {
-            let conn = conn.blocking_lock();
-            conn.query_row(&sql, params, f)
-        })
-        .await??;
-
-        Ok(result)
+        self.with_reader(move |conn| Ok(conn.query_row(&sql, params, f)?))
+            .await
     }
 
     /// SELECT文を実行し、複数行の結果をベクタとして返します
@@ -146,11 +464,9 @@ impl DataBase {
         F: FnMut(&rusqlite::Row<'_>) -> rusqlite::Result<T> + Send + 'static,
         T: Send + 'static,
     {
-        let conn = self.conn.clone();
         let sql = sql.to_string();
 
-        tokio::task::spawn_blocking(move || {
-            let conn = conn.blocking_lock();
+        self.with_reader(move |conn| {
             let mut stmt = conn.prepare(&sql)?;
             let rows = stmt.query_map(params, |row| f(row))?;
             let mut results = Vec::new();
@@ -159,159 +475,706 @@ impl DataBase {
             }
             Ok(results)
         })
-        .await?
-    }
-
-    /// 一連の処理をトランザクションとして安全に実行します。
-    /// atomic に実行され、他スレッドからブロックされます。
-    pub async fn exclusive_transaction<F, T, E>(&self, f: F) -> Result<T, E>
-    where
-        F: FnOnce(&rusqlite::Transaction<'_>) -> Result<T, E> + Send + 'static,
-        T: Send + 'static,
-        E: From<rusqlite::Error> + From<tokio::task::JoinError> + Send + 'static,
-    {
-        let conn = self.conn.clone();
-
-        tokio::task::spawn_blocking(move || {
-            let mut conn = conn.blocking_lock();
-            let tx = conn.transaction()?;
-            let result = f(&tx)?;
-            tx.commit()?;
-            Ok(result)
-        })
-        .await?
+        .await
     }
 
     /// 複数SQL文をまとめて実行
     pub async fn execute_batch(&self, sql: &str) -> Result<()> {
-        let conn = self.conn.clone();
         let sql = sql.to_string();
 
-        tokio::task::spawn_blocking(move || {
-            let conn = conn.blocking_lock();
+        self.with_writer(move |conn| {
             conn.execute_batch(&sql)?;
             Ok(())
         })
-        .await?
+        .await
+    }
+
+    /// ---
+    /// WALの内容をデータベースファイルに書き戻します。
+    /// 実行中の処理がある場合は、その完了を待ってから実行されます。
+    /// ---
+    pub async fn checkpoint(&self) -> Result<()> {
+        self.with_writer(move |conn| {
+            // WALモードでない場合も結果の行が返るだけで何もしない
+            conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+            Ok(())
+        })
+        .await
     }
 
     /// schemaファイルを読み込む
     pub async fn load_schema(&self, sql: &str) -> Result<()> {
-        self.execute_batch(&sql).await?;
+        self.execute_batch(sql).await?;
         Ok(())
     }
 
+    /// ---
     /// データベース情報ダンプ
     /// データベースをSQL形式でダンプし、指定したファイルに出力します。
+    /// 出力したSQLを空のデータベースで実行すると、スキーマ・データ・スキーマのversionが元通りになります。
+    /// ---
     pub async fn dump_database<P: AsRef<Path>>(&self, path: P) -> Result<(), anyhow::Error> {
-        let conn = self.conn.clone();
         let path = path.as_ref().to_path_buf();
 
-        tokio::task::spawn_blocking(move || {
-            let conn = conn.blocking_lock();
-            let mut file = File::create(&path)?;
-
-            // 1. sqlite_masterからスキーマ取得
-            let mut stmt = conn.prepare(
-                "SELECT type, name, tbl_name, sql FROM sqlite_master
-                 WHERE type IN ('table', 'index', 'trigger', 'view')
-                 ORDER BY type DESC, name",
-            )?;
-
-            let entries = stmt.query_map([], |row| {
-                Ok((
-                    row.get::<_, String>(0)?,         // type
-                    row.get::<_, String>(1)?,         // name
-                    row.get::<_, String>(2)?,         // tbl_name
-                    row.get::<_, Option<String>>(3)?, // sql
-                ))
-            })?;
-
-            // 2. 出力開始
-            writeln!(file, "BEGIN TRANSACTION;")?;
-
-            for entry in entries {
-                let (ty, name, _tbl_name, sql) = entry?;
-                if let Some(sql) = sql {
-                    writeln!(file, "{};", sql)?;
-                }
-
-                // 3. テーブルデータをINSERT文で出力
-                if ty == "table" && name != "sqlite_sequence" {
-                    let mut stmt = conn.prepare(&format!("SELECT * FROM {}", name))?;
-                    let column_count = stmt.column_count();
-                    let mut rows = stmt.query([])?;
-
-                    while let Some(row) = rows.next()? {
-                        let mut values = Vec::with_capacity(column_count);
-                        for i in 0..column_count {
-                            let value: rusqlite::types::Value = row.get(i)?;
-                            let val_str = match value {
-                                rusqlite::types::Value::Null => "NULL".to_string(),
-                                rusqlite::types::Value::Integer(v) => v.to_string(),
-                                rusqlite::types::Value::Real(v) => v.to_string(),
-                                rusqlite::types::Value::Text(t) => {
-                                    format!("'{}'", t.replace('\'', "''"))
-                                }
-                                rusqlite::types::Value::Blob(_) => "'<BLOB>'".to_string(),
-                            };
-                            values.push(val_str);
-                        }
-
-                        writeln!(file, "INSERT INTO {} VALUES({});", name, values.join(", "))?;
-                    }
-                }
-            }
-
-            writeln!(file, "COMMIT;")?;
+        self.with_reader(move |conn| {
+            let mut file = BufWriter::new(File::create(&path)?);
+            write_dump(conn, &mut file)?;
+            file.flush()?;
             Ok::<_, anyhow::Error>(())
         })
-        .await?
+        .await
+    }
+}
+
+/// ---
+/// データベースをSQL形式で出力します。
+/// テーブルとデータを作成順に出力してから、インデックス・ビュー・トリガーを出力します
+/// （先にトリガーを作ると、データの読み込みでトリガーが動いてしまうため）。
+/// ---
+fn write_dump(conn: &Connection, out: &mut impl Write) -> anyhow::Result<()> {
+    // 1つの読み取りトランザクションで読み、途中の書き込みが混ざらないようにする
+    let tx = conn.unchecked_transaction()?;
+    let user_version: u32 = tx.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+    // 読み込み中に外部キーを検査しないようにする（トランザクション内では変更できないため、BEGINより前に書く）
+    writeln!(out, "PRAGMA foreign_keys=OFF;")?;
+    writeln!(out, "BEGIN TRANSACTION;")?;
+
+    // 1. テーブルとデータ（参照先のテーブルが先になるよう作成順に出力）
+    let tables = tx
+        .prepare(
+            "SELECT name, sql FROM sqlite_master
+             WHERE type = 'table' AND substr(name, 1, 7) != 'sqlite_'
+             ORDER BY rowid",
+        )?
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (name, sql) in &tables {
+        writeln!(out, "{};", sql)?;
+        write_rows(&tx, out, name)?;
+    }
+
+    // AUTOINCREMENTの採番状況（sqlite_sequenceはAUTOINCREMENTのテーブルの作成時に作られる）
+    let has_sequence: bool = tx.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'sqlite_sequence')",
+        [],
+        |row| row.get(0),
+    )?;
+    if has_sequence {
+        writeln!(out, "DELETE FROM sqlite_sequence;")?;
+        write_rows(&tx, out, "sqlite_sequence")?;
+    }
+
+    // 2. インデックス・ビュー・トリガー（自動作成のインデックスはsqlがNULL）
+    let mut stmt = tx.prepare(
+        "SELECT sql FROM sqlite_master
+         WHERE type IN ('index', 'view', 'trigger') AND sql IS NOT NULL
+         ORDER BY CASE type WHEN 'index' THEN 0 WHEN 'view' THEN 1 ELSE 2 END, rowid",
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        writeln!(out, "{};", row.get::<_, String>(0)?)?;
+    }
+
+    writeln!(out, "PRAGMA user_version={};", user_version)?;
+    writeln!(out, "COMMIT;")?;
+    // このデータベースは常に外部キーを有効にして使う
+    writeln!(out, "PRAGMA foreign_keys=ON;")?;
+    Ok(())
+}
+
+/// テーブルの全ての行をINSERT文で出力します
+fn write_rows(conn: &Connection, out: &mut impl Write, table: &str) -> anyhow::Result<()> {
+    let table = quote_identifier(table);
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {}", table))?;
+    let column_count = stmt.column_count();
+    let mut rows = stmt.query([])?;
+
+    while let Some(row) = rows.next()? {
+        let mut values = Vec::with_capacity(column_count);
+        for i in 0..column_count {
+            values.push(sql_literal(row.get_ref(i)?));
+        }
+        writeln!(out, "INSERT INTO {} VALUES({});", table, values.join(","))?;
+    }
+    Ok(())
+}
+
+/// 識別子をダブルクォートで囲みます
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// ---
+/// 値を読み込み直すと同じ値になるSQLのリテラルにします。
+/// - REAL: 元の値に戻る最短の表記（無限大は範囲外の指数で表します）
+/// - BLOB: X'..'の16進表記
+/// - TEXT: UTF-8でない場合やNUL文字を含む場合は、16進表記をTEXTに変換します
+/// ---
+fn sql_literal(value: ValueRef<'_>) -> String {
+    match value {
+        ValueRef::Null => "NULL".to_string(),
+        ValueRef::Integer(v) => v.to_string(),
+        ValueRef::Real(v) if v.is_infinite() => if v > 0.0 { "9e999" } else { "-9e999" }.to_string(),
+        ValueRef::Real(v) => format!("{:?}", v),
+        ValueRef::Text(bytes) => match std::str::from_utf8(bytes) {
+            Ok(text) if !text.contains('\0') => format!("'{}'", text.replace('\'', "''")),
+            _ => format!("CAST({} AS TEXT)", hex_literal(bytes)),
+        },
+        ValueRef::Blob(bytes) => hex_literal(bytes),
+    }
+}
+
+fn hex_literal(bytes: &[u8]) -> String {
+    let mut literal = String::with_capacity(bytes.len() * 2 + 3);
+    literal.push_str("X'");
+    for byte in bytes {
+        literal.push_str(&format!("{:02X}", byte));
+    }
+    literal.push('\'');
+    literal
+}
+
+/// ---
+/// トランザクションの開始方法
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionMode {
+    /// 読み取り専用の接続で読むだけのトランザクション（BEGIN DEFERRED）
+    /// 開始時点のコミット済みの内容を読み、書き込み中のトランザクションを待ちません。
+    /// インメモリのデータベースでは書き込み用の接続で読みます。
+    Read,
+    /// 開始時に書き込みのロックを取るトランザクション（BEGIN IMMEDIATE）
+    Immediate,
+    /// 開始時に排他ロックを取るトランザクション（BEGIN EXCLUSIVE）
+    /// WALモードではImmediateと同じで、それ以外では読み取りも締め出します。
+    Exclusive,
+}
+
+impl TransactionMode {
+    fn behavior(&self) -> TransactionBehavior {
+        match self {
+            TransactionMode::Read => TransactionBehavior::Deferred,
+            TransactionMode::Immediate => TransactionBehavior::Immediate,
+            TransactionMode::Exclusive => TransactionBehavior::Exclusive,
+        }
+    }
+}
+
+/// SQLITE_BUSYでトランザクションをやり直す最大の回数
+pub const MAX_BUSY_RETRIES: u32 = 5;
+/// やり直すまでの待ち時間（回数に比例して長くします）
+const BUSY_RETRY_DELAY: Duration = Duration::from_millis(20);
+
+/// ---
+/// トランザクションの処理が返すエラー
+/// SQLITE_BUSY（busy_timeoutを過ぎてもロックが取れない）の場合はやり直します。
+/// ---
+pub trait TransactionError: From<rusqlite::Error> + From<JoinError> + Send + 'static {
+    /// データベースのロックが取れずに失敗したかどうか
+    fn is_busy(&self) -> bool;
+}
+
+/// SQLITE_BUSY・SQLITE_LOCKEDのエラーかどうか
+pub fn is_busy_error(e: &rusqlite::Error) -> bool {
+    matches!(
+        e.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+    )
+}
+
+impl TransactionError for DatabaseError {
+    fn is_busy(&self) -> bool {
+        matches!(self, DatabaseError::Sqlite(e) if is_busy_error(e))
+    }
+}
+
+impl TransactionError for anyhow::Error {
+    fn is_busy(&self) -> bool {
+        self.downcast_ref::<rusqlite::Error>().is_some_and(is_busy_error)
     }
 }
 
 impl DataBase {
-    /// トランザクションを開始します。
-    pub async fn transaction<F, T>(&self, mut f: F) -> Result<T>
+    /// ---
+    /// 一連の処理を1つのトランザクションで実行します。
+    /// fがOkを返すとコミットし、Errを返すとロールバックします。
+    /// ロックが取れずに失敗した場合は、ロールバックしてからfを最初からやり直します（最大MAX_BUSY_RETRIES回）。
+    /// 処理の一部だけを取り消したい場合は QueryTransaction::savepoint を使います。
+    /// ---
+    pub async fn transaction<F, T, E>(&self, mode: TransactionMode, mut f: F) -> Result<T, E>
     where
-        F: for<'tx> FnMut(&'tx QueryTransaction<'tx>) -> Result<T> + Send + 'static,
+        F: FnMut(&QueryTransaction<'_>) -> Result<T, E> + Send + 'static,
         T: Send + 'static,
+        E: TransactionError,
     {
-        let conn = self.conn.clone();
-
-        tokio::task::spawn_blocking(move || {
-            let mut conn = conn.blocking_lock();
-            let tx = conn.transaction()?;
-            let query_tx = QueryTransaction::new(tx);
-            match f(&query_tx) {
-                Ok(result) => {
-                    query_tx.commit()?;
-                    Ok(result)
-                }
-                Err(e) => {
-                    query_tx.rollback()?;
-                    Err(e)
+        let run = move |handle: &mut Handle| -> Result<T, E> {
+            let mut retries = 0;
+            loop {
+                match run_transaction(handle, mode, &mut f) {
+                    Err(e) if e.is_busy() && retries < MAX_BUSY_RETRIES => {
+                        retries += 1;
+                        std::thread::sleep(BUSY_RETRY_DELAY * retries);
+                    }
+                    result => return result,
                 }
             }
-        })
-        .await?
+        };
+        match mode {
+            TransactionMode::Read => self.with_reader(run).await,
+            TransactionMode::Immediate | TransactionMode::Exclusive => self.with_writer(run).await,
+        }
     }
 }
 
+/// トランザクションを1回実行します（Errの場合はdropでロールバックされます）
+fn run_transaction<F, T, E>(handle: &mut Handle, mode: TransactionMode, f: &mut F) -> Result<T, E>
+where
+    F: FnMut(&QueryTransaction<'_>) -> Result<T, E>,
+    E: From<rusqlite::Error>,
+{
+    let Handle { conn, statements } = handle;
+    let tx = QueryTransaction {
+        tx: conn.transaction_with_behavior(mode.behavior())?,
+        statements,
+        savepoints: Cell::new(0),
+    };
+    let result = f(&tx)?;
+    tx.tx.commit()?;
+    Ok(result)
+}
+
+/// ---
+/// 実行中のトランザクション
+/// Connectionのメソッドをそのまま使えます。execute・query・query_row・prepare・prepare_cachedはステートメントキャッシュを使います。
+/// ---
 pub struct QueryTransaction<'conn> {
     tx: rusqlite::Transaction<'conn>,
+    statements: &'conn StatementTracker,
+    /// 開いているセーブポイントの数
+    savepoints: Cell<u32>,
+}
+
+impl Deref for QueryTransaction<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.tx
+    }
+}
+
+impl QueryTransaction<'_> {
+    /// キャッシュしたステートメントを取り出します（なければ作ります）
+    pub fn prepare_cached(&self, sql: &str) -> rusqlite::Result<TrackedStatement<'_>> {
+        self.statements.prepare(&self.tx, sql)
+    }
+
+    /// ---
+    /// キャッシュしたステートメントを取り出します（prepare_cachedと同じです）
+    /// Connection::prepareを隠し、トランザクションの中のステートメントもキャッシュを通します。
+    /// ---
+    pub fn prepare(&self, sql: &str) -> rusqlite::Result<TrackedStatement<'_>> {
+        self.prepare_cached(sql)
+    }
+
+    /// SQL文を実行し、変更した行数を返します
+    pub fn execute<P: Params>(&self, sql: &str, params: P) -> rusqlite::Result<usize> {
+        self.prepare_cached(sql)?.execute(params)
+    }
+
+    /// SELECT文を実行し、すべての行をfで変換して返します
+    pub fn query<T, F, P>(&self, sql: &str, params: P, mut f: F) -> rusqlite::Result<Vec<T>>
+    where
+        P: Params,
+        F: FnMut(&rusqlite::Row<'_>) -> rusqlite::Result<T>,
+    {
+        let mut stmt = self.prepare_cached(sql)?;
+        let rows = stmt.query_map(params, |row| f(row))?;
+        rows.collect()
+    }
+
+    /// SELECT文を実行し、最初の行をfで変換して返します（行がなければQueryReturnedNoRowsを返します）
+    pub fn query_row<T, F, P>(&self, sql: &str, params: P, f: F) -> rusqlite::Result<T>
+    where
+        P: Params,
+        F: FnOnce(&rusqlite::Row<'_>) -> rusqlite::Result<T>,
+    {
+        self.prepare_cached(sql)?.query_row(params, f)
+    }
+
+    /// ---
+    /// fをセーブポイントの中で実行します。入れ子にできます。
+    /// fがErrを返すと、fの中の変更だけを取り消してErrを返します（トランザクションは続きます）。
+    /// ---
+    pub fn savepoint<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&Self) -> Result<T, E>,
+        E: From<rusqlite::Error>,
+    {
+        let depth = self.savepoints.get() + 1;
+        let name = format!("sp_{}", depth);
+        self.tx.execute_batch(&format!("SAVEPOINT {}", name))?;
+        self.savepoints.set(depth);
+        let result = f(self);
+        self.savepoints.set(depth - 1);
+
+        match result {
+            Ok(value) => {
+                self.tx.execute_batch(&format!("RELEASE {}", name))?;
+                Ok(value)
+            }
+            Err(e) => {
+                self.tx.execute_batch(&format!("ROLLBACK TO {0}; RELEASE {0}", name))?;
+                Err(e)
+            }
+        }
+    }
 }
 
-impl<'conn> QueryTransaction<'conn> {
-    pub fn new(tx: rusqlite::Transaction<'conn>) -> Self {
-        Self { tx }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::migration::MigrationMode;
+
+    /// テスト用のデータベースファイルのパス（WAL・SHMのファイルも消します）
+    struct TempPath(std::path::PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("shiritori-db-{}-{}.db", name, std::process::id()));
+            let temp = Self(path);
+            temp.remove();
+            temp
+        }
+
+        fn as_str(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+
+        fn remove(&self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", self.as_str(), suffix));
+            }
+        }
     }
 
-    pub fn rollback(self) -> rusqlite::Result<()> {
-        self.tx.rollback()
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            self.remove();
+        }
     }
 
-    pub fn commit(self) -> rusqlite::Result<()> {
-        self.tx.commit()
+    #[tokio::test]
+    async fn test_reader_pool() -> anyhow::Result<()> {
+        let memory = DataBase::new(":memory:").await?;
+        assert_eq!(memory.reader_count(), 0, "インメモリのデータベースで読み取り用の接続が作られました。");
+
+        let path = TempPath::new("pool");
+        let db = DataBase::with_readers(path.as_str(), 2).await?;
+        assert_eq!(db.reader_count(), 2);
+        db.execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL);").await?;
+        db.execute("INSERT INTO items (name) VALUES (?1)", ["a".to_string()]).await?;
+
+        let names = db.query("SELECT name FROM items", [], |row| row.get::<_, String>(0)).await?;
+        assert_eq!(names, vec!["a".to_string()], "書き込んだ内容が読み取り用の接続から見えません。");
+
+        // 読み取り用の接続では書き込めない
+        let result = db
+            .transaction(TransactionMode::Read, |tx| -> Result<usize> {
+                Ok(tx.execute("INSERT INTO items (name) VALUES ('b')", [])?)
+            })
+            .await;
+        assert!(result.is_err(), "読み取り用の接続で書き込めました。");
+
+        // 同時に接続数より多く読んでも、すべて完了する
+        let db = Arc::new(db);
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let db = db.clone();
+                tokio::spawn(async move { db.query("SELECT count(*) FROM items", [], |row| row.get::<_, i64>(0)).await })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await??, vec![1]);
+        }
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_read_during_write() -> anyhow::Result<()> {
+        let path = TempPath::new("concurrent");
+        let db = Arc::new(DataBase::with_readers(path.as_str(), 2).await?);
+        db.execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL); INSERT INTO items (name) VALUES ('a');")
+            .await?;
+
+        // 書き込みのトランザクションを開いたまま止めておく
+        let (started_tx, started_rx) = tokio::sync::oneshot::channel::<()>();
+        let mut started_tx = Some(started_tx);
+        let (finish_tx, finish_rx) = std::sync::mpsc::channel::<()>();
+        let writer = tokio::spawn({
+            let db = db.clone();
+            async move {
+                db.transaction(TransactionMode::Immediate, move |tx| -> Result<()> {
+                    tx.execute("INSERT INTO items (name) VALUES ('b')", [])?;
+                    if let Some(started_tx) = started_tx.take() {
+                        let _ = started_tx.send(());
+                    }
+                    let _ = finish_rx.recv();
+                    Ok(())
+                })
+                .await
+            }
+        });
+        started_rx.await?;
+
+        let read = db.query("SELECT count(*) FROM items", [], |row| row.get::<_, i64>(0));
+        let count = tokio::time::timeout(Duration::from_secs(5), read).await;
+        finish_tx.send(())?;
+        writer.await??;
+
+        assert_eq!(count.map_err(|_| anyhow::anyhow!("読み取りが書き込みを待ちました。"))??, vec![1], "コミット前の内容が読まれました。");
+        let count = db.query("SELECT count(*) FROM items", [], |row| row.get::<_, i64>(0)).await?;
+        assert_eq!(count, vec![2], "コミット後の内容が読まれていません。");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_statement_cache() -> anyhow::Result<()> {
+        let options = DataBaseOptions { statement_cache_capacity: 2, ..Default::default() };
+        let db = DataBase::with_options(":memory:", options).await?;
+        db.execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL);").await?;
+        assert_eq!(db.statement_cache_stats(), StatementCacheStats::default());
+
+        for name in ["a", "b"] {
+            db.execute("INSERT INTO items (name) VALUES (?1)", [name.to_string()]).await?;
+        }
+        db.query("SELECT name FROM items", [], |row| row.get::<_, String>(0)).await?;
+        db.query(" SELECT name FROM items ", [], |row| row.get::<_, String>(0)).await?;
+        assert_eq!(
+            db.statement_cache_stats(),
+            StatementCacheStats { hits: 2, misses: 2 },
+            "同じSQLのステートメントが再利用されていません。"
+        );
+
+        // 容量を超えると古いものから追い出される
+        db.query("SELECT id FROM items", [], |row| row.get::<_, i64>(0)).await?;
+        db.execute("INSERT INTO items (name) VALUES (?1)", ["c".to_string()]).await?;
+        assert_eq!(db.statement_cache_stats(), StatementCacheStats { hits: 2, misses: 4 }, "追い出されたステートメントがヒットしました。");
+
+        // 解析に失敗したSQLはキャッシュされない
+        assert!(db.query("SELECT nothing FROM items", [], |row| row.get::<_, i64>(0)).await.is_err());
+        assert!(db.query("SELECT nothing FROM items", [], |row| row.get::<_, i64>(0)).await.is_err());
+        assert_eq!(db.statement_cache_stats().misses, 6);
+
+        // トランザクションでも同じキャッシュを使う
+        db.transaction(TransactionMode::Immediate, |tx| -> Result<()> {
+            tx.query("SELECT id FROM items", [], |row| row.get::<_, i64>(0))?;
+            Ok(())
+        })
+        .await?;
+        assert_eq!(db.statement_cache_stats().hits, 3, "トランザクションでキャッシュが使われていません。");
+
+        // query_row・prepareもConnectionのメソッドではなくキャッシュを通る
+        db.transaction(TransactionMode::Immediate, |tx| -> Result<()> {
+            for _ in 0..2 {
+                tx.query_row("SELECT count(*) FROM items", [], |row| row.get::<_, i64>(0))?;
+            }
+            tx.prepare("SELECT count(*) FROM items")?.query_row([], |row| row.get::<_, i64>(0))?;
+            Ok(())
+        })
+        .await?;
+        assert_eq!(
+            db.statement_cache_stats(),
+            StatementCacheStats { hits: 5, misses: 7 },
+            "トランザクションの中で繰り返したquery_rowがキャッシュにヒットしていません。"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_statement_cache_same_sql_in_use() -> anyhow::Result<()> {
+        let db = DataBase::new(":memory:").await?;
+        db.execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL);").await?;
+        let before = db.statement_cache_stats();
+
+        let runs = db
+            .transaction(TransactionMode::Immediate, |tx| -> Result<i32> {
+                let sql = "SELECT count(*) FROM items";
+                {
+                    // 1つ目を使っている間はキャッシュにないため、2つ目は新しく作られる
+                    let mut first = tx.prepare_cached(sql)?;
+                    let mut second = tx.prepare_cached(sql)?;
+                    first.query_row([], |row| row.get::<_, i64>(0))?;
+                    second.query_row([], |row| row.get::<_, i64>(0))?;
+                }
+                // 使い終わったステートメントは再利用される（実行済みの回数が残っている）
+                let reused = tx.prepare_cached(sql)?;
+                Ok(reused.get_status(rusqlite::StatementStatus::Run))
+            })
+            .await?;
+        assert_eq!(runs, 1, "使い終わったステートメントが再利用されていません。");
+        let after = db.statement_cache_stats();
+        assert_eq!(
+            (after.hits - before.hits, after.misses - before.misses),
+            (1, 2),
+            "使用中のステートメントと同じSQLがヒットとして数えられました。"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_savepoint() -> anyhow::Result<()> {
+        let db = DataBase::new(":memory:").await?;
+        db.execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE);").await?;
+
+        let names = db
+            .transaction(TransactionMode::Immediate, |tx| -> Result<Vec<String>> {
+                tx.execute("INSERT INTO items (name) VALUES ('a')", [])?;
+                // 失敗したセーブポイントの変更だけが取り消される
+                let failed = tx.savepoint(|tx| -> Result<()> {
+                    tx.execute("INSERT INTO items (name) VALUES ('b')", [])?;
+                    tx.execute("INSERT INTO items (name) VALUES ('a')", [])?;
+                    Ok(())
+                });
+                assert!(failed.is_err());
+                // 入れ子のセーブポイント
+                tx.savepoint(|tx| -> Result<()> {
+                    tx.execute("INSERT INTO items (name) VALUES ('c')", [])?;
+                    let inner = tx.savepoint(|tx| -> Result<()> {
+                        tx.execute("INSERT INTO items (name) VALUES ('d')", [])?;
+                        Err(rusqlite::Error::QueryReturnedNoRows.into())
+                    });
+                    assert!(inner.is_err());
+                    Ok(())
+                })?;
+                Ok(tx.query("SELECT name FROM items ORDER BY name", [], |row| row.get(0))?)
+            })
+            .await?;
+        assert_eq!(names, vec!["a".to_string(), "c".to_string()], "セーブポイントの取り消しが正しくありません。");
+
+        // Errを返したトランザクションはすべて取り消される
+        let result = db
+            .transaction(TransactionMode::Exclusive, |tx| -> Result<()> {
+                tx.execute("INSERT INTO items (name) VALUES ('e')", [])?;
+                Err(rusqlite::Error::QueryReturnedNoRows.into())
+            })
+            .await;
+        assert!(result.is_err());
+        let count = db.query("SELECT count(*) FROM items", [], |row| row.get::<_, i64>(0)).await?;
+        assert_eq!(count, vec![2], "失敗したトランザクションがコミットされました。");
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_busy_retry() -> anyhow::Result<()> {
+        let path = TempPath::new("busy");
+        let options = DataBaseOptions { busy_timeout: Duration::from_millis(1), ..Default::default() };
+        let db = Arc::new(DataBase::with_options(path.as_str(), options).await?);
+        db.execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL);").await?;
+
+        // 別の接続で書き込みのロックを少しの間持ち続ける
+        let other = Connection::open(path.as_str())?;
+        other.execute_batch("BEGIN IMMEDIATE; INSERT INTO items (name) VALUES ('other');")?;
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            other.execute_batch("COMMIT;")
+        });
+
+        let mut attempts = 0;
+        let attempts = db
+            .transaction(TransactionMode::Immediate, move |tx| -> Result<u32> {
+                attempts += 1;
+                tx.execute("INSERT INTO items (name) VALUES ('mine')", [])?;
+                Ok(attempts)
+            })
+            .await?;
+        release.join().unwrap()?;
+        assert_eq!(attempts, 1, "ロックが取れるまでfを実行してはいけません。");
+        let count = db.query("SELECT count(*) FROM items", [], |row| row.get::<_, i64>(0)).await?;
+        assert_eq!(count, vec![2], "ロックの解放後にやり直されていません。");
+        Ok(())
+    }
+
+    /// 全てのテーブルの内容（リテラル表記で比べるので、-0.0やBLOBも区別される）とスキーマ
+    type Contents = (u32, Vec<(String, String, Option<String>)>, Vec<(String, Vec<Vec<String>>)>);
+
+    async fn contents(db: &DataBase) -> anyhow::Result<Contents> {
+        db.with_reader(|conn| {
+            let user_version: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+            let schema = conn
+                .prepare("SELECT type, name, sql FROM sqlite_master ORDER BY type, name")?
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                .collect::<rusqlite::Result<Vec<(String, String, Option<String>)>>>()?;
+
+            let mut tables = Vec::new();
+            for (ty, name, _) in &schema {
+                if ty != "table" {
+                    continue;
+                }
+                let mut stmt = conn.prepare(&format!("SELECT * FROM {}", quote_identifier(name)))?;
+                let column_count = stmt.column_count();
+                let mut rows = stmt.query([])?;
+                let mut values = Vec::new();
+                while let Some(row) = rows.next()? {
+                    values.push((0..column_count).map(|i| row.get_ref(i).map(sql_literal)).collect::<rusqlite::Result<Vec<_>>>()?);
+                }
+                values.sort();
+                tables.push((name.clone(), values));
+            }
+            Ok::<_, anyhow::Error>((user_version, schema, tables))
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_dump_round_trip() -> anyhow::Result<()> {
+        let db = DataBase::new(":memory:").await?;
+        db.migrate(MigrationMode::Apply).await?;
+        db.execute_batch(
+            "INSERT INTO rooms (id) VALUES (1);
+             INSERT INTO room_members (room_id, user_id) VALUES (1, 10), (1, 20);
+             UPDATE room_members SET next = 30 - user_id, prev = 30 - user_id WHERE room_id = 1;
+             INSERT INTO room_votes (room_id, current_user_id, word, updated_at) VALUES (1, 10, 'しりとり', '2024-01-01 00:00:00');
+
+             CREATE TABLE \"odd \"\"name\"\"\" (id INTEGER PRIMARY KEY AUTOINCREMENT, data BLOB, ratio REAL, note TEXT);
+             INSERT INTO \"odd \"\"name\"\"\" (note) VALUES (NULL);
+             CREATE INDEX \"odd index\" ON \"odd \"\"name\"\"\" (ratio);
+             CREATE VIEW \"odd view\" AS SELECT id, note FROM \"odd \"\"name\"\"\";
+             CREATE TRIGGER \"odd trigger\" AFTER INSERT ON \"odd \"\"name\"\"\" WHEN NEW.note IS NULL
+             BEGIN
+                 UPDATE \"odd \"\"name\"\"\" SET note = 'trigger' WHERE id = NEW.id;
+             END;",
+        )
+        .await?;
+        let reals = [0.1, 1.0 / 3.0, f64::MAX, 5e-324, -0.0, 1e16, f64::INFINITY, f64::NEG_INFINITY];
+        db.with_writer(move |conn| {
+            let mut stmt = conn.prepare("INSERT INTO \"odd \"\"name\"\"\" (data, ratio, note) VALUES (?1, ?2, ?3)")?;
+            for (i, ratio) in reals.iter().enumerate() {
+                stmt.execute(rusqlite::params![vec![0u8, i as u8, 0xff], ratio, format!("it's\n\"{}\"\0end", i)])?;
+            }
+            stmt.execute(rusqlite::params![Vec::<u8>::new(), 1.5, "plain"])?;
+            Ok::<_, DatabaseError>(())
+        })
+        .await?;
+
+        let path = TempPath::new("dump");
+        db.dump_database(&path.0).await?;
+        let sql = std::fs::read_to_string(&path.0)?;
+        assert!(!sql.contains("<BLOB>"), "BLOBの内容が出力されていません。");
+
+        let restored = DataBase::new(":memory:").await?;
+        restored.load_schema(&sql).await?;
+        assert_eq!(contents(&restored).await?, contents(&db).await?, "ダンプから読み込んだ内容が一致しません。");
+
+        let foreign_keys = restored.query("PRAGMA foreign_keys", [], |row| row.get::<_, bool>(0)).await?;
+        assert_eq!(foreign_keys, vec![true], "読み込み後に外部キーが有効に戻っていません。");
+        let violations = restored.query("PRAGMA foreign_key_check", [], |row| row.get::<_, String>(0)).await?;
+        assert!(violations.is_empty(), "外部キーの違反があります: {:?}", violations);
+
+        // AUTOINCREMENTの採番も引き継がれる
+        restored.execute_batch("INSERT INTO \"odd \"\"name\"\"\" (note) VALUES ('next');").await?;
+        let ids = restored.query("SELECT max(id) FROM \"odd \"\"name\"\"\"", [], |row| row.get::<_, i64>(0)).await?;
+        assert_eq!(ids, vec![reals.len() as i64 + 3], "AUTOINCREMENTの採番が引き継がれていません。");
+        Ok(())
     }
 }
@@ -0,0 +1,432 @@
+// src/database/migration.rs
+use rusqlite::OptionalExtension;
+
+use crate::database::db::{DataBase, DatabaseError, QueryTransaction, Result, TransactionMode};
+
+/// ---
+/// バイナリに埋め込まれたスキーマ移行
+/// versionは1からの連番で、PRAGMA user_versionに適用済みのversionを記録します。
+/// ---
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial",
+        sql: include_str!("../../migrations/0001_initial.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "game_status",
+        sql: include_str!("../../migrations/0002_game_status.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "word_history",
+        sql: include_str!("../../migrations/0003_word_history.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "vote_message",
+        sql: include_str!("../../migrations/0004_vote_message.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "vote_policy",
+        sql: include_str!("../../migrations/0005_vote_policy.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "time_limits",
+        sql: include_str!("../../migrations/0006_time_limits.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "room_settings",
+        sql: include_str!("../../migrations/0007_room_settings.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "dictionary",
+        sql: include_str!("../../migrations/0008_dictionary.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "word_reading",
+        sql: include_str!("../../migrations/0009_word_reading.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "word_key",
+        sql: include_str!("../../migrations/0010_word_key.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "computer_player",
+        sql: include_str!("../../migrations/0011_computer_player.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "player_stats",
+        sql: include_str!("../../migrations/0012_player_stats.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "chain_rules",
+        sql: include_str!("../../migrations/0013_chain_rules.sql"),
+    },
+];
+
+/// このバイナリが扱えるスキーマのversion
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// ---
+/// 移行の実行方法
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationMode {
+    /// 未適用の移行を適用します
+    Apply,
+    /// 未適用の移行をトランザクション内で実行して検証し、ロールバックします
+    DryRun,
+}
+
+/// ---
+/// 移行の実行結果
+/// ---
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub applied: Vec<(u32, &'static str)>,
+    pub mode: MigrationMode,
+}
+
+impl DataBase {
+    /// ---
+    /// 未適用のスキーマ移行を1つのトランザクションで適用します。
+    /// データベースがこのバイナリより新しいスキーマの場合はSchemaTooNewを返します。
+    /// ---
+    pub async fn migrate(&self, mode: MigrationMode) -> Result<MigrationReport> {
+        self.transaction(TransactionMode::Exclusive, move |tx| -> Result<MigrationReport> {
+            if mode == MigrationMode::DryRun {
+                tx.execute_batch("SAVEPOINT migration_dry_run")?;
+            }
+
+            let from_version = current_version(tx)?;
+            let latest = latest_version();
+            if from_version > latest {
+                return Err(DatabaseError::SchemaTooNew {
+                    database: from_version,
+                    supported: latest,
+                });
+            }
+
+            let pending: Vec<&Migration> = MIGRATIONS
+                .iter()
+                .filter(|m| m.version > from_version)
+                .collect();
+
+            for migration in &pending {
+                tx.execute_batch(migration.sql)?;
+                tx.pragma_update(None, "user_version", migration.version)?;
+            }
+
+            if mode == MigrationMode::DryRun {
+                tx.execute_batch("ROLLBACK TO migration_dry_run; RELEASE migration_dry_run")?;
+            }
+
+            Ok(MigrationReport {
+                from_version,
+                to_version: pending.last().map(|m| m.version).unwrap_or(from_version),
+                applied: pending.iter().map(|m| (m.version, m.name)).collect(),
+                mode,
+            })
+        })
+        .await
+    }
+
+    /// 現在のスキーマversionを取得します
+    pub async fn schema_version(&self) -> Result<u32> {
+        self.transaction(TransactionMode::Immediate, current_version).await
+    }
+}
+
+/// ---
+/// 適用済みのversionを取得します。
+/// user_versionが0でも、移行導入前のINIT_SQLで作成されたデータベースであれば
+/// テーブル構造から相当するversionを判定して記録します。
+/// ---
+fn current_version(tx: &QueryTransaction<'_>) -> Result<u32> {
+    let version: u32 = tx.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version != 0 {
+        return Ok(version);
+    }
+
+    let legacy_version = detect_legacy_version(tx)?;
+    if legacy_version != 0 {
+        tx.pragma_update(None, "user_version", legacy_version)?;
+    }
+    Ok(legacy_version)
+}
+
+fn detect_legacy_version(tx: &QueryTransaction<'_>) -> Result<u32> {
+    let has_rooms = tx
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'rooms'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+    if !has_rooms {
+        return Ok(0);
+    }
+
+    // 移行導入後に追加されたカラムはuser_versionで管理されるため、ここでは判定しない
+    if has_column(tx, "room_words", "turn")? {
+        Ok(3)
+    } else if has_column(tx, "rooms", "status")? {
+        Ok(2)
+    } else {
+        Ok(1)
+    }
+}
+
+fn has_column(tx: &QueryTransaction<'_>, table: &str, column: &str) -> Result<bool> {
+    let count: i64 = tx.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+        [table, column],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::db::QueryExecutor;
+
+    #[test]
+    fn test_migration_versions_are_sequential() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as u32 + 1, "移行のversionが連番ではありません: {}", migration.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_migrate_fresh_database() -> anyhow::Result<()> {
+        let db = DataBase::new(":memory:").await?;
+
+        let report = db.migrate(MigrationMode::Apply).await?;
+        assert_eq!(report.from_version, 0, "新規データベースのversionが0ではありません。");
+        assert_eq!(report.to_version, latest_version(), "最新versionまで適用されていません。");
+        assert_eq!(db.schema_version().await?, latest_version());
+
+        // 2回目は何も適用しない
+        let report = db.migrate(MigrationMode::Apply).await?;
+        assert!(report.applied.is_empty(), "適用済みの移行が再適用されました。\nreport: {:?}", report);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_dry_run() -> anyhow::Result<()> {
+        let db = DataBase::new(":memory:").await?;
+
+        let report = db.migrate(MigrationMode::DryRun).await?;
+        assert_eq!(report.applied.len(), MIGRATIONS.len(), "dry-runで適用予定の移行が報告されていません。");
+        assert_eq!(db.schema_version().await?, 0, "dry-runでversionが更新されました。");
+
+        let tables = db
+            .query("SELECT name FROM sqlite_master WHERE type = 'table'", [], |row| row.get::<_, String>(0))
+            .await?;
+        assert!(tables.is_empty(), "dry-runでテーブルが作成されました。\ntables: {:?}", tables);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_refuse_newer_database() -> anyhow::Result<()> {
+        let db = DataBase::new(":memory:").await?;
+        db.execute_batch(&format!("PRAGMA user_version = {}", latest_version() + 1)).await?;
+
+        let result = db.migrate(MigrationMode::Apply).await;
+        assert!(
+            matches!(result, Err(DatabaseError::SchemaTooNew { .. })),
+            "新しいスキーマのデータベースで移行が拒否されませんでした。\nresult: {:?}",
+            result
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_legacy_database() -> anyhow::Result<()> {
+        // 移行導入前のINIT_SQLで作成されたデータベース
+        let db = DataBase::new(":memory:").await?;
+        db.execute_batch(MIGRATIONS[0].sql).await?;
+        db.execute_batch(
+            "INSERT INTO rooms VALUES(1);
+             INSERT INTO room_words VALUES(1, 'しりとり');
+             INSERT INTO room_words VALUES(1, 'りんご');",
+        )
+        .await?;
+
+        let report = db.migrate(MigrationMode::Apply).await?;
+        assert_eq!(report.from_version, 1, "既存データベースが初期スキーマとして認識されませんでした。");
+
+        let words = db
+            .query("SELECT word, turn FROM room_words ORDER BY turn", [], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })
+            .await?;
+        assert_eq!(
+            words,
+            vec![("しりとり".to_string(), 1), ("りんご".to_string(), 2)],
+            "既存の単語が手番付きで移行されていません。"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_room_settings() -> anyhow::Result<()> {
+        // roomsに設定を保存していたversion 6のデータベース
+        let db = DataBase::new(":memory:").await?;
+        for migration in &MIGRATIONS[..6] {
+            db.execute_batch(migration.sql).await?;
+        }
+        db.execute_batch(
+            "PRAGMA user_version = 6;
+             INSERT INTO rooms (id) VALUES(1);
+             INSERT INTO rooms (id, n_ending, vote_policy, vote_threshold, turn_timeout, turn_timeout_action)
+                 VALUES(2, 'continue', 'supermajority', 70, 90, 'eliminate');",
+        )
+        .await?;
+
+        db.migrate(MigrationMode::Apply).await?;
+        let settings = db
+            .query(
+                "SELECT room_id, n_ending, vote_policy, vote_threshold, vote_quorum, turn_timeout, turn_timeout_action
+                 FROM room_settings ORDER BY room_id",
+                [],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, Option<i64>>(3)?,
+                        row.get::<_, Option<i64>>(4)?,
+                        row.get::<_, Option<i64>>(5)?,
+                        row.get::<_, Option<String>>(6)?,
+                    ))
+                },
+            )
+            .await?;
+        assert_eq!(
+            settings,
+            vec![(
+                2,
+                Some("continue".to_string()),
+                Some("supermajority".to_string()),
+                Some(70),
+                Some(0),
+                Some(90),
+                Some("eliminate".to_string())
+            )],
+            "既定値と異なる設定だけが移行されていません。"
+        );
+
+        let columns = db
+            .query("SELECT name FROM pragma_table_info('rooms')", [], |row| row.get::<_, String>(0))
+            .await?;
+        assert!(!columns.contains(&"n_ending".to_string()), "roomsから設定のカラムが削除されていません。\ncolumns: {:?}", columns);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_word_reading() -> anyhow::Result<()> {
+        // 表記だけを保存していたversion 8のデータベース
+        let db = DataBase::new(":memory:").await?;
+        for migration in &MIGRATIONS[..8] {
+            db.execute_batch(migration.sql).await?;
+        }
+        db.execute_batch(
+            "PRAGMA user_version = 8;
+             INSERT INTO rooms (id) VALUES(1);
+             INSERT INTO room_words (room_id, word, turn) VALUES(1, 'Apple', 1);
+             INSERT INTO room_votes (room_id, current_user_id, word) VALUES(1, 100, 'えんぴつ');",
+        )
+        .await?;
+
+        db.migrate(MigrationMode::Apply).await?;
+        let readings = db
+            .query(
+                "SELECT reading FROM room_words UNION ALL SELECT reading FROM room_votes",
+                [],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .await?;
+        assert_eq!(
+            readings,
+            vec![Some("apple".to_string()), Some("えんぴつ".to_string())],
+            "既存の単語のよみが設定されていません。"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_word_key() -> anyhow::Result<()> {
+        // 表記ゆれの単語が別の単語として保存されていたversion 9のデータベース
+        let db = DataBase::new(":memory:").await?;
+        for migration in &MIGRATIONS[..9] {
+            db.execute_batch(migration.sql).await?;
+        }
+        db.execute_batch(
+            "PRAGMA user_version = 9;
+             INSERT INTO rooms (id) VALUES(1);
+             INSERT INTO room_words (room_id, word, turn, reading) VALUES(1, 'りんご', 1, 'りんご');
+             INSERT INTO room_words (room_id, word, turn, reading) VALUES(1, 'ゴリラ', 2, 'ゴリラ');
+             INSERT INTO room_words (room_id, word, turn, reading) VALUES(1, 'リンゴ', 3, 'リンゴ');",
+        )
+        .await?;
+
+        db.migrate(MigrationMode::Apply).await?;
+        let words = db
+            .query(
+                "SELECT word, reading, word_key FROM room_words ORDER BY turn",
+                [],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+            )
+            .await?;
+        let expected = [
+            ("りんご", "りんご", "りんご"),
+            ("ゴリラ", "ごりら", "ごりら"),
+            ("リンゴ", "りんご", "りんご#3"),
+        ]
+        .map(|(word, reading, key)| (word.to_string(), reading.to_string(), key.to_string()));
+        assert_eq!(words, expected.to_vec(), "既存の単語の正規化キーが想定と異なります。");
+
+        // テーブルの作り直しで、以前のversionで作ったインデックスが失われない
+        let indexes = db
+            .query(
+                "SELECT name FROM sqlite_master WHERE type = 'index' AND tbl_name = 'room_words' AND sql IS NOT NULL",
+                [],
+                |row| row.get::<_, String>(0),
+            )
+            .await?;
+        assert_eq!(indexes, vec!["idx_room_words_reading".to_string()], "room_wordsのインデックスが失われました。");
+
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+pub mod backup;
 pub mod db;
+pub mod migration;
 pub mod repository;
 pub mod wrap_params;
\ No newline at end of file
@@ -4,13 +4,28 @@ use rusqlite::Error as SqliteError;
 use rusqlite::OptionalExtension;
 use rusqlite::Row;
 use tokio::task::JoinError;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::Arc;
 use thiserror::Error;
 
+use crate::archive::RoomArchive;
+use crate::database::backup::Snapshot;
 use crate::database::db::DatabaseError;
+use crate::rules::chain::{is_chained, LongVowelRule};
+use crate::dictionary::{normalize_reading, DictionaryEntry, DictionaryFormat};
+use crate::rules::config::{ConfigOverrides, DictionaryMode, Language, RoomConfig};
+use crate::rules::canonical::canonical_key;
+use crate::rules::computer::{head_chars, Difficulty, COMPUTER_USER_ID};
+use crate::rules::reading::{contains_kanji, WordInput};
+use crate::rules::score::{best_streak, PlayerStats, TimeWindow};
+use crate::rules::script::ScriptSet;
+use crate::rules::terminal::NEndingRule;
+use crate::rules::timeout::{TimeLimits, TurnTimeoutAction};
+use crate::rules::vote::{Ballot, Verdict, VotePolicy, VoteRule};
 use crate::{
     database::{
-        db::{DataBase, QueryExecutor},
+        db::{DataBase, QueryExecutor, QueryTransaction, TransactionError, TransactionMode, is_busy_error},
         wrap_params::{i64_to_u64_bitwise},
     },
     wrap_params,
@@ -37,8 +52,30 @@ pub enum RepoError {
     InvalidVoteState,
     #[error("ユーザー順序が破損しています(BrokenChain)")]
     BrokenChain,
+    #[error("手番の順番が参加者と一致しません(InvalidQueue)")]
+    InvalidQueue,
     #[error("キューの先頭ユーザーではありません(NotFirstUser)")]
     NotFirstUser,
+    #[error("前の単語から続いていません(ChainMismatch)")]
+    ChainMismatch,
+    #[error("投票が進行中です(VoteInProgress)")]
+    VoteInProgress,
+    #[error("ゲームが進行中ではありません(GameNotActive)")]
+    GameNotActive,
+    #[error("ゲームが一時停止されていません(GameNotPaused)")]
+    GameNotPaused,
+    #[error("単語が短すぎます(WordTooShort)")]
+    WordTooShort,
+    #[error("使えない文字が含まれています(ScriptNotAllowed)")]
+    ScriptNotAllowed,
+    #[error("辞書に載っていない単語です(NotInDictionary)")]
+    NotInDictionary,
+    #[error("辞書が存在しません(DictionaryNotFound)")]
+    DictionaryNotFound,
+    #[error("よみを指定してください(ReadingRequired)")]
+    ReadingRequired,
+    #[error("単語が存在しません(WordNotFound)")]
+    WordNotFound,
     #[error("JoinError: {0}")]
     JoinError(#[from] JoinError),
     #[error("データベースエラー: {0}")]
@@ -52,24 +89,40 @@ impl_repo_error_partial_eq!(RepoError {
     RoomNotFound,
     RoomAlreadyExists,
     UserAlreadyExists,
+    UserNotFound,
     WordAlreadyExists,
     VoteNotExists,
     InvalidVoteState,
     NullWord,
     BrokenChain,
-    NotFirstUser
+    InvalidQueue,
+    NotFirstUser,
+    ChainMismatch,
+    VoteInProgress,
+    GameNotActive,
+    GameNotPaused,
+    WordTooShort,
+    ScriptNotAllowed,
+    NotInDictionary,
+    DictionaryNotFound,
+    ReadingRequired,
+    WordNotFound
 });
 
-#[derive(thiserror::Error, Debug)]
-pub enum TxError {
-    #[error("database error: {0}")]
-    Database(#[from] DatabaseError),
-    #[error("sqlite error: {0}")]
-    Sqlite(#[from] rusqlite::Error),
-    #[error("repo error: {0}")]
-    Repo(#[from] RepoError),
-    #[error("join error: {0}")]
-    JoinError(#[from] JoinError),
+impl From<DatabaseError> for RepoError {
+    fn from(e: DatabaseError) -> Self {
+        match e {
+            DatabaseError::Sqlite(e) => RepoError::Database(e),
+            DatabaseError::Join(e) => RepoError::JoinError(e),
+            other => RepoError::Other(anyhow::anyhow!(other)),
+        }
+    }
+}
+
+impl TransactionError for RepoError {
+    fn is_busy(&self) -> bool {
+        matches!(self, RepoError::Database(e) if is_busy_error(e))
+    }
 }
 
 impl Eq for RepoError {}
@@ -81,12 +134,176 @@ pub struct Vote {
     pub room_id: u64,
     pub user_id: u64,
     pub word: Option<String>,
+    /// 投票中の単語のよみ（正規化済み）
+    pub reading: Option<String>,
     pub good: Vec<u64>,
     pub bad: Vec<u64>,
     pub none: Vec<u64>,
-    pub updated_at: Option<NaiveDateTime>
+    pub updated_at: Option<NaiveDateTime>,
+    pub message_id: Option<u64>,
+}
+
+/// ---
+/// 投票の確定時の集計
+/// ---
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct VoteTally {
+    pub good: u64,
+    pub bad: u64,
+}
+
+impl From<&Vote> for VoteTally {
+    fn from(vote: &Vote) -> Self {
+        Self {
+            good: vote.good.len() as u64,
+            bad: vote.bad.len() as u64,
+        }
+    }
+}
+
+/// ---
+/// 既出単語の履歴
+/// ---
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct WordRecord {
+    pub room_id: u64,
+    pub turn: u64,
+    pub word: String,
+    /// よみ（正規化済み）。接続と既出の判定に使います
+    pub reading: String,
+    pub user_id: Option<u64>,
+    pub approved_at: Option<NaiveDateTime>,
+    pub tally: VoteTally,
+}
+
+/// WordRecordの取得に使うカラム（row_to_word_recordと対応）
+const WORD_RECORD_COLUMNS: &str =
+    "room_id, turn, word, user_id, approved_at, good_count, bad_count, reading";
+
+/// ---
+/// 読み込まれている辞書の情報
+/// ---
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct DictionaryInfo {
+    pub name: String,
+    pub format: DictionaryFormat,
+    pub entries: u64,
+    pub loaded_at: Option<NaiveDateTime>,
+}
+
+/// ---
+/// 成績を集計する範囲
+/// ---
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum StatsScope {
+    /// 1つのルーム
+    Room(u64),
+    /// サーバー全体
+    Guild(u64),
+}
+
+/// ---
+/// ルームのゲーム状態
+/// ---
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RoomStatus {
+    Active,
+    Finished,
+    Paused,
+}
+
+impl RoomStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoomStatus::Active => "active",
+            RoomStatus::Finished => "finished",
+            RoomStatus::Paused => "paused",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "active" => Some(RoomStatus::Active),
+            "finished" => Some(RoomStatus::Finished),
+            "paused" => Some(RoomStatus::Paused),
+            _ => None,
+        }
+    }
+}
+
+/// ---
+/// 終了したゲームの結果
+/// ---
+#[derive(PartialEq, Eq, Debug)]
+pub struct GameResult {
+    pub room_id: u64,
+    pub loser_id: Option<u64>,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+/// ---
+/// 投票の確定処理の結果
+/// ---
+#[derive(PartialEq, Eq, Debug)]
+pub enum VoteResolution {
+    /// まだ結果が確定していない
+    Pending,
+    /// 承認されて既出単語に追加され、手番がnext_user_idへ移った
+    Accepted {
+        user_id: u64,
+        word: String,
+        reading: String,
+        turn: u64,
+        tally: VoteTally,
+        next_user_id: u64,
+    },
+    /// 承認された単語でゲームが終了し、user_idが敗者になった
+    GameOver {
+        user_id: u64,
+        word: String,
+        reading: String,
+        turn: u64,
+        tally: VoteTally,
+    },
+    /// 否決され、手番は同じプレイヤーに戻った
+    Rejected {
+        user_id: u64,
+        word: String,
+        reading: String,
+        tally: VoteTally,
+    },
+}
+
+/// ---
+/// 制限時間の経過によって起きたルームの変化
+/// ---
+#[derive(PartialEq, Eq, Debug)]
+pub enum TimeoutEvent {
+    /// 投票の期限切れ、または異議なし期間の経過で投票が確定した
+    /// message_idは確定前に投票を表示していたメッセージ
+    VoteResolved {
+        room_id: u64,
+        message_id: Option<u64>,
+        resolution: VoteResolution,
+    },
+    /// 制限時間内に単語を投稿しなかったユーザーの手番を飛ばした
+    TurnSkipped {
+        room_id: u64,
+        user_id: u64,
+        next_user_id: u64,
+    },
+    /// 制限時間内に単語を投稿しなかったユーザーを脱落させた
+    /// 参加者がいなくなった場合はnext_user_idがNone
+    PlayerEliminated {
+        room_id: u64,
+        user_id: u64,
+        next_user_id: Option<u64>,
+    },
 }
 
+/// 制限時間の処理に失敗したルームのIDとエラー（そのルームの変更は取り消されています）
+pub type TimeoutFailure = (u64, RepoError);
+
 #[derive(Clone)]
 pub struct Repository {
     db: Arc<DataBase>,
@@ -97,6 +314,20 @@ impl Repository {
         Ok(Self { db: Arc::new(db) })
     }
 
+    /// 終了前にデータベースの内容をファイルへ書き戻します
+    pub async fn checkpoint(&self) -> Result<()> {
+        let result = self.db.checkpoint().await;
+        db_to_repo!(result, {})
+    }
+
+    /// ---
+    /// dirにデータベースのスナップショットを作成し、retentionより古い世代を削除します。
+    /// ---
+    pub async fn take_snapshot(&self, dir: &Path, retention: usize) -> Result<Snapshot> {
+        let result = self.db.take_snapshot(dir, retention).await;
+        db_to_repo!(result, {})
+    }
+
     /// repositoryにルームを作成します
     /// 
     /// エラー可能性: 
@@ -104,7 +335,7 @@ impl Repository {
     pub async fn create_room(&self, room_id: u64) -> Result<()> {
         let result = self
             .db
-            .execute("INSERT INTO rooms VALUES(?1)", wrap_params!(room_id))
+            .execute("INSERT INTO rooms (id) VALUES(?1)", wrap_params!(room_id))
             .await;
         db_to_repo!(result, {
             SQLITE_CONSTRAINT_PRIMARYKEY => RepoError::RoomAlreadyExists,
@@ -126,16 +357,162 @@ impl Repository {
     }
     
     /// ユーザー登録
+    /// 手番の順番では、手番のユーザーの次（手番がなければ最後）に入ります。
+    /// 
+    /// エラー可能性: 
+    /// RoomNotFound
+    /// UserAlreadyExists
     pub async fn add_user(&self, user_id: u64, room_id: u64) -> Result<usize> {
+        self.db
+            .transaction(TransactionMode::Immediate, move |tx| add_member_in(tx, room_id, user_id, None))
+            .await
+    }
+
+    /// ---
+    /// コンピューターの参加者をルームに追加します。
+    /// 手番の順番には人間の参加者と同じように入ります。
+    /// ---
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    /// UserAlreadyExists (すでにコンピューターが参加している)
+    pub async fn add_computer(&self, room_id: u64, difficulty: Difficulty) -> Result<()> {
+        self.db
+            .transaction(TransactionMode::Immediate, move |tx| add_member_in(tx, room_id, COMPUTER_USER_ID, Some(difficulty)))
+            .await?;
+        Ok(())
+    }
+
+    /// 参加しているコンピューターの強さを変更します
+    ///
+    /// エラー可能性:
+    /// UserNotFound (コンピューターが参加していない)
+    pub async fn set_computer_level(&self, room_id: u64, difficulty: Difficulty) -> Result<()> {
         let result = self
             .db
-            .execute("INSERT INTO room_members (room_id, user_id) VALUES(?1, ?2)", wrap_params!(room_id, user_id)).await;
-        
-        let success_count = db_to_repo!(result, {
-            SQLITE_CONSTRAINT_PRIMARYKEY => RepoError::UserAlreadyExists,
-        })?;
-        
-        Ok(success_count)
+            .execute(
+                "UPDATE room_members SET computer_level = ?3 WHERE room_id = ?1 AND user_id = ?2",
+                wrap_params!(room_id, COMPUTER_USER_ID, difficulty.as_str()),
+            )
+            .await;
+        match db_to_repo!(result, {})? {
+            0 => Err(RepoError::UserNotFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// ルームに参加しているコンピューターの強さを取得します（参加していなければNone）
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn get_computer_level(&self, room_id: u64) -> Result<Option<Difficulty>> {
+        self.db.transaction(TransactionMode::Read, move |tx| -> Result<Option<Difficulty>> {
+            ensure_room_exists(tx, room_id)?;
+            load_computer_level(tx, room_id)
+        }).await
+    }
+
+    /// ---
+    /// コンピューターが次に出す単語を辞書から選びます。
+    /// 直前の単語から続き、まだ出ていない単語のうち、ルームの制限（文字数・文字の種類）と
+    /// コンピューターの強さに合うものを選びます。ゲームを終了させる単語（「ん」で終わる単語など）は選びません。
+    /// 辞書が大きくても処理が重くならないよう、条件に合う単語を最大COMPUTER_CANDIDATE_LIMIT個読み込んだ中から選びます。
+    /// 出せる単語がない場合はNoneを返します。
+    /// ---
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    /// UserNotFound (コンピューターが参加していない)
+    pub async fn choose_computer_word(&self, room_id: u64) -> Result<Option<DictionaryEntry>> {
+        self.db.transaction(TransactionMode::Read, move |tx| -> Result<Option<DictionaryEntry>> {
+            let config = load_room_config(tx, room_id)?;
+            let rules = config.chain_rules;
+            let difficulty = load_computer_level(tx, room_id)?.ok_or(RepoError::UserNotFound)?;
+            let last_reading = load_last_reading(tx, room_id)?;
+
+            let heads = match &last_reading {
+                Some(prev) => match head_chars(prev, &rules) {
+                    heads if heads.is_empty() => return Ok(None),
+                    heads => heads,
+                },
+                // 最初の単語は何から始めてもよい
+                None => Vec::new(),
+            };
+            // 既出・長さ・文字の種類・「ん」はSQLで除き、残りの条件を確かめる
+            let candidates: Vec<DictionaryEntry> = sample_dictionary_words(tx, room_id, &heads, &config, difficulty)?
+                .into_iter()
+                .filter(|entry| {
+                    difficulty.knows(&entry.reading)
+                        && last_reading.as_ref().is_none_or(|prev| is_chained(prev, &entry.reading, &rules))
+                        && config.allowed_scripts.allows(&entry.surface)
+                        && config.is_long_enough(&entry.reading)
+                        && !config.n_ending.is_terminal(&entry.reading, &rules)
+                })
+                .collect();
+
+            // 続く単語の数は末尾の文字ごとに数える
+            let mut followers: HashMap<Vec<char>, u64> = HashMap::new();
+            let mut count_error = None;
+            let word = difficulty.choose(candidates, |entry| {
+                let heads = head_chars(&entry.reading, &rules);
+                if let Some(&count) = followers.get(&heads) {
+                    return count;
+                }
+                let count = count_readings_starting_with(tx, &heads).unwrap_or_else(|e| {
+                    count_error.get_or_insert(e);
+                    0
+                });
+                followers.insert(heads, count);
+                count
+            });
+            if let Some(e) = count_error {
+                return Err(e);
+            }
+            Ok(word)
+        }).await
+    }
+
+    /// ルームの参加者を参加順に取得します
+    /// 
+    /// エラー可能性: 
+    /// RoomNotFound
+    pub async fn get_members(&self, room_id: u64) -> Result<Vec<u64>> {
+        self.db.transaction(TransactionMode::Read, move |tx| -> Result<Vec<u64>> {
+            ensure_room_exists(tx, room_id)?;
+            let mut stmt = tx.prepare("SELECT user_id FROM room_members WHERE room_id = ?1 ORDER BY rowid")?;
+            let rows = stmt.query_map(wrap_params!(room_id), |row| row_to_u64(row, 0))?;
+            let list = rows.collect::<Result<Vec<_>, _>>()?;
+            Ok(list)
+        }).await
+    }
+
+    /// ルームからユーザーを削除します
+    /// 前後のユーザーのリンクはつなぎ直され、手番のユーザーだった場合は次のユーザーに手番が移ります。
+    /// 
+    /// エラー可能性: 
+    /// UserNotFound
+    pub async fn remove_user(&self, room_id: u64, user_id: u64) -> Result<()> {
+        self.db
+            .transaction(TransactionMode::Immediate, move |tx| remove_user_in(tx, room_id, user_id))
+            .await
+    }
+
+    /// 終了したゲームを同じ参加者で最初からやり直します
+    /// 既出単語と投票は削除されます。
+    /// 
+    /// エラー可能性: 
+    /// RoomNotFound
+    pub async fn restart_game(&self, room_id: u64) -> Result<()> {
+        self.db.transaction(TransactionMode::Immediate, move |tx| -> Result<()> {
+            ensure_room_exists(tx, room_id)?;
+            tx.execute("DELETE FROM room_votes WHERE room_id = ?1", wrap_params!(room_id))?;
+            tx.execute("DELETE FROM room_words WHERE room_id = ?1", wrap_params!(room_id))?;
+            tx.execute(
+                "UPDATE rooms SET status = 'active', loser_id = NULL, finished_at = NULL WHERE id = ?1",
+                wrap_params!(room_id),
+            )?;
+            Ok(())
+        }).await
     }
 
     /// repositoryのルームに既出単語を追加します
@@ -144,33 +521,36 @@ impl Repository {
     /// WordAlreadyExists
     /// RoomNotFound
     /// NullWord
+    /// ChainMismatch
     pub async fn insert_word(&self, room_id: u64, word: &str) -> Result<usize> {
-        if word.len() == 0 {
+        self.record_word(room_id, None, word, VoteTally::default()).await?;
+        Ok(1)
+    }
+
+    /// 出したユーザーと投票結果を含めて既出単語を追加し、割り当てられた手番を返します
+    /// 
+    /// エラー可能性: 
+    /// WordAlreadyExists
+    /// RoomNotFound
+    /// NullWord
+    /// ChainMismatch
+    pub async fn record_word(&self, room_id: u64, user_id: Option<u64>, word: &str, tally: VoteTally) -> Result<u64> {
+        if word.is_empty() {
             return Err(RepoError::NullWord);
         }
 
-        let result = self
-            .db
-            .execute(
-                "INSERT INTO room_words VALUES(?1, ?2)",
-                wrap_params!(room_id, word),
-            )
-            .await;
-
-        let inserted_count = db_to_repo!(result, {
-            SQLITE_CONSTRAINT_PRIMARYKEY => RepoError::WordAlreadyExists,
-            SQLITE_CONSTRAINT_FOREIGNKEY => RepoError::RoomNotFound,
-            SQLITE_CONSTRAINT_NOTNULL => RepoError::NullWord
-        })?;
-
-        Ok(inserted_count)
+        let word = word.to_string();
+        self.db.transaction(TransactionMode::Immediate, move |tx| -> Result<u64> {
+            let word = ensure_word_playable(tx, room_id, &word)?;
+            insert_word_record(tx, room_id, user_id, &word, tally)
+        }).await
     }
 
     /// repositoryに登録されているルームのリストを取得します
     pub async fn get_rooms(&self) -> Result<Vec<u64>> {
         let result = self
             .db
-            .query_map("SELECT id FROM rooms", [], |row| {
+            .query("SELECT id FROM rooms", [], |row| {
                 let room_id_i64: i64 = row.get(0)?;
                 Ok(i64_to_u64_bitwise(room_id_i64))
             })
@@ -187,33 +567,217 @@ impl Repository {
     /// RoomNotFound
     /// JoinError
     pub async fn get_words(&self, room_id: u64) -> Result<Vec<String>> {
-        let list_result = self.db.exclusive_transaction(move |tx| -> Result<Vec<String>, TxError> {
-            let _room_result = tx
+        self.db.transaction(TransactionMode::Read, move |tx| -> Result<Vec<String>> {
+            ensure_room_exists(tx, room_id)?;
+            let list = tx.query(
+                "SELECT word FROM room_words WHERE room_id = ?1 ORDER BY turn",
+                wrap_params!(room_id),
+                |row| row.get::<_, String>(0),
+            )?;
+            Ok(list)
+        }).await
+    }
+
+    /// ルームの最後に承認された単語を取得します
+    /// 
+    /// エラー可能性: 
+    /// RoomNotFound
+    pub async fn get_last_word(&self, room_id: u64) -> Result<Option<WordRecord>> {
+        self.db.transaction(TransactionMode::Read, move |tx| -> Result<Option<WordRecord>> {
+            ensure_room_exists(tx, room_id)?;
+            let record = tx
                 .query_row(
-                    "SELECT id FROM rooms WHERE id = ?1",
+                    &format!("SELECT {} FROM room_words WHERE room_id = ?1 ORDER BY turn DESC LIMIT 1", WORD_RECORD_COLUMNS),
                     wrap_params!(room_id),
-                    |f| f.get::<_, i64>(0),
+                    row_to_word_record,
                 )
-                .map_err(|_| RepoError::RoomNotFound)?;
-            
-            let mut stmt = tx
-                .prepare("SELECT word FROM room_words WHERE room_id = ?1")?;
-
-            let rows = stmt
-                .query_map(wrap_params!(room_id), |row| row.get::<_,String>(0))?;
+                .optional()?;
+            Ok(record)
+        }).await
+    }
 
-            let list = rows.collect::<Result<Vec<_>,_>>()?;
+    /// ルームの単語履歴を新しい順にページ単位で取得します
+    /// pageは0始まりで、0が最新のページです。
+    /// 
+    /// エラー可能性: 
+    /// RoomNotFound
+    pub async fn get_history_page(&self, room_id: u64, page: u64, per_page: u64) -> Result<Vec<WordRecord>> {
+        self.db.transaction(TransactionMode::Read, move |tx| -> Result<Vec<WordRecord>> {
+            ensure_room_exists(tx, room_id)?;
+            let mut stmt = tx.prepare(&format!(
+                "SELECT {} FROM room_words WHERE room_id = ?1 ORDER BY turn DESC LIMIT ?2 OFFSET ?3",
+                WORD_RECORD_COLUMNS
+            ))?;
+            let rows = stmt.query_map(
+                wrap_params!(room_id, per_page, page.saturating_mul(per_page)),
+                row_to_word_record,
+            )?;
+            let list = rows.collect::<Result<Vec<_>, _>>()?;
+            Ok(list)
+        }).await
+    }
 
+    /// ルームで指定したユーザーが出した単語を古い順に取得します
+    /// 
+    /// エラー可能性: 
+    /// RoomNotFound
+    pub async fn get_words_by_user(&self, room_id: u64, user_id: u64) -> Result<Vec<WordRecord>> {
+        self.db.transaction(TransactionMode::Read, move |tx| -> Result<Vec<WordRecord>> {
+            ensure_room_exists(tx, room_id)?;
+            let mut stmt = tx.prepare(&format!(
+                "SELECT {} FROM room_words WHERE room_id = ?1 AND user_id = ?2 ORDER BY turn",
+                WORD_RECORD_COLUMNS
+            ))?;
+            let rows = stmt.query_map(wrap_params!(room_id, user_id), row_to_word_record)?;
+            let list = rows.collect::<Result<Vec<_>, _>>()?;
             Ok(list)
-        }).await;
-        
-        match list_result {
-            Ok(list) => Ok(list),
-            Err(TxError::Repo(e)) => Err(e),
-            Err(TxError::JoinError(e)) => Err(RepoError::from(e)),
-            Err(TxError::Sqlite(e)) => Err(RepoError::from(e)), // TODO: 実際のエラーキャッチを考えてない
-            Err(TxError::Database(e)) => db_to_repo!(Err(e), {}),
-        }
+        }).await
+    }
+
+    /// ---
+    /// ルームの履歴（単語・投稿者・承認日時・投票結果・ゲーム状態）と、ルームに保存された設定を書き出します。
+    /// ---
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn export_room(&self, room_id: u64) -> Result<RoomArchive> {
+        self.db.transaction(TransactionMode::Read, move |tx| -> Result<RoomArchive> {
+            let (status, loser_id, finished_at) = tx
+                .query_row(
+                    "SELECT status, loser_id, finished_at FROM rooms WHERE id = ?1",
+                    wrap_params!(room_id),
+                    |row| {
+                        Ok((
+                            row.get::<_, String>(0)?,
+                            row.get::<_, Option<i64>>(1)?.map(i64_to_u64_bitwise),
+                            row.get::<_, Option<String>>(2)?,
+                        ))
+                    },
+                )
+                .optional()?
+                .ok_or(RepoError::RoomNotFound)?;
+            let status = RoomStatus::parse(&status)
+                .ok_or_else(|| RepoError::Other(anyhow::anyhow!("不明なゲーム状態: {}", status)))?;
+
+            let mut stmt = tx.prepare(&format!(
+                "SELECT {} FROM room_words WHERE room_id = ?1 ORDER BY turn",
+                WORD_RECORD_COLUMNS
+            ))?;
+            let words = stmt
+                .query_map(wrap_params!(room_id), row_to_word_record)?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(RoomArchive {
+                room_id,
+                status,
+                loser_id,
+                finished_at: finished_at.and_then(|s| NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S").ok()),
+                settings: load_overrides(tx, SettingsScope::Room, room_id)?.unwrap_or_default(),
+                words,
+            })
+        }).await
+    }
+
+    /// ---
+    /// 書き出した履歴からroom_idのルームを作成し、取り込んだ単語の数を返します。
+    /// 単語は手番の順に、前の単語から続くことと既出でないことを確かめながら取り込みます（手番は1から振り直します）。
+    /// 参加者と成績は取り込みません。途中でエラーになった場合は何も取り込みません。
+    /// ---
+    ///
+    /// エラー可能性:
+    /// RoomAlreadyExists
+    /// NullWord
+    /// WordAlreadyExists
+    /// ChainMismatch
+    pub async fn import_room(&self, room_id: u64, archive: RoomArchive) -> Result<u64> {
+        self.db.transaction(TransactionMode::Immediate, move |tx| -> Result<u64> {
+            let result = tx
+                .execute("INSERT INTO rooms (id) VALUES(?1)", wrap_params!(room_id))
+                .map_err(DatabaseError::from);
+            db_to_repo!(result, {
+                SQLITE_CONSTRAINT_PRIMARYKEY => RepoError::RoomAlreadyExists,
+            })?;
+            if !archive.settings.is_empty() {
+                store_overrides(tx, SettingsScope::Room, room_id, &archive.settings)?;
+            }
+            // 単語のつながりは、取り込んだ設定の接続判定で確かめる
+            let rules = load_room_config(tx, room_id)?.chain_rules;
+
+            let mut words: Vec<&WordRecord> = archive.words.iter().collect();
+            words.sort_by_key(|record| record.turn);
+            let mut used = HashSet::new();
+            let mut last_reading: Option<String> = None;
+            for (turn, record) in (1u64..).zip(words) {
+                let word = PlayableWord {
+                    surface: record.word.trim().to_string(),
+                    reading: normalize_reading(&record.reading),
+                };
+                if word.surface.is_empty() || word.reading.is_empty() {
+                    return Err(RepoError::NullWord);
+                }
+                if !used.insert(word.key()) {
+                    return Err(RepoError::WordAlreadyExists);
+                }
+                if let Some(last_reading) = &last_reading
+                    && !is_chained(last_reading, &word.reading, &rules)
+                {
+                    return Err(RepoError::ChainMismatch);
+                }
+
+                tx.execute(
+                    "INSERT INTO room_words (room_id, word, reading, word_key, turn, user_id, approved_at, good_count, bad_count)
+                     VALUES(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    wrap_params!(
+                        room_id,
+                        word.surface.as_str(),
+                        word.reading.as_str(),
+                        word.key(),
+                        turn,
+                        record.user_id,
+                        record.approved_at.map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string()),
+                        record.tally.good,
+                        record.tally.bad
+                    ),
+                )?;
+                last_reading = Some(word.reading);
+            }
+
+            tx.execute(
+                "UPDATE rooms SET status = ?2, loser_id = ?3, finished_at = ?4 WHERE id = ?1",
+                wrap_params!(
+                    room_id,
+                    archive.status.as_str(),
+                    archive.loser_id,
+                    archive.finished_at.map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string())
+                ),
+            )?;
+            Ok(used.len() as u64)
+        }).await
+    }
+
+    /// ---
+    /// ユーザーの発言を単語として受け付ける状態かどうかを、読み取り用の接続で調べます。
+    /// 進行中のルームの参加者で、投票中でなく、手番がそのユーザーである（手番がまだなければ誰でも）場合にtrueを返します。
+    /// ゲームと関係のない発言でadd_vote_stateの書き込みのトランザクションを開かないよう、先に呼び出します。
+    /// ---
+    pub async fn is_awaiting_word(&self, room_id: u64, user_id: u64) -> Result<bool> {
+        let result = self
+            .db
+            .query(
+                "SELECT EXISTS (
+                     SELECT 1 FROM rooms r
+                     JOIN room_members m ON m.room_id = r.id AND m.user_id = ?2
+                     LEFT JOIN room_votes v ON v.room_id = r.id
+                     WHERE r.id = ?1 AND r.status = 'active'
+                       AND (v.room_id IS NULL OR (v.current_user_id = ?2 AND v.word IS NULL))
+                 )",
+                wrap_params!(room_id, user_id),
+                |row| row.get::<_, bool>(0),
+            )
+            .await;
+
+        let list = db_to_repo!(result, {})?;
+        Ok(list.first().copied().unwrap_or(false))
     }
 
     /// ルームの投票状態を作成します
@@ -221,31 +785,57 @@ impl Repository {
     /// エラー可能性: 
     /// NotFirstUser
     /// RoomNotFound
+    /// UserNotFound
+    /// VoteInProgress
     /// WordAlreadyExists
+    /// ChainMismatch
     pub async fn add_vote_state(&self, room_id: u64, user_id: u64, word: &str) -> Result<()> {
         let word = word.to_string();
-        self.db.exclusive_transaction(move |tx| -> Result<()> {
-            let current_user_result = tx
-                .query_one(
-                    "SELECT current_user_id FROM room_votes WHERE room_id = ?1",
+        self.db.transaction(TransactionMode::Immediate, move |tx| -> Result<()> {
+            ensure_room_exists(tx, room_id)?;
+            tx.query_row(
+                "SELECT 1 FROM room_members WHERE room_id = ?1 AND user_id = ?2",
+                wrap_params!(room_id, user_id),
+                |_| Ok(()),
+            )
+            .optional()?
+            .ok_or(RepoError::UserNotFound)?;
+
+            // 手番のユーザーのみ投票を作成できる（投票がまだなければ誰でも可）
+            let current_vote = tx
+                .query_row(
+                    "SELECT current_user_id, word FROM room_votes WHERE room_id = ?1",
                     wrap_params!(room_id),
-                    |row| Ok(row_to_u64(row, 0).map_err(RepoError::from)))
-                    .map_err(DatabaseError::from);
-            
-            match current_user_result {
-                Ok(row_result) => {
-                    let current_user_id = row_result?;
-                    if user_id != current_user_id {
-                        return Err(RepoError::NotFirstUser);
-                    }
+                    |row| Ok((row_to_u64(row, 0)?, row.get::<_, Option<String>>(1)?)),
+                )
+                .optional()?;
+
+            if let Some((current_user_id, current_word)) = current_vote {
+                if user_id != current_user_id {
+                    return Err(RepoError::NotFirstUser);
+                }
+                if current_word.is_some() {
+                    return Err(RepoError::VoteInProgress);
                 }
-                Err(DatabaseError::Sqlite(SqliteError::QueryReturnedNoRows)) => {/* チェックを無視して続行 */}
-                Err(e) => return Err(db_to_repo!(Err(e), {})?)
             }
+
+            let word = ensure_word_playable(tx, room_id, &word)?;
+
+            // 手番が回ってきた時刻（単語のない投票の最終更新）から回答までの秒数
+            let response_secs = tx
+                .query_row(
+                    "SELECT CAST(strftime('%s', 'now') - strftime('%s', updated_at) AS INTEGER)
+                     FROM room_votes WHERE room_id = ?1 AND word IS NULL",
+                    wrap_params!(room_id),
+                    |row| row.get::<_, Option<i64>>(0),
+                )
+                .optional()?
+                .flatten();
             
             let insert_result = tx.execute(
-                "INSERT OR REPLACE INTO room_votes (room_id, current_user_id, word) VALUES(?1, ?2, ?3)",
-                wrap_params!(room_id, user_id, word)
+                "INSERT OR REPLACE INTO room_votes (room_id, current_user_id, word, reading, word_key, response_secs)
+                 VALUES(?1, ?2, ?3, ?4, ?5, ?6)",
+                wrap_params!(room_id, user_id, word.surface.as_str(), word.reading.as_str(), word.key(), response_secs)
             )
             .map_err(DatabaseError::from);
             
@@ -256,6 +846,17 @@ impl Repository {
                 SQLITE_CONSTRAINT_TRIGGER => RepoError::WordAlreadyExists,
             })?;
 
+            // INSERT OR REPLACEでは投票変更時のトリガーが動かないため、前回の投票状態をここで消す
+            tx.execute(
+                "UPDATE room_members SET state = 'none' WHERE room_id = ?1",
+                wrap_params!(room_id),
+            )?;
+            // コンピューターは他の参加者の単語に自動で賛成する
+            tx.execute(
+                "UPDATE room_members SET state = 'good' WHERE room_id = ?1 AND user_id != ?2 AND computer_level IS NOT NULL",
+                wrap_params!(room_id, user_id),
+            )?;
+
             Ok(())
         }).await?;
 
@@ -264,31 +865,33 @@ impl Repository {
 
     /// ルームの投票状態を取得します
     pub async fn get_vote_state(&self, room_id: u64) -> Result<Option<Vote>> {
-        let vote_optional: Option<Vote> = self.db.exclusive_transaction(move |tx| -> Result<Option<Vote>> {
+        let vote_optional: Option<Vote> = self.db.transaction(TransactionMode::Read, move |tx| -> Result<Option<Vote>> {
             // 基本投票取得
             let room_vote_optional= tx
                 .query_row(
-                    "SELECT room_id, current_user_id, word, updated_at FROM room_votes WHERE room_id = ?1",
+                    "SELECT room_id, current_user_id, word, updated_at, message_id, reading FROM room_votes WHERE room_id = ?1",
                     wrap_params!(room_id),
                     |row| {
                         let room_id = row_to_u64(row, 0)?;
                         let current_user_id = row_to_u64(row, 1)?;
                         let word = row.get::<_, Option<String>>(2)?;
                         let updated_at = row.get::<_, String>(3)?;
+                        let message_id = row.get::<_, Option<i64>>(4)?.map(i64_to_u64_bitwise);
+                        let reading = row.get::<_, Option<String>>(5)?;
                         
-                        Ok((room_id, current_user_id, word, updated_at))
+                        Ok((room_id, current_user_id, word, reading, updated_at, message_id))
                     }
                 )
                 .optional()?;
             
-            let (room_id, current_user_id, word, updated_at_str) = match room_vote_optional {
+            let (room_id, current_user_id, word, reading, updated_at_str, message_id) = match room_vote_optional {
                 Some(v) => v,
                 None => return Ok(None)
             };
             let updated_at = NaiveDateTime::parse_from_str(&updated_at_str, "%Y-%m-%d %H:%M:%S").ok();
             
             // 投票状態取得
-            let sql = "SELECT user_id FROM member_votes WHERE room_id = ?1 AND state = ?2";
+            let sql = "SELECT user_id FROM room_members WHERE room_id = ?1 AND state = ?2";
             let mut stmt = tx.prepare(sql)?;
             
             let vote_list: Vec<Vec<u64>> = ["good", "bad", "none"]
@@ -311,10 +914,12 @@ impl Repository {
                 user_id:
                 current_user_id,
                 word,
-                good: vote_list.get(0).unwrap().to_vec(),
+                reading,
+                good: vote_list.first().unwrap().to_vec(),
                 bad: vote_list.get(1).unwrap().to_vec(),
                 none: vote_list.get(2).unwrap().to_vec(),
-                updated_at 
+                updated_at,
+                message_id,
             }))
         }).await?;
 
@@ -327,549 +932,3294 @@ impl Repository {
     /// RoomNotFound
     /// VoteNotExists
     /// InvalidVoteState
+    /// UserNotFound
     pub async fn vote(&self, room_id: u64, user_id: u64, state: &str) -> Result<()> {
-        let result = self.db.execute(
-            "UPDATE room_members SET state = ?3 WHERE room_id = ?1 AND user_id = ?2",
-            wrap_params![room_id as i64, user_id as i64, state],
-        ).await;
-        
-        let _update_count = db_to_repo!(result, {
-            // TODO: room_id / user_id エラー可能性
-            SQLITE_CONSTRAINT_FOREIGNKEY => RepoError::RoomNotFound,
-            SQLITE_CONSTRAINT_CHECK => RepoError::InvalidVoteState,
-            SQLITE_CONSTRAINT_TRIGGER => RepoError::VoteNotExists,
-        })?;
-        
-        Ok(())
-    }
-
-    pub async fn set_queue(&self, room_id: u64, queue: Vec<u64>) -> Result<(), RepoError> {
-        let result = self.db.exclusive_transaction(move |tx| -> Result<(), RepoError> {
-            // ユーザー存在確認
-            let placeholders = queue.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
-            let users_check_sql = format!(
-                "SELECT COUNT(*) FROM room_members WHERE room_id = ?1 AND user_id IN ({})",
-                placeholders
-            );
-            let found_count = {
-                let mut stmt = tx.prepare(&users_check_sql)?;
-                let mut params: Vec<&dyn rusqlite::ToSql> = vec![&room_id];
-                for user_id in &queue {
-                    params.push(user_id);
-                }
-                stmt.query_row(rusqlite::params_from_iter(params), |row| row.get::<_, i64>(0))?
-            };
-            if found_count != queue.len() as i64 {
-                return Err(RepoError::UserNotFound);
+        let state = state.to_string();
+        self.db.transaction(TransactionMode::Immediate, move |tx| -> Result<()> {
+            // 単語が提出されていない（手番待ちの）状態では投票できない
+            let word = tx
+                .query_row(
+                    "SELECT word FROM room_votes WHERE room_id = ?1",
+                    wrap_params!(room_id),
+                    |row| row.get::<_, Option<String>>(0),
+                )
+                .optional()?
+                .flatten();
+            if word.is_none() {
+                return Err(RepoError::VoteNotExists);
             }
 
-            // 既存のリンクをクリア
-            let clear_result = tx.execute(
-                "UPDATE room_members SET prev = NULL, next = NULL WHERE room_id = ?1",
-                wrap_params![room_id],
-            );
+            let result = tx.execute(
+                "UPDATE room_members SET state = ?3 WHERE room_id = ?1 AND user_id = ?2",
+                wrap_params![room_id as i64, user_id as i64, state.as_str()],
+            )
+            .map_err(DatabaseError::from);
             
-            if queue.len() == 0 {
-                // 次のユーザー未定義
-                return Ok(());
+            let update_count = db_to_repo!(result, {
+                SQLITE_CONSTRAINT_FOREIGNKEY => RepoError::RoomNotFound,
+                SQLITE_CONSTRAINT_CHECK => RepoError::InvalidVoteState,
+                SQLITE_CONSTRAINT_TRIGGER => RepoError::VoteNotExists,
+            })?;
+            if update_count == 0 {
+                return Err(RepoError::UserNotFound);
             }
+            
+            Ok(())
+        }).await
+    }
 
-            for i in 0..queue.len() {
-                let user_id = queue[i];
-                let prev_user_id = if i == 0 { queue[queue.len() - 1] } else { queue[i - 1] };
-                let next_user_id = if i + 1 == queue.len() { queue[0] } else { queue[i + 1] };
-
-                let update_result = tx.execute(
-                    "UPDATE room_members SET prev = ?2, next = ?3 WHERE room_id = ?1 AND user_id = ?4",
-                    wrap_params![
-                        room_id,
-                        prev_user_id,
-                        next_user_id,
-                        user_id
-                    ],
-                )?;
-            }
+    /// 投票を表示しているメッセージを記録します
+    ///
+    /// エラー可能性
+    /// VoteNotExists
+    pub async fn set_vote_message(&self, room_id: u64, message_id: u64) -> Result<()> {
+        let result = self
+            .db
+            .execute(
+                "UPDATE room_votes SET message_id = ?2 WHERE room_id = ?1 AND word IS NOT NULL",
+                wrap_params!(room_id, message_id),
+            )
+            .await;
 
-            Ok(())
-        }).await;
-        
+        if db_to_repo!(result, {})? == 0 {
+            return Err(RepoError::VoteNotExists);
+        }
         Ok(())
     }
 
-    /*
-    pub async fn get_queue(&self, room_id: u64) -> Result<Vec<u64>> {
-        self.db
-            .exclusive_transaction(move |tx| {
-                // ルームがないときは空配列
-                if let Err(_) = tx.query_row("SELECT id FROM rooms WHERE id = ?1", [room_id], |_r| Ok(())) {
-                    return Ok(Vec::<u64>::new());
-                }
+    /// ルームのゲーム状態を取得します
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn get_room_status(&self, room_id: u64) -> Result<RoomStatus> {
+        let result = self
+            .db
+            .query("SELECT status FROM rooms WHERE id = ?1", wrap_params!(room_id), |row| {
+                row.get::<_, String>(0)
+            })
+            .await;
 
-                let current_user= match tx.query_row(
-                    "SELECT current_user_id FROM room_votes WHERE room_id = ?1",
-                    [room_id],
-                    |row| {
-                        let i: i64 = row.get(0)?;
-                        Ok(i64_to_u64_bitwise(i))
-                    },
-                ) {
-                    Ok(id) => id,
-                    Err(rusqlite::Error::QueryReturnedNoRows) => { return Ok(Vec::<u64>::new()); },
-                    Err(e) => return Err(e.into()),
-                };
+        let list = db_to_repo!(result, {})?;
+        let status = list.first().ok_or(RepoError::RoomNotFound)?;
+        RoomStatus::parse(status)
+            .ok_or_else(|| RepoError::Other(anyhow::anyhow!("不明なルーム状態: {}", status)))
+    }
 
+    /// 進行中のゲームを一時停止します
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    /// GameNotActive
+    pub async fn pause_game(&self, room_id: u64) -> Result<()> {
+        let result = self
+            .db
+            .execute(
+                "UPDATE rooms SET status = 'paused' WHERE id = ?1 AND status = 'active'",
+                wrap_params!(room_id),
+            )
+            .await;
 
-                let mut stmt = tx.prepare(
-                    "SELECT user_id, prev, next FROM room_members WHERE room_id = ?1",
-                )?;
-                let rows = stmt.query_map([room_id], |row| {
-                    let i: i64 = row.get(0)?;
-                    let u0 = i64_to_u64_bitwise(i);
+        if db_to_repo!(result, {})? == 0 {
+            self.get_room_status(room_id).await?;
+            return Err(RepoError::GameNotActive);
+        }
+        Ok(())
+    }
 
-                    Ok((
-                        u0,
-                        row.get::<_, Option<i64>>(1)?.map(i64_to_u64_bitwise),
-                        row.get::<_, Option<i64>>(2)?.map(i64_to_u64_bitwise),
-                    ))
-                }).map_err(|_e| RepoError::BrokenChain)?;
+    /// 一時停止中のゲームを再開します
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    /// GameNotPaused
+    pub async fn resume_game(&self, room_id: u64) -> Result<()> {
+        let result = self
+            .db
+            .execute(
+                "UPDATE rooms SET status = 'active' WHERE id = ?1 AND status = 'paused'",
+                wrap_params!(room_id),
+            )
+            .await;
+
+        if db_to_repo!(result, {})? == 0 {
+            self.get_room_status(room_id).await?;
+            return Err(RepoError::GameNotPaused);
+        }
+        Ok(())
+    }
 
+    /// ゲームを終了し、敗者を記録します
+    /// 進行中の投票は破棄されます。
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    /// GameNotActive (すでに終了している)
+    pub async fn finish_game(&self, room_id: u64, loser_id: Option<u64>) -> Result<()> {
+        self.db
+            .transaction(TransactionMode::Immediate, move |tx| finish_game_in(tx, room_id, loser_id))
+            .await
+    }
 
-                use std::collections::{HashMap, HashSet};
-                let mut links: HashMap<u64, (Option<u64>, Option<u64>)> = HashMap::new();
+    /// 終了したゲームの結果を取得します
+    /// ゲームが終了していない場合はNoneを返します。
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn get_game_result(&self, room_id: u64) -> Result<Option<GameResult>> {
+        let result = self
+            .db
+            .query(
+                "SELECT status, loser_id, finished_at FROM rooms WHERE id = ?1",
+                wrap_params!(room_id),
+                |row| {
+                    let status = row.get::<_, String>(0)?;
+                    let loser_id = row.get::<_, Option<i64>>(1)?.map(i64_to_u64_bitwise);
+                    let finished_at = row.get::<_, Option<String>>(2)?;
+                    Ok((status, loser_id, finished_at))
+                },
+            )
+            .await;
 
-                for row in rows {
-                    let (user_id, prev, next) = row?;
-                    links.insert(user_id, (prev, next));
-                }
+        let list = db_to_repo!(result, {})?;
+        let (status, loser_id, finished_at) = list.into_iter().next().ok_or(RepoError::RoomNotFound)?;
+        if RoomStatus::parse(&status) != Some(RoomStatus::Finished) {
+            return Ok(None);
+        }
 
-                let mut queue = Vec::new();
-                let mut visited = HashSet::new();
-                let mut cursor = Some(current_user);
+        Ok(Some(GameResult {
+            room_id,
+            loser_id,
+            finished_at: finished_at
+                .and_then(|s| NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S").ok()),
+        }))
+    }
 
-                while let Some(user_id) = cursor {
-                    // すでに訪問済みなら循環 → 終了
-                    if !visited.insert(user_id) {
-                        break;
-                    }
+    /// ---
+    /// 範囲と期間に含まれる全ユーザーの成績を取得します。
+    /// 成績の記録がないユーザーは含まれません。
+    /// ---
+    pub async fn get_player_stats(&self, scope: StatsScope, window: TimeWindow) -> Result<Vec<PlayerStats>> {
+        self.db
+            .transaction(TransactionMode::Read, move |tx| load_player_stats(tx, scope, window, None))
+            .await
+    }
 
-                    queue.push(user_id);
+    /// ---
+    /// 範囲と期間に含まれるユーザーの成績を取得します。
+    /// 成績の記録がない場合はすべて0の成績を返します。
+    /// ---
+    pub async fn get_user_stats(&self, scope: StatsScope, window: TimeWindow, user_id: u64) -> Result<PlayerStats> {
+        self.db
+            .transaction(TransactionMode::Read, move |tx| {
+                let stats = load_player_stats(tx, scope, window, Some(user_id))?;
+                Ok(stats.into_iter().next().unwrap_or(PlayerStats { user_id, ..Default::default() }))
+            })
+            .await
+    }
 
-                    // 次のユーザーへ進む
-                    if let Some((_, next)) = links.get(&user_id) {
-                        cursor = *next;
-                    }
-                }
+    /// ルームの「ん」で終わる単語の扱いを取得します
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn get_n_ending_rule(&self, room_id: u64) -> Result<NEndingRule> {
+        Ok(self.get_room_config(room_id).await?.n_ending)
+    }
 
-                if queue.len() < links.len() {
-                    return Err(RepoError::BrokenChain.into());
-                }
+    /// ルームの「ん」で終わる単語の扱いを変更します
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn set_n_ending_rule(&self, room_id: u64, rule: NEndingRule) -> Result<()> {
+        let patch = ConfigOverrides { n_ending: Some(rule), ..Default::default() };
+        self.update_room_settings(room_id, patch).await
+    }
 
-                Ok(queue)
-            })
+    /// wordが承認された時点でゲームが終了するかどうかを、ルームのルールに従って判定します
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn is_terminal_word(&self, room_id: u64, word: &str) -> Result<bool> {
+        let config = self.get_room_config(room_id).await?;
+        Ok(config.n_ending.is_terminal(word, &config.chain_rules))
+    }
+
+    /// ルームの投票確定ルールを取得します
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn get_vote_rule(&self, room_id: u64) -> Result<VoteRule> {
+        Ok(self.get_room_config(room_id).await?.vote_rule)
+    }
+
+    /// ルームの投票確定ルールを変更します
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn set_vote_rule(&self, room_id: u64, rule: VoteRule) -> Result<()> {
+        let patch = ConfigOverrides { vote_rule: Some(rule), ..Default::default() };
+        self.update_room_settings(room_id, patch).await
+    }
+
+    /// ルームの制限時間を取得します
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn get_time_limits(&self, room_id: u64) -> Result<TimeLimits> {
+        Ok(self.get_room_config(room_id).await?.time_limits)
+    }
+
+    /// ルームの制限時間を変更します
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn set_time_limits(&self, room_id: u64, limits: TimeLimits) -> Result<()> {
+        let patch = ConfigOverrides::default().with_time_limits(limits);
+        self.update_room_settings(room_id, patch).await
+    }
+
+    /// ---
+    /// ルームで使われる設定を取得します。
+    /// 既定値にサーバーの設定、ルームの設定の順で重ねたものです。
+    /// ---
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn get_room_config(&self, room_id: u64) -> Result<RoomConfig> {
+        self.db
+            .transaction(TransactionMode::Read, move |tx| load_room_config(tx, room_id))
             .await
-            .map_err(Into::into)
     }
 
+    /// ルームに保存されている設定（サーバーから引き継ぐ項目はNone）を取得します
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn get_room_settings(&self, room_id: u64) -> Result<ConfigOverrides> {
+        self.db.transaction(TransactionMode::Read, move |tx| -> Result<ConfigOverrides> {
+            ensure_room_exists(tx, room_id)?;
+            Ok(load_overrides(tx, SettingsScope::Room, room_id)?.unwrap_or_default())
+        }).await
+    }
+
+    /// ルームの設定のうち、patchで指定した項目を変更します
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn update_room_settings(&self, room_id: u64, patch: ConfigOverrides) -> Result<()> {
+        self.db.transaction(TransactionMode::Immediate, move |tx| -> Result<()> {
+            ensure_room_exists(tx, room_id)?;
+            let mut settings = load_overrides(tx, SettingsScope::Room, room_id)?.unwrap_or_default();
+            settings.merge(&patch);
+            store_overrides(tx, SettingsScope::Room, room_id, &settings)
+        }).await
+    }
 
-    pub async fn next_user(&self, room_id: u64) -> Result<()> {
-        self.db.exclusive_transaction(move |tx| {
-            // 現在のユーザーを取得
-            let current: Option<i64> = match tx.query_row(
-                "SELECT current_user_id FROM room_votes WHERE room_id = ?1",
-                [room_id as i64],
-                |row| row.get(0),
-            ) {
-                Ok(v) => Some(v),
-                Err(rusqlite::Error::QueryReturnedNoRows) => None,
-                Err(e) => return Err(e.into()),
-            };
+    /// ルームの設定をすべて消し、サーバーの設定を引き継ぐようにします
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn reset_room_settings(&self, room_id: u64) -> Result<()> {
+        self.db.transaction(TransactionMode::Immediate, move |tx| -> Result<()> {
+            ensure_room_exists(tx, room_id)?;
+            store_overrides(tx, SettingsScope::Room, room_id, &ConfigOverrides::default())
+        }).await
+    }
+
+    /// ルームが設定を引き継ぐサーバーを登録します
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn set_room_guild(&self, room_id: u64, guild_id: u64) -> Result<()> {
+        let result = self
+            .db
+            .execute(
+                "INSERT INTO room_settings (room_id, guild_id) VALUES(?1, ?2)
+                 ON CONFLICT(room_id) DO UPDATE SET guild_id = excluded.guild_id",
+                wrap_params!(room_id, guild_id),
+            )
+            .await;
+        db_to_repo!(result, {
+            SQLITE_CONSTRAINT_FOREIGNKEY => RepoError::RoomNotFound,
+        })?;
+        Ok(())
+    }
+
+    /// サーバーの設定を取得します（未設定の項目はNone）
+    pub async fn get_guild_settings(&self, guild_id: u64) -> Result<ConfigOverrides> {
+        self.db.transaction(TransactionMode::Read, move |tx| -> Result<ConfigOverrides> {
+            Ok(load_overrides(tx, SettingsScope::Guild, guild_id)?.unwrap_or_default())
+        }).await
+    }
+
+    /// サーバーの設定のうち、patchで指定した項目を変更します
+    pub async fn update_guild_settings(&self, guild_id: u64, patch: ConfigOverrides) -> Result<()> {
+        self.db.transaction(TransactionMode::Immediate, move |tx| -> Result<()> {
+            let mut settings = load_overrides(tx, SettingsScope::Guild, guild_id)?.unwrap_or_default();
+            settings.merge(&patch);
+            store_overrides(tx, SettingsScope::Guild, guild_id, &settings)
+        }).await
+    }
+
+    /// サーバーの設定をすべて消し、既定値に戻します
+    pub async fn reset_guild_settings(&self, guild_id: u64) -> Result<()> {
+        let result = self
+            .db
+            .execute("DELETE FROM guild_settings WHERE guild_id = ?1", wrap_params!(guild_id))
+            .await;
+        db_to_repo!(result, {})?;
+        Ok(())
+    }
+
+    /// ---
+    /// 進行中のすべてのルームの投票と手番を調べ、制限時間を過ぎたものを処理します。
+    /// 投票は期限を過ぎていればルームのルールで確定させ、期限前でも異議なし期間の経過などで
+    /// 確定していれば反映します。手番は制限時間を過ぎたユーザーを飛ばすか脱落させます。
+    /// ルームごとにセーブポイントで処理し、処理に失敗したルームは変更を取り消して飛ばします。
+    /// 起きた変化と、処理に失敗したルームを返します。
+    /// ---
+    pub async fn process_timeouts(&self) -> Result<(Vec<TimeoutEvent>, Vec<TimeoutFailure>)> {
+        self.db.transaction(TransactionMode::Immediate, move |tx| -> Result<(Vec<TimeoutEvent>, Vec<TimeoutFailure>)> {
+            let mut events = Vec::new();
+            let mut failures = Vec::new();
 
-            // 現在ユーザーが存在しない場合は何もしない
-            let Some(current_user_id) = current else {
-                return Ok(());
+            // 投票中のルーム
+            let open_votes = {
+                let mut stmt = tx.prepare(
+                    "SELECT v.room_id, v.message_id,
+                            CAST(strftime('%s', 'now') - strftime('%s', v.updated_at) AS INTEGER)
+                     FROM room_votes v JOIN rooms r ON r.id = v.room_id
+                     WHERE r.status = 'active' AND v.word IS NOT NULL",
+                )?;
+                let rows = stmt.query_map([], |row| {
+                    Ok((
+                        row_to_u64(row, 0)?,
+                        row.get::<_, Option<i64>>(1)?.map(i64_to_u64_bitwise),
+                        row.get::<_, Option<i64>>(2)?.unwrap_or(0),
+                    ))
+                })?;
+                rows.collect::<Result<Vec<_>, _>>()?
             };
+            for (room_id, message_id, elapsed) in open_votes {
+                let resolution = tx.savepoint(|tx| -> Result<VoteResolution> {
+                    let timeout = load_room_config(tx, room_id)?.time_limits.vote_secs;
+                    let at_deadline = timeout.is_some_and(|timeout| elapsed >= timeout as i64);
+                    resolve_vote_in(tx, room_id, at_deadline)
+                });
+                match resolution {
+                    Ok(VoteResolution::Pending) => {}
+                    Ok(resolution) => events.push(TimeoutEvent::VoteResolved {
+                        room_id,
+                        message_id,
+                        resolution,
+                    }),
+                    Err(e) if e.is_busy() => return Err(e),
+                    // 壊れたルームがあっても、他のルームの処理は続ける
+                    Err(e) => failures.push((room_id, e)),
+                }
+            }
 
-            // 次のユーザーを取得
-            let next_user: Option<i64> = match tx.query_row(
-                "SELECT next FROM room_members WHERE room_id = ?1 AND user_id = ?2",
-                [room_id as i64, current_user_id],
-                |row| row.get(0),
-            ) {
-                Ok(v) => Some(v),
-                Err(rusqlite::Error::QueryReturnedNoRows) => None,
-                Err(e) => return Err(e.into()),
+            // 単語の投稿を待っているルーム
+            let waiting_turns = {
+                let mut stmt = tx.prepare(
+                    "SELECT v.room_id, v.current_user_id,
+                            CAST(strftime('%s', 'now') - strftime('%s', v.updated_at) AS INTEGER)
+                     FROM room_votes v JOIN rooms r ON r.id = v.room_id
+                     WHERE r.status = 'active' AND v.word IS NULL",
+                )?;
+                let rows = stmt.query_map([], |row| {
+                    Ok((row_to_u64(row, 0)?, row_to_u64(row, 1)?, row.get::<_, Option<i64>>(2)?.unwrap_or(0)))
+                })?;
+                rows.collect::<Result<Vec<_>, _>>()?
             };
+            for (room_id, user_id, elapsed) in waiting_turns {
+                let event = tx.savepoint(|tx| -> Result<Option<TimeoutEvent>> {
+                    let limits = load_room_config(tx, room_id)?.time_limits;
+                    if limits.turn_secs.is_none_or(|secs| elapsed < secs as i64) {
+                        return Ok(None);
+                    }
+                    let next_user_id = next_in_ring(tx, room_id, user_id)?;
 
-            // 次ユーザーがNULLでもそのまま反映
-            tx.execute(
-                "UPDATE room_votes SET current_user_id = ?2 WHERE room_id = ?1",
-                wrap_params![room_id as i64, next_user.unwrap()],
-            )?;
+                    match limits.on_turn_timeout {
+                        TurnTimeoutAction::Skip => {
+                            // 1人のルームでは手番が変わらないため、制限時間だけやり直す
+                            tx.execute(
+                                "UPDATE room_votes SET current_user_id = ?2, updated_at = datetime('now') WHERE room_id = ?1",
+                                wrap_params!(room_id, next_user_id),
+                            )?;
+                            Ok(Some(TimeoutEvent::TurnSkipped {
+                                room_id,
+                                user_id,
+                                next_user_id,
+                            }))
+                        }
+                        TurnTimeoutAction::Eliminate => {
+                            remove_user_in(tx, room_id, user_id)?;
+                            Ok(Some(TimeoutEvent::PlayerEliminated {
+                                room_id,
+                                user_id,
+                                next_user_id: Some(next_user_id).filter(|&id| id != user_id),
+                            }))
+                        }
+                    }
+                });
+                match event {
+                    Ok(event) => events.extend(event),
+                    Err(e) if e.is_busy() => return Err(e),
+                    Err(e) => failures.push((room_id, e)),
+                }
+            }
 
-            Ok(())
-        }).await?;
+            Ok((events, failures))
+        }).await
+    }
 
-        Ok(())
+    /// ---
+    /// 進行中の投票をルームのルールで判定し、結果が確定していれば反映します。
+    /// 承認された場合は単語を既出単語に追加して手番を次のユーザーへ進め、
+    /// 否決された場合は同じユーザーの手番に戻します。
+    /// ゲームを終了させる単語が承認された場合は、出したユーザーを敗者としてゲームを終了します。
+    /// ---
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    /// GameNotActive
+    /// VoteNotExists
+    pub async fn resolve_vote(&self, room_id: u64) -> Result<VoteResolution> {
+        self.db
+            .transaction(TransactionMode::Immediate, move |tx| resolve_vote_in(tx, room_id, false))
+            .await
     }
 
-    pub async fn dump_database<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        self.db.dump_database(path).await
+    /// ---
+    /// 進行中の投票を、期限を迎えたものとして投票済みの票で確定させます。
+    /// 管理用CLIから止まった投票を進めるために使います。
+    /// ---
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    /// GameNotActive
+    /// VoteNotExists
+    pub async fn force_resolve_vote(&self, room_id: u64) -> Result<VoteResolution> {
+        self.db
+            .transaction(TransactionMode::Immediate, move |tx| resolve_vote_in(tx, room_id, true))
+            .await
     }
 
-    */
-}
+    /// ---
+    /// 既出単語を手番の番号で指定して削除します。
+    /// 他の単語の手番は振り直しません。
+    /// ---
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    /// WordNotFound
+    pub async fn delete_word(&self, room_id: u64, turn: u64) -> Result<WordRecord> {
+        self.db.transaction(TransactionMode::Immediate, move |tx| -> Result<WordRecord> {
+            ensure_room_exists(tx, room_id)?;
+            let record = tx
+                .query_row(
+                    &format!("SELECT {} FROM room_words WHERE room_id = ?1 AND turn = ?2", WORD_RECORD_COLUMNS),
+                    wrap_params!(room_id, turn),
+                    row_to_word_record,
+                )
+                .optional()?
+                .ok_or(RepoError::WordNotFound)?;
+            tx.execute("DELETE FROM room_words WHERE room_id = ?1 AND turn = ?2", wrap_params!(room_id, turn))?;
+            Ok(record)
+        }).await
+    }
 
-#[cfg(test)]
-mod tests {
-    use core::panic;
-    use std::{fs, result};
+    /// ---
+    /// 手番の順番を設定します。
+    /// queueの順に輪になるようにnext/prevをつなぎ直します。queueはルームの全参加者を1回ずつ含む必要があります。
+    /// ---
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    /// UserNotFound (ルームに参加していないユーザーが含まれる)
+    /// InvalidQueue (重複している、または含まれていない参加者がいる)
+    pub async fn set_queue(&self, room_id: u64, queue: Vec<u64>) -> Result<()> {
+        self.db.transaction(TransactionMode::Immediate, move |tx| -> Result<()> {
+            ensure_room_exists(tx, room_id)?;
+            let members: Vec<u64> = load_links(tx, room_id)?.into_iter().map(|link| link.user_id).collect();
 
-    use crate::{
-        assert_or_ok,
-        database::{
-            db::DataBase,
-            repository::{RepoError, Repository},
-        },
-        define_test_guard,
-    };
-    use anyhow::Result;
+            if let Some(_unknown) = queue.iter().find(|user_id| !members.contains(user_id)) {
+                return Err(RepoError::UserNotFound);
+            }
+            let unique: HashSet<u64> = queue.iter().copied().collect();
+            if unique.len() != queue.len() || queue.len() != members.len() {
+                return Err(RepoError::InvalidQueue);
+            }
 
-    define_test_guard!(Repository);
+            link_ring(tx, room_id, &queue)
+        }).await
+    }
 
-    // セットアップ
-    async fn setup_repo() -> Result<Repository> {
-        let init_sql = fs::read_to_string("./schema.sql")?;
-        let db = DataBase::new(":memory:", Some(&init_sql)).await?;
-        Repository::new(db)
+    /// ---
+    /// 手番の順番を、現在の手番のユーザー（いなければ最初の参加者）から取得します。
+    /// ---
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    /// BrokenChain (next/prevのリンクが輪になっていない)
+    pub async fn get_queue(&self, room_id: u64) -> Result<Vec<u64>> {
+        self.db.transaction(TransactionMode::Read, move |tx| -> Result<Vec<u64>> {
+            ensure_room_exists(tx, room_id)?;
+            let links = load_links(tx, room_id)?;
+            match ring_start(tx, room_id, &links)? {
+                Some(start) => walk_ring(&links, start),
+                None => Ok(Vec::new()),
+            }
+        }).await
     }
 
-    async fn setup_create_rooms(repo: &Repository, rooms: &Vec<u64>) {
-        for id in rooms {
-            repo.create_room(*id).await.unwrap_or_else(|e| {
-                panic!(
-                    "テスト対象外のcreate_roomでエラーが発生しました。\nエラー: {:?}",
-                    e
-                )
-            });
-        }
+    /// ---
+    /// 壊れた手番の輪を修復し、修復後の順番を返します。
+    /// 現在の手番のユーザーから正しくつながっている部分はそのまま残し、
+    /// 残りの参加者を参加順に後ろへつなぎます。
+    /// ---
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    pub async fn repair_queue(&self, room_id: u64) -> Result<Vec<u64>> {
+        self.db.transaction(TransactionMode::Immediate, move |tx| -> Result<Vec<u64>> {
+            ensure_room_exists(tx, room_id)?;
+            repair_ring(tx, room_id)
+        }).await
     }
-    
-    async fn setup_add_users(repo: &Repository, users: &Vec<u64>, room_id: u64) {
-        for id in users {
-            repo.add_user(*id, room_id).await.unwrap_or_else(|e| {
-                panic!(
-                    "テスト対象外のadd_userでエラーが発生しました。\nエラー: {:?}",
-                    e
+
+    /// ---
+    /// 手番を次のユーザーへ進め、新しい手番のユーザーを返します。
+    /// 投票中の単語は取り消されます。
+    /// ---
+    ///
+    /// エラー可能性:
+    /// RoomNotFound
+    /// VoteNotExists (手番のユーザーがいない)
+    pub async fn next_user(&self, room_id: u64) -> Result<u64> {
+        self.db.transaction(TransactionMode::Immediate, move |tx| -> Result<u64> {
+            ensure_room_exists(tx, room_id)?;
+            let current_user_id = tx
+                .query_row(
+                    "SELECT current_user_id FROM room_votes WHERE room_id = ?1",
+                    wrap_params!(room_id),
+                    |row| row_to_u64(row, 0),
                 )
-            });
-        }
+                .optional()?
+                .ok_or(RepoError::VoteNotExists)?;
+
+            let next_user_id = next_in_ring(tx, room_id, current_user_id)?;
+            tx.execute(
+                "UPDATE room_votes SET current_user_id = ?2, word = NULL, reading = NULL, word_key = NULL, message_id = NULL WHERE room_id = ?1",
+                wrap_params!(room_id, next_user_id),
+            )?;
+            Ok(next_user_id)
+        }).await
     }
 
-    async fn setup_delete_rooms(repo: &Repository, rooms: &Vec<u64>) {
-        for id in rooms {
-            repo.delete_room(*id).await.unwrap_or_else(|e| {
-                panic!(
-                    "テスト対象外のdelete_roomでエラーが発生しました。\nエラー: {:?}",
-                    e
-                )
-            });
-        }
+    /// ---
+    /// 辞書を読み込み、登録した単語数を返します。
+    /// 同じ名前の辞書がすでにある場合は、1つのトランザクションの中で置き換えます。
+    /// 重複する単語は1つにまとめます。
+    /// ---
+    pub async fn load_dictionary(
+        &self,
+        name: &str,
+        format: DictionaryFormat,
+        entries: Vec<DictionaryEntry>,
+    ) -> Result<u64> {
+        let name = name.to_string();
+        self.db.transaction(TransactionMode::Immediate, move |tx| -> Result<u64> {
+            tx.execute("DELETE FROM dictionaries WHERE name = ?1", wrap_params!(name.as_str()))?;
+            tx.execute(
+                "INSERT INTO dictionaries (name, format) VALUES(?1, ?2)",
+                wrap_params!(name.as_str(), format.as_str()),
+            )?;
+            let dictionary_id = tx.last_insert_rowid();
+
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO dictionary_entries (dictionary_id, surface, reading) VALUES(?1, ?2, ?3)",
+            )?;
+            let mut count = 0;
+            for entry in &entries {
+                if entry.surface.is_empty() || entry.reading.is_empty() {
+                    continue;
+                }
+                count += stmt.execute(wrap_params!(dictionary_id, entry.surface.as_str(), entry.reading.as_str()))? as u64;
+            }
+            Ok(count)
+        }).await
     }
 
-    async fn setup_insert_words(repo: &Repository, room_id: u64, words: &Vec<&str>) {
-        for word in words {
-            repo.insert_word(room_id, word).await.unwrap_or_else(|e| {
-                panic!(
-                    "テスト対象外のinsert_wordでエラーが発生しました。\nエラー: {:?}",
-                    e
-                )
-            });
+    /// 辞書を削除します
+    ///
+    /// エラー可能性:
+    /// DictionaryNotFound
+    pub async fn delete_dictionary(&self, name: &str) -> Result<()> {
+        let result = self
+            .db
+            .execute("DELETE FROM dictionaries WHERE name = ?1", wrap_params!(name.to_string()))
+            .await;
+        match db_to_repo!(result, {})? {
+            0 => Err(RepoError::DictionaryNotFound),
+            _ => Ok(()),
         }
     }
 
-    // 初期化テスト
+    /// 読み込まれている辞書の一覧を名前順に取得します
+    pub async fn list_dictionaries(&self) -> Result<Vec<DictionaryInfo>> {
+        let result = self
+            .db
+            .query(
+                "SELECT d.name, d.format, d.loaded_at, COUNT(e.surface)
+                 FROM dictionaries d LEFT JOIN dictionary_entries e ON e.dictionary_id = d.id
+                 GROUP BY d.id ORDER BY d.name",
+                [],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row_to_u64(row, 3)?,
+                    ))
+                },
+            )
+            .await;
 
-    #[tokio::test]
-    async fn test_initial_repository() -> Result<()> {
-        let repo = setup_repo().await;
-        assert_or_ok!(repo, "単純なRepositoryの初期化でエラーが発生しました。");
+        db_to_repo!(result, {})?
+            .into_iter()
+            .map(|(name, format, loaded_at, entries)| {
+                let format = DictionaryFormat::parse(&format)
+                    .ok_or_else(|| RepoError::Other(anyhow::anyhow!("不明な辞書の形式: {}", format)))?;
+                Ok(DictionaryInfo {
+                    name,
+                    format,
+                    entries,
+                    loaded_at: loaded_at.and_then(|s| NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S").ok()),
+                })
+            })
+            .collect()
+    }
+
+    /// 表記が一致する単語をすべての辞書から取得します
+    pub async fn lookup_surface(&self, surface: &str) -> Result<Vec<DictionaryEntry>> {
+        self.lookup_dictionary("surface", surface.trim().to_string()).await
+    }
+
+    /// よみが一致する単語をすべての辞書から取得します（よみは正規化して比較します）
+    pub async fn lookup_reading(&self, reading: &str) -> Result<Vec<DictionaryEntry>> {
+        self.lookup_dictionary("reading", normalize_reading(reading)).await
+    }
+
+    /// 表記とよみの組がいずれかの辞書に載っているかどうか
+    pub async fn is_in_dictionary(&self, surface: &str, reading: &str) -> Result<bool> {
+        let (surface, reading) = (surface.to_string(), reading.to_string());
+        self.db
+            .transaction(TransactionMode::Read, move |tx| is_in_dictionary(tx, &surface, &reading))
+            .await
+    }
+
+    async fn lookup_dictionary(&self, column: &'static str, key: String) -> Result<Vec<DictionaryEntry>> {
+        let result = self
+            .db
+            .query(
+                &format!(
+                    "SELECT DISTINCT surface, reading FROM dictionary_entries WHERE {} = ?1 ORDER BY surface, reading",
+                    column
+                ),
+                wrap_params!(key),
+                |row| {
+                    Ok(DictionaryEntry {
+                        surface: row.get(0)?,
+                        reading: row.get(1)?,
+                    })
+                },
+            )
+            .await;
+        Ok(db_to_repo!(result, {})?)
+    }
+}
+
+fn row_to_u64(row: &Row<'_>, idx: usize) -> Result<u64, SqliteError> {
+    let i: i64 = row.get(idx)?;
+    Ok(i64_to_u64_bitwise(i))
+}
+
+fn row_to_word_record(row: &Row<'_>) -> Result<WordRecord, SqliteError> {
+    let approved_at = row
+        .get::<_, Option<String>>(4)?
+        .and_then(|s| NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S").ok());
+
+    Ok(WordRecord {
+        room_id: row_to_u64(row, 0)?,
+        turn: row_to_u64(row, 1)?,
+        word: row.get(2)?,
+        reading: row.get(7)?,
+        user_id: row.get::<_, Option<i64>>(3)?.map(i64_to_u64_bitwise),
+        approved_at,
+        tally: VoteTally {
+            good: row_to_u64(row, 5)?,
+            bad: row_to_u64(row, 6)?,
+        },
+    })
+}
+
+/// ルームが存在しない場合はRoomNotFoundを返します
+fn ensure_room_exists(tx: &QueryTransaction<'_>, room_id: u64) -> Result<()> {
+    tx.query_row("SELECT 1 FROM rooms WHERE id = ?1", wrap_params!(room_id), |_| Ok(()))
+        .optional()?
+        .ok_or(RepoError::RoomNotFound)
+}
+
+/// ---
+/// 進行中の投票を判定し、確定していれば反映します。
+/// at_deadlineがtrueの場合は投票の期限として、投票済みの票で必ず確定させます。
+/// ---
+fn resolve_vote_in(tx: &QueryTransaction<'_>, room_id: u64, at_deadline: bool) -> Result<VoteResolution> {
+    let status = tx
+        .query_row("SELECT status FROM rooms WHERE id = ?1", wrap_params!(room_id), |row| {
+            row.get::<_, String>(0)
+        })
+        .optional()?;
+    let status = status.ok_or(RepoError::RoomNotFound)?;
+    if RoomStatus::parse(&status) != Some(RoomStatus::Active) {
+        return Err(RepoError::GameNotActive);
+    }
+
+    let (user_id, word, reading, elapsed_secs, response_secs) = tx
+        .query_row(
+            "SELECT current_user_id, word, reading, CAST(strftime('%s', 'now') - strftime('%s', updated_at) AS INTEGER), response_secs
+             FROM room_votes WHERE room_id = ?1",
+            wrap_params!(room_id),
+            |row| {
+                Ok((
+                    row_to_u64(row, 0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<i64>>(3)?.unwrap_or(0),
+                    row.get::<_, Option<i64>>(4)?,
+                ))
+            },
+        )
+        .optional()?
+        .ok_or(RepoError::VoteNotExists)?;
+    let word = word.ok_or(RepoError::VoteNotExists)?;
+    let reading = reading.unwrap_or_else(|| normalize_reading(&word));
+
+    // 単語を出したユーザー自身の票は数えない
+    let (good, bad, eligible) = tx.query_row(
+        "SELECT
+            COALESCE(SUM(state = 'good'), 0),
+            COALESCE(SUM(state = 'bad'), 0),
+            COUNT(*)
+         FROM room_members WHERE room_id = ?1 AND user_id != ?2",
+        wrap_params!(room_id, user_id),
+        |row| Ok((row.get::<_, u32>(0)?, row.get::<_, u32>(1)?, row.get::<_, u32>(2)?)),
+    )?;
+    let tally = VoteTally {
+        good: good as u64,
+        bad: bad as u64,
+    };
+
+    let ballot = Ballot {
+        good,
+        bad,
+        eligible,
+        elapsed_secs,
+    };
+    let config = load_room_config(tx, room_id)?;
+    let verdict = if at_deadline {
+        config.vote_rule.evaluate_at_deadline(&ballot)
+    } else {
+        config.vote_rule.evaluate(&ballot)
+    };
+    match verdict {
+        Verdict::Pending => Ok(VoteResolution::Pending),
+        Verdict::Fail => {
+            tx.execute(
+                "UPDATE room_votes SET word = NULL, reading = NULL, word_key = NULL, message_id = NULL WHERE room_id = ?1",
+                wrap_params!(room_id),
+            )?;
+            record_score_event(tx, room_id, user_id, ScoreEvent::Rejected { word: &word, reading: &reading, response_secs })?;
+            Ok(VoteResolution::Rejected { user_id, word, reading, tally })
+        }
+        Verdict::Pass => {
+            let played = PlayableWord { surface: word, reading };
+            let turn = insert_word_record(tx, room_id, Some(user_id), &played, tally)?;
+            let PlayableWord { surface: word, reading } = played;
+            record_score_event(tx, room_id, user_id, ScoreEvent::Accepted { word: &word, reading: &reading, response_secs })?;
+
+            if config.n_ending.is_terminal(&reading, &config.chain_rules) {
+                finish_game_in(tx, room_id, Some(user_id))?;
+                return Ok(VoteResolution::GameOver { user_id, word, reading, turn, tally });
+            }
+
+            // 1人のルームでは同じユーザーが続ける
+            let next_user_id = next_in_ring(tx, room_id, user_id)?;
+            tx.execute(
+                "UPDATE room_votes SET current_user_id = ?2, word = NULL, reading = NULL, word_key = NULL, message_id = NULL WHERE room_id = ?1",
+                wrap_params!(room_id, next_user_id),
+            )?;
+
+            Ok(VoteResolution::Accepted {
+                user_id,
+                word,
+                reading,
+                turn,
+                tally,
+                next_user_id,
+            })
+        }
+    }
+}
+
+/// ---
+/// 参加者を追加し、手番の輪に入れます。
+/// 手番の順番では、手番のユーザーの次（手番がなければ最後）に入ります。
+/// computer_levelはコンピューターの参加者の強さ（人間の参加者はNone）です。
+/// ---
+fn add_member_in(
+    tx: &QueryTransaction<'_>,
+    room_id: u64,
+    user_id: u64,
+    computer_level: Option<Difficulty>,
+) -> Result<usize> {
+    // 追加前の手番の順番（壊れていれば修復する）
+    let queue = repair_ring(tx, room_id)?;
+
+    let result = tx
+        .execute(
+            "INSERT INTO room_members (room_id, user_id, computer_level) VALUES(?1, ?2, ?3)",
+            wrap_params!(room_id, user_id, computer_level.map(|level| level.as_str())),
+        )
+        .map_err(DatabaseError::from);
+    let success_count = db_to_repo!(result, {
+        SQLITE_CONSTRAINT_PRIMARYKEY => RepoError::UserAlreadyExists,
+        SQLITE_CONSTRAINT_FOREIGNKEY => RepoError::RoomNotFound,
+    })?;
+
+    // 手番のユーザーの次に入れる（手番がなければ最後に入れる）
+    let mut queue = queue;
+    let pos = if has_current_user(tx, room_id)? { 1 } else { queue.len() };
+    queue.insert(pos.min(queue.len()), user_id);
+    link_ring(tx, room_id, &queue)?;
+
+    Ok(success_count)
+}
+
+/// ユーザーをルームから削除し、手番と前後のリンクをつなぎ直します
+fn remove_user_in(tx: &QueryTransaction<'_>, room_id: u64, user_id: u64) -> Result<()> {
+    // 壊れた輪のまま削除するとリンクが失われるため、先に修復する
+    repair_ring(tx, room_id)?;
+
+    let (prev, next) = tx
+        .query_row(
+            "SELECT prev, next FROM room_members WHERE room_id = ?1 AND user_id = ?2",
+            wrap_params!(room_id, user_id),
+            |row| {
+                Ok((
+                    row.get::<_, Option<i64>>(0)?.map(i64_to_u64_bitwise),
+                    row.get::<_, Option<i64>>(1)?.map(i64_to_u64_bitwise),
+                ))
+            },
+        )
+        .optional()?
+        .ok_or(RepoError::UserNotFound)?;
+
+    // 自分自身へのリンクは残さない
+    let prev = prev.filter(|&id| id != user_id);
+    let next = next.filter(|&id| id != user_id);
+
+    // 削除するユーザーを参照しているリンクをつなぎ直す
+    // (room_id, next)の外部キーはON DELETE SET NULLでroom_idまでNULLにしようとするため、削除前に外す
+    tx.execute(
+        "UPDATE room_members SET next = ?3 WHERE room_id = ?1 AND next = ?2",
+        wrap_params!(room_id, user_id, next),
+    )?;
+    tx.execute(
+        "UPDATE room_members SET prev = ?3 WHERE room_id = ?1 AND prev = ?2",
+        wrap_params!(room_id, user_id, prev),
+    )?;
+    tx.execute(
+        "UPDATE room_members SET prev = NULL, next = NULL WHERE room_id = ?1 AND user_id = ?2",
+        wrap_params!(room_id, user_id),
+    )?;
+
+    // 手番のユーザーだった場合は次のユーザーへ渡し、進行中の投票は取り消す
+    match next {
+        Some(next_user_id) => tx.execute(
+            "UPDATE room_votes SET current_user_id = ?3, word = NULL, reading = NULL, word_key = NULL, message_id = NULL WHERE room_id = ?1 AND current_user_id = ?2",
+            wrap_params!(room_id, user_id, next_user_id),
+        )?,
+        None => tx.execute(
+            "DELETE FROM room_votes WHERE room_id = ?1 AND current_user_id = ?2",
+            wrap_params!(room_id, user_id),
+        )?,
+    };
+
+    tx.execute(
+        "DELETE FROM room_members WHERE room_id = ?1 AND user_id = ?2",
+        wrap_params!(room_id, user_id),
+    )?;
+    Ok(())
+}
+
+/// 参加者と手番の前後のユーザー
+#[derive(Debug, Clone, Copy)]
+struct MemberLink {
+    user_id: u64,
+    prev: Option<u64>,
+    next: Option<u64>,
+}
+
+/// ルームの参加者と前後のリンクを参加順に読み込みます
+fn load_links(tx: &QueryTransaction<'_>, room_id: u64) -> Result<Vec<MemberLink>> {
+    let mut stmt = tx.prepare("SELECT user_id, prev, next FROM room_members WHERE room_id = ?1 ORDER BY rowid")?;
+    let rows = stmt.query_map(wrap_params!(room_id), |row| {
+        Ok(MemberLink {
+            user_id: row_to_u64(row, 0)?,
+            prev: row.get::<_, Option<i64>>(1)?.map(i64_to_u64_bitwise),
+            next: row.get::<_, Option<i64>>(2)?.map(i64_to_u64_bitwise),
+        })
+    })?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// 手番のユーザーがいるかどうかを返します
+fn has_current_user(tx: &QueryTransaction<'_>, room_id: u64) -> Result<bool> {
+    Ok(tx.query_row(
+        "SELECT EXISTS(SELECT 1 FROM room_votes WHERE room_id = ?1)",
+        wrap_params!(room_id),
+        |row| row.get::<_, bool>(0),
+    )?)
+}
+
+/// 手番の輪を辿る起点（手番のユーザー、いなければ最初の参加者）を返します
+fn ring_start(
+    tx: &QueryTransaction<'_>,
+    room_id: u64,
+    links: &[MemberLink],
+) -> Result<Option<u64>> {
+    let current = tx
+        .query_row(
+            "SELECT current_user_id FROM room_votes WHERE room_id = ?1",
+            wrap_params!(room_id),
+            |row| row_to_u64(row, 0),
+        )
+        .optional()?;
+
+    Ok(current
+        .filter(|&current| links.iter().any(|link| link.user_id == current))
+        .or_else(|| links.first().map(|link| link.user_id)))
+}
+
+/// ---
+/// startからnextを辿って手番の順番を返します。
+/// すべての参加者を1回ずつ通ってstartに戻り、各リンクのprevが辿ってきたユーザーを指していなければBrokenChainを返します。
+/// ---
+fn walk_ring(links: &[MemberLink], start: u64) -> Result<Vec<u64>> {
+    let map = link_map(links);
+    if !map.contains_key(&start) {
+        return Err(RepoError::BrokenChain);
+    }
+
+    let mut queue = vec![start];
+    let mut cursor = start;
+    loop {
+        let next = map[&cursor].next.ok_or(RepoError::BrokenChain)?;
+        let next_link = map.get(&next).ok_or(RepoError::BrokenChain)?;
+        if next_link.prev != Some(cursor) {
+            return Err(RepoError::BrokenChain);
+        }
+        if next == start {
+            break;
+        }
+        if queue.contains(&next) {
+            // startを通らない輪
+            return Err(RepoError::BrokenChain);
+        }
+        queue.push(next);
+        cursor = next;
+    }
+
+    if queue.len() != map.len() {
+        return Err(RepoError::BrokenChain);
+    }
+    Ok(queue)
+}
+
+fn link_map(links: &[MemberLink]) -> HashMap<u64, MemberLink> {
+    links.iter().map(|link| (link.user_id, *link)).collect()
+}
+
+/// queueの順に輪になるようにnext/prevを設定します
+fn link_ring(tx: &QueryTransaction<'_>, room_id: u64, queue: &[u64]) -> Result<()> {
+    tx.execute(
+        "UPDATE room_members SET prev = NULL, next = NULL WHERE room_id = ?1",
+        wrap_params!(room_id),
+    )?;
+
+    for (i, &user_id) in queue.iter().enumerate() {
+        let prev_user_id = queue[(i + queue.len() - 1) % queue.len()];
+        let next_user_id = queue[(i + 1) % queue.len()];
+        tx.execute(
+            "UPDATE room_members SET prev = ?2, next = ?3 WHERE room_id = ?1 AND user_id = ?4",
+            wrap_params!(room_id, prev_user_id, next_user_id, user_id),
+        )?;
+    }
+    Ok(())
+}
+
+/// 輪が壊れていれば修復し、手番の順番を返します
+fn repair_ring(tx: &QueryTransaction<'_>, room_id: u64) -> Result<Vec<u64>> {
+    let links = load_links(tx, room_id)?;
+    let Some(start) = ring_start(tx, room_id, &links)? else {
+        return Ok(Vec::new());
+    };
+    match walk_ring(&links, start) {
+        Err(RepoError::BrokenChain) => {}
+        result => return result,
+    }
+
+    let map = link_map(&links);
+
+    // 起点から正しくつながっている部分を残す
+    let mut queue = vec![start];
+    let mut cursor = start;
+    while let Some(next) = map[&cursor].next
+        && !queue.contains(&next)
+        && map.get(&next).is_some_and(|link| link.prev == Some(cursor))
+    {
+        queue.push(next);
+        cursor = next;
+    }
+
+    // 残りの参加者を参加順に追加する
+    for link in &links {
+        if !queue.contains(&link.user_id) {
+            queue.push(link.user_id);
+        }
+    }
+
+    link_ring(tx, room_id, &queue)?;
+    Ok(queue)
+}
+
+/// 手番の輪でuser_idの次のユーザーを返します。輪が壊れている場合は修復してから判定します
+fn next_in_ring(tx: &QueryTransaction<'_>, room_id: u64, user_id: u64) -> Result<u64> {
+    let queue = repair_ring(tx, room_id)?;
+    let pos = queue.iter().position(|&id| id == user_id).ok_or(RepoError::UserNotFound)?;
+    Ok(queue[(pos + 1) % queue.len()])
+}
+
+/// ゲームを終了状態にし、進行中の投票を破棄します
+fn finish_game_in(tx: &QueryTransaction<'_>, room_id: u64, loser_id: Option<u64>) -> Result<()> {
+    let updated = tx.execute(
+        "UPDATE rooms SET status = 'finished', loser_id = ?2, finished_at = datetime('now')
+         WHERE id = ?1 AND status != 'finished'",
+        wrap_params!(room_id, loser_id),
+    )?;
+
+    if updated == 0 {
+        let exists = tx
+            .query_row("SELECT 1 FROM rooms WHERE id = ?1", wrap_params!(room_id), |_| Ok(()))
+            .optional()?;
+        return Err(match exists {
+            Some(_) => RepoError::GameNotActive,
+            None => RepoError::RoomNotFound,
+        });
+    }
+
+    tx.execute("DELETE FROM room_votes WHERE room_id = ?1", wrap_params!(room_id))?;
+
+    // 敗者が決まった場合は、残りの参加者全員を勝者として記録する
+    if let Some(loser_id) = loser_id {
+        let members = {
+            let mut stmt = tx.prepare("SELECT user_id FROM room_members WHERE room_id = ?1 ORDER BY user_id")?;
+            let rows = stmt.query_map(wrap_params!(room_id), |row| row_to_u64(row, 0))?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+        for user_id in members {
+            let event = if user_id == loser_id { ScoreEvent::Lost } else { ScoreEvent::Won };
+            record_score_event(tx, room_id, user_id, event)?;
+        }
+    }
+    Ok(())
+}
+
+/// ---
+/// 成績として記録する出来事
+/// response_secsは手番が回ってから単語を出すまでの秒数です。
+/// ---
+enum ScoreEvent<'a> {
+    Accepted { word: &'a str, reading: &'a str, response_secs: Option<i64> },
+    Rejected { word: &'a str, reading: &'a str, response_secs: Option<i64> },
+    Won,
+    Lost,
+}
+
+/// 成績の記録を追加します（ルームが属するサーバーも一緒に記録します）
+fn record_score_event(tx: &QueryTransaction<'_>, room_id: u64, user_id: u64, event: ScoreEvent<'_>) -> Result<()> {
+    let (kind, word, reading_length, response_secs) = match event {
+        ScoreEvent::Accepted { word, reading, response_secs } => {
+            ("accepted", Some(word), Some(reading.chars().count() as i64), response_secs)
+        }
+        ScoreEvent::Rejected { word, reading, response_secs } => {
+            ("rejected", Some(word), Some(reading.chars().count() as i64), response_secs)
+        }
+        ScoreEvent::Won => ("won", None, None, None),
+        ScoreEvent::Lost => ("lost", None, None, None),
+    };
+    tx.execute(
+        "INSERT INTO score_events (room_id, guild_id, user_id, kind, word, reading_length, response_secs)
+         VALUES(?1, (SELECT guild_id FROM room_settings WHERE room_id = ?1), ?2, ?3, ?4, ?5, ?6)",
+        wrap_params!(room_id, user_id, kind, word, reading_length, response_secs),
+    )?;
+    Ok(())
+}
+
+/// ---
+/// 範囲と期間に含まれる成績を、ユーザーごとに集計します。
+/// user_idを指定した場合はそのユーザーだけを集計します。
+/// ---
+fn load_player_stats(
+    tx: &QueryTransaction<'_>,
+    scope: StatsScope,
+    window: TimeWindow,
+    user_id: Option<u64>,
+) -> Result<Vec<PlayerStats>> {
+    let (column, id) = match scope {
+        StatsScope::Room(room_id) => ("room_id", room_id),
+        StatsScope::Guild(guild_id) => ("guild_id", guild_id),
+    };
+    let filter = format!(
+        "{column} = ?1 AND (?2 IS NULL OR created_at >= datetime('now', ?2)) AND (?3 IS NULL OR user_id = ?3)"
+    );
+    let since = window.days().map(|days| format!("-{days} days"));
+
+    let mut stats: Vec<PlayerStats> = {
+        let mut stmt = tx.prepare(&format!(
+            "SELECT user_id, SUM(kind = 'accepted'), SUM(kind = 'rejected'), SUM(kind = 'won'), SUM(kind = 'lost'),
+                    MIN(CASE WHEN kind = 'accepted' THEN response_secs END)
+             FROM score_events WHERE {filter} GROUP BY user_id ORDER BY user_id"
+        ))?;
+        let rows = stmt.query_map(wrap_params!(id, since.clone(), user_id), |row| {
+            Ok(PlayerStats {
+                user_id: row_to_u64(row, 0)?,
+                accepted: row.get::<_, i64>(1)? as u64,
+                rejected: row.get::<_, i64>(2)? as u64,
+                won: row.get::<_, i64>(3)? as u64,
+                lost: row.get::<_, i64>(4)? as u64,
+                fastest_secs: row.get::<_, Option<i64>>(5)?.map(|secs| secs.max(0) as u64),
+                ..Default::default()
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()?
+    };
+
+    // 最長の単語（MAXと同じ行の単語を取り出す）
+    let longest: HashMap<u64, (String, u64)> = {
+        let mut stmt = tx.prepare(&format!(
+            "SELECT user_id, word, MAX(reading_length) FROM score_events
+             WHERE {filter} AND kind = 'accepted' GROUP BY user_id"
+        ))?;
+        let rows = stmt.query_map(wrap_params!(id, since.clone(), user_id), |row| {
+            Ok((row_to_u64(row, 0)?, (row.get::<_, String>(1)?, row.get::<_, i64>(2)? as u64)))
+        })?;
+        rows.collect::<Result<HashMap<_, _>, _>>()?
+    };
+
+    // 連続承認は記録の順に数える
+    let mut results: HashMap<u64, Vec<bool>> = HashMap::new();
+    {
+        let mut stmt = tx.prepare(&format!(
+            "SELECT user_id, kind = 'accepted' FROM score_events
+             WHERE {filter} AND kind IN ('accepted', 'rejected') ORDER BY id"
+        ))?;
+        let mut rows = stmt.query(wrap_params!(id, since, user_id))?;
+        while let Some(row) = rows.next()? {
+            results.entry(row_to_u64(row, 0)?).or_default().push(row.get::<_, bool>(1)?);
+        }
+    }
+
+    for player in &mut stats {
+        if let Some((word, length)) = longest.get(&player.user_id) {
+            player.longest_word = Some(word.clone());
+            player.longest_length = *length;
+        }
+        player.best_streak = best_streak(results.remove(&player.user_id).unwrap_or_default());
+    }
+    Ok(stats)
+}
+
+/// ---
+/// 表記とよみの組が、読み込まれたいずれかの辞書に載っているかどうか
+/// 仮名で書かれた単語（表記を正規化するとよみになる単語）は、よみだけが一致すれば載っているとみなします。
+/// ---
+fn is_in_dictionary(tx: &QueryTransaction<'_>, surface: &str, reading: &str) -> Result<bool> {
+    let surface = surface.trim();
+    let reading = normalize_reading(reading);
+    let written_in_kana = normalize_reading(surface) == reading;
+    let found = tx.query_row(
+        "SELECT EXISTS(SELECT 1 FROM dictionary_entries WHERE surface = ?1 AND reading = ?2)
+             OR (?3 AND EXISTS(SELECT 1 FROM dictionary_entries WHERE reading = ?2))",
+        wrap_params!(surface, reading, written_in_kana),
+        |row| row.get::<_, bool>(0),
+    )?;
+    Ok(found)
+}
+
+/// ---
+/// 設定を保存する単位
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingsScope {
+    Guild,
+    Room,
+}
+
+impl SettingsScope {
+    fn table(&self) -> &'static str {
+        match self {
+            SettingsScope::Guild => "guild_settings",
+            SettingsScope::Room => "room_settings",
+        }
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            SettingsScope::Guild => "guild_id",
+            SettingsScope::Room => "room_id",
+        }
+    }
+}
+
+/// 設定の項目のカラム（ConfigOverridesの項目順）
+const SETTINGS_COLUMNS: &str = "n_ending, vote_policy, vote_threshold, vote_quorum, vote_timeout, turn_timeout, \
+                                turn_timeout_action, min_word_length, allowed_scripts, language, dictionary_mode, \
+                                long_vowel, expand_small_kana, ignore_dakuten";
+
+/// 保存されている設定を読み込みます。行がなければNoneを返します
+fn load_overrides(tx: &QueryTransaction<'_>, scope: SettingsScope, id: u64) -> Result<Option<ConfigOverrides>> {
+    let row = tx
+        .query_row(
+            &format!("SELECT {} FROM {} WHERE {} = ?1", SETTINGS_COLUMNS, scope.table(), scope.key()),
+            wrap_params!(id),
+            |row| {
+                Ok((
+                    row.get::<_, Option<String>>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<u32>>(2)?,
+                    row.get::<_, Option<u32>>(3)?,
+                    row.get::<_, Option<u32>>(4)?,
+                    row.get::<_, Option<u32>>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, Option<u32>>(7)?,
+                    row.get::<_, Option<String>>(8)?,
+                    row.get::<_, Option<String>>(9)?,
+                    row.get::<_, Option<String>>(10)?,
+                    row.get::<_, Option<String>>(11)?,
+                    row.get::<_, Option<bool>>(12)?,
+                    row.get::<_, Option<bool>>(13)?,
+                ))
+            },
+        )
+        .optional()?;
+    let Some((
+        n_ending,
+        policy,
+        threshold,
+        quorum,
+        vote_timeout,
+        turn_timeout,
+        action,
+        min_word_length,
+        scripts,
+        language,
+        dictionary_mode,
+        long_vowel,
+        expand_small_kana,
+        ignore_dakuten,
+    )) = row
+    else {
+        return Ok(None);
+    };
+
+    let invalid = |what: &str, value: &str| RepoError::Other(anyhow::anyhow!("不明な{}: {}", what, value));
+    let n_ending = n_ending
+        .map(|s| NEndingRule::parse(&s).ok_or_else(|| invalid("「ん」ルール", &s)))
+        .transpose()?;
+    let vote_rule = policy
+        .map(|s| {
+            VotePolicy::parse(&s, threshold)
+                .map(|policy| VoteRule { policy, quorum: quorum.unwrap_or(0) })
+                .ok_or_else(|| invalid("投票ルール", &format!("{} ({:?})", s, threshold)))
+        })
+        .transpose()?;
+    let turn_timeout_action = action
+        .map(|s| TurnTimeoutAction::parse(&s).ok_or_else(|| invalid("手番の時間切れの扱い", &s)))
+        .transpose()?;
+    let allowed_scripts = scripts
+        .map(|s| ScriptSet::parse(&s).ok_or_else(|| invalid("文字の種類", &s)))
+        .transpose()?;
+    let language = language
+        .map(|s| Language::parse(&s).ok_or_else(|| invalid("言語", &s)))
+        .transpose()?;
+    let dictionary_mode = dictionary_mode
+        .map(|s| DictionaryMode::parse(&s).ok_or_else(|| invalid("辞書の扱い", &s)))
+        .transpose()?;
+    let long_vowel = long_vowel
+        .map(|s| LongVowelRule::parse(&s).ok_or_else(|| invalid("長音の扱い", &s)))
+        .transpose()?;
+
+    Ok(Some(ConfigOverrides {
+        n_ending,
+        vote_rule,
+        vote_timeout,
+        turn_timeout,
+        turn_timeout_action,
+        min_word_length,
+        allowed_scripts,
+        language,
+        dictionary_mode,
+        long_vowel,
+        expand_small_kana,
+        ignore_dakuten,
+    }))
+}
+
+/// 設定を保存します（ルームの場合、引き継ぐサーバーはそのまま残します）
+fn store_overrides(tx: &QueryTransaction<'_>, scope: SettingsScope, id: u64, settings: &ConfigOverrides) -> Result<()> {
+    let sql = format!(
+        "INSERT INTO {table} ({key}, {columns}) VALUES(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+         ON CONFLICT({key}) DO UPDATE SET
+            n_ending = excluded.n_ending,
+            vote_policy = excluded.vote_policy,
+            vote_threshold = excluded.vote_threshold,
+            vote_quorum = excluded.vote_quorum,
+            vote_timeout = excluded.vote_timeout,
+            turn_timeout = excluded.turn_timeout,
+            turn_timeout_action = excluded.turn_timeout_action,
+            min_word_length = excluded.min_word_length,
+            allowed_scripts = excluded.allowed_scripts,
+            language = excluded.language,
+            dictionary_mode = excluded.dictionary_mode,
+            long_vowel = excluded.long_vowel,
+            expand_small_kana = excluded.expand_small_kana,
+            ignore_dakuten = excluded.ignore_dakuten",
+        table = scope.table(),
+        key = scope.key(),
+        columns = SETTINGS_COLUMNS,
+    );
+    let vote_rule = settings.vote_rule;
+    tx.execute(
+        &sql,
+        wrap_params!(
+            id,
+            settings.n_ending.map(|rule| rule.as_str()),
+            vote_rule.map(|rule| rule.policy.as_str()),
+            vote_rule.and_then(|rule| rule.policy.threshold()),
+            vote_rule.map(|rule| rule.quorum),
+            settings.vote_timeout,
+            settings.turn_timeout,
+            settings.turn_timeout_action.map(|action| action.as_str()),
+            settings.min_word_length,
+            settings.allowed_scripts.map(|scripts| scripts.to_db()),
+            settings.language.map(|language| language.as_str()),
+            settings.dictionary_mode.map(|mode| mode.as_str()),
+            settings.long_vowel.map(|rule| rule.as_str()),
+            settings.expand_small_kana,
+            settings.ignore_dakuten
+        ),
+    )?;
+    Ok(())
+}
+
+/// ルームで使われる設定を、既定値・サーバー・ルームの順に重ねて読み込みます
+fn load_room_config(tx: &QueryTransaction<'_>, room_id: u64) -> Result<RoomConfig> {
+    ensure_room_exists(tx, room_id)?;
+    let mut config = RoomConfig::default();
+
+    let guild_id = tx
+        .query_row(
+            "SELECT guild_id FROM room_settings WHERE room_id = ?1",
+            wrap_params!(room_id),
+            |row| row.get::<_, Option<i64>>(0),
+        )
+        .optional()?
+        .flatten()
+        .map(i64_to_u64_bitwise);
+    if let Some(guild_id) = guild_id
+        && let Some(guild) = load_overrides(tx, SettingsScope::Guild, guild_id)?
+    {
+        guild.apply(&mut config);
+    }
+    if let Some(room) = load_overrides(tx, SettingsScope::Room, room_id)? {
+        room.apply(&mut config);
+    }
+    Ok(config)
+}
+
+/// 次の手番として既出単語を追加し、割り当てた手番を返します
+fn insert_word_record(
+    tx: &QueryTransaction<'_>,
+    room_id: u64,
+    user_id: Option<u64>,
+    word: &PlayableWord,
+    tally: VoteTally,
+) -> Result<u64> {
+    let turn = tx.query_row(
+        "SELECT COALESCE(MAX(turn), 0) + 1 FROM room_words WHERE room_id = ?1",
+        wrap_params!(room_id),
+        |row| row_to_u64(row, 0),
+    )?;
+
+    let result = tx
+        .execute(
+            "INSERT INTO room_words (room_id, word, reading, word_key, turn, user_id, good_count, bad_count)
+             VALUES(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            wrap_params!(
+                room_id,
+                word.surface.as_str(),
+                word.reading.as_str(),
+                word.key(),
+                turn,
+                user_id,
+                tally.good,
+                tally.bad
+            ),
+        )
+        .map_err(DatabaseError::from);
+
+    db_to_repo!(result, {
+        SQLITE_CONSTRAINT_PRIMARYKEY => RepoError::WordAlreadyExists,
+        SQLITE_CONSTRAINT_FOREIGNKEY => RepoError::RoomNotFound,
+        SQLITE_CONSTRAINT_NOTNULL => RepoError::NullWord
+    })?;
+
+    Ok(turn)
+}
+
+/// ---
+/// 出せることを確認した単語（表記とよみ）
+/// ---
+struct PlayableWord {
+    surface: String,
+    reading: String,
+}
+
+impl PlayableWord {
+    /// 既出判定の正規化キー
+    fn key(&self) -> String {
+        canonical_key(&self.reading)
+    }
+}
+
+/// ---
+/// ゲーム状態のチェック、単語の制限のチェック、既出チェック、直前の単語からの接続チェックを行います。
+/// 投稿をよみと表記に分け、よみで既出と接続を判定します。
+/// ---
+fn ensure_word_playable(tx: &QueryTransaction<'_>, room_id: u64, input: &str) -> Result<PlayableWord> {
+    let status = tx
+        .query_row("SELECT status FROM rooms WHERE id = ?1", wrap_params!(room_id), |row| {
+            row.get::<_, String>(0)
+        })
+        .optional()?;
+    match status.as_deref().and_then(RoomStatus::parse) {
+        None => return Err(RepoError::RoomNotFound),
+        Some(RoomStatus::Active) => {}
+        Some(_) => return Err(RepoError::GameNotActive),
+    }
+
+    let input = WordInput::parse(input);
+    if input.surface.is_empty() {
+        return Err(RepoError::NullWord);
+    }
+    let config = load_room_config(tx, room_id)?;
+    if !config.allowed_scripts.allows(&input.surface) {
+        return Err(RepoError::ScriptNotAllowed);
+    }
+    let reading = resolve_reading(tx, &input)?;
+    if !config.is_long_enough(&reading) {
+        return Err(RepoError::WordTooShort);
+    }
+    if config.dictionary_mode == DictionaryMode::Required && !is_in_dictionary(tx, &input.surface, &reading)? {
+        return Err(RepoError::NotInDictionary);
+    }
+
+    let word = PlayableWord {
+        surface: input.surface,
+        reading,
+    };
+    let already_used = tx.query_row(
+        "SELECT EXISTS(SELECT 1 FROM room_words WHERE room_id = ?1 AND word_key = ?2)",
+        wrap_params!(room_id, word.key()),
+        |row| row.get::<_, bool>(0),
+    )?;
+    if already_used {
+        return Err(RepoError::WordAlreadyExists);
+    }
+
+    if let Some(last_reading) = load_last_reading(tx, room_id)?
+        && !is_chained(&last_reading, &word.reading, &config.chain_rules)
+    {
+        return Err(RepoError::ChainMismatch);
+    }
+
+    Ok(word)
+}
+
+/// ルームの最後に承認された単語のよみを返します
+fn load_last_reading(tx: &QueryTransaction<'_>, room_id: u64) -> Result<Option<String>> {
+    Ok(tx
+        .query_row(
+            "SELECT reading FROM room_words WHERE room_id = ?1 ORDER BY turn DESC LIMIT 1",
+            wrap_params!(room_id),
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?)
+}
+
+/// ルームに参加しているコンピューターの強さを返します（参加していなければNone）
+fn load_computer_level(tx: &QueryTransaction<'_>, room_id: u64) -> Result<Option<Difficulty>> {
+    let level = tx
+        .query_row(
+            "SELECT computer_level FROM room_members WHERE room_id = ?1 AND user_id = ?2",
+            wrap_params!(room_id, COMPUTER_USER_ID),
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()?
+        .flatten();
+    level
+        .map(|s| Difficulty::parse(&s).ok_or_else(|| RepoError::Other(anyhow::anyhow!("不明なコンピューターの強さ: {}", s))))
+        .transpose()
+}
+
+/// ---
+/// よみがheadsのいずれかの文字で始まる条件と、そのパラメータを返します。
+/// インデックスを使えるよう、前方一致を範囲の比較で表します。headsが空の場合はすべてに一致します。
+/// ---
+fn reading_prefix_clause(heads: &[char]) -> (String, Vec<String>) {
+    if heads.is_empty() {
+        return ("1".to_string(), Vec::new());
+    }
+    let mut params = Vec::new();
+    let clauses: Vec<String> = heads
+        .iter()
+        .map(|&head| {
+            let upper = char::from_u32(head as u32 + 1).unwrap_or(char::MAX);
+            params.push(head.to_string());
+            params.push(upper.to_string());
+            format!("(reading >= ?{} AND reading < ?{})", params.len() - 1, params.len())
+        })
+        .collect();
+    (clauses.join(" OR "), params)
+}
+
+/// コンピューターの候補として1回に辞書から読む単語の最大数
+const COMPUTER_CANDIDATE_LIMIT: usize = 200;
+
+/// ---
+/// よみがheadsのいずれかの文字で始まる辞書の単語を、最大COMPUTER_CANDIDATE_LIMIT個ランダムな順に取得します。
+/// ルームで既出の単語、コンピューターの強さや最低文字数に合わない長さの単語、
+/// ルームで使えない文字を含む単語、負けになる場合は「ん」で終わる単語をSQLで除きます。
+/// 辞書全体を読まないよう、よみの次の文字（headsが空の場合は先頭の文字）をランダムに選んでそこから読み、
+/// 足りない分はその前から読みます。
+/// ---
+fn sample_dictionary_words(
+    tx: &QueryTransaction<'_>,
+    room_id: u64,
+    heads: &[char],
+    config: &RoomConfig,
+    difficulty: Difficulty,
+) -> Result<Vec<DictionaryEntry>> {
+    // ひらがな（ぁ〜ゖ）のうちの1文字
+    let offset: u32 = tx.query_row("SELECT abs(random() % 86)", [], |row| row.get(0))?;
+    let pivot = char::from_u32(0x3041 + offset).unwrap_or('あ');
+
+    let mut words = Vec::new();
+    for after_pivot in [true, false] {
+        let limit = COMPUTER_CANDIDATE_LIMIT - words.len();
+        if limit == 0 {
+            break;
+        }
+        let (clause, params) = reading_window_clause(heads, pivot, after_pivot);
+        let mut values: Vec<rusqlite::types::Value> = params.into_iter().map(Into::into).collect();
+        let first = values.len() + 1;
+        values.extend([
+            (config.min_word_length as i64).into(),
+            difficulty.max_reading_len().map_or(i64::MAX, |max| max as i64).into(),
+            config.n_ending.is_terminal("ん", &config.chain_rules).into(),
+            config.allowed_scripts.to_db().into(),
+            (room_id as i64).into(),
+            (limit as i64).into(),
+        ]);
+        let mut stmt = tx.prepare(&format!(
+            "SELECT surface, reading FROM (
+                 SELECT DISTINCT surface, reading FROM dictionary_entries AS entries
+                 WHERE ({clause})
+                   AND length(reading) BETWEEN ?{min} AND ?{max}
+                   AND NOT (?{n_ending} AND reading LIKE '%ん')
+                   AND allows_scripts(surface, ?{scripts})
+                   AND NOT EXISTS (
+                       SELECT 1 FROM room_words WHERE room_id = ?{room} AND word_key = canonical_key(entries.reading)
+                   )
+                 LIMIT ?{limit}
+             )
+             ORDER BY RANDOM()",
+            clause = clause,
+            min = first,
+            max = first + 1,
+            n_ending = first + 2,
+            scripts = first + 3,
+            room = first + 4,
+            limit = first + 5,
+        ))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(values), |row| {
+            Ok(DictionaryEntry {
+                surface: row.get(0)?,
+                reading: row.get(1)?,
+            })
+        })?;
+        for row in rows {
+            words.push(row?);
+        }
+    }
+    Ok(words)
+}
+
+/// ---
+/// よみがheadsのいずれかの文字で始まり、次の文字がpivot以降（after_pivotがfalseならpivotより前）の条件と、
+/// そのパラメータを返します。headsが空の場合は先頭の文字をpivotと比べます。
+/// ---
+fn reading_window_clause(heads: &[char], pivot: char, after_pivot: bool) -> (String, Vec<String>) {
+    if heads.is_empty() {
+        let op = if after_pivot { ">=" } else { "<" };
+        return (format!("reading {} ?1", op), vec![pivot.to_string()]);
+    }
+    let mut params = Vec::new();
+    let clauses: Vec<String> = heads
+        .iter()
+        .map(|&head| {
+            let upper = char::from_u32(head as u32 + 1).unwrap_or(char::MAX);
+            let middle = format!("{}{}", head, pivot);
+            let (lower, upper) = if after_pivot { (middle, upper.to_string()) } else { (head.to_string(), middle) };
+            params.push(lower);
+            params.push(upper);
+            format!("(reading >= ?{} AND reading < ?{})", params.len() - 1, params.len())
+        })
+        .collect();
+    (clauses.join(" OR "), params)
+}
+
+/// よみがheadsのいずれかの文字で始まる辞書の単語の数（よみの種類数）を返します
+fn count_readings_starting_with(tx: &QueryTransaction<'_>, heads: &[char]) -> Result<u64> {
+    if heads.is_empty() {
+        return Ok(0);
+    }
+    let (clause, params) = reading_prefix_clause(heads);
+    Ok(tx.query_row(
+        &format!("SELECT COUNT(DISTINCT reading) FROM dictionary_entries WHERE {}", clause),
+        rusqlite::params_from_iter(params),
+        |row| row_to_u64(row, 0),
+    )?)
+}
+
+/// ---
+/// 投稿のよみを決めます。
+/// よみが指定されていない場合、漢字を含まない表記はそのままよみとし、
+/// 漢字を含む表記は辞書からよみを探します（見つからないか、複数ある場合はReadingRequired）。
+/// ---
+fn resolve_reading(tx: &QueryTransaction<'_>, input: &WordInput) -> Result<String> {
+    if let Some(reading) = &input.reading {
+        return Ok(normalize_reading(reading));
+    }
+    if !contains_kanji(&input.surface) {
+        return Ok(normalize_reading(&input.surface));
+    }
+
+    let mut stmt = tx.prepare("SELECT DISTINCT reading FROM dictionary_entries WHERE surface = ?1 LIMIT 2")?;
+    let readings = stmt
+        .query_map(wrap_params!(input.surface.as_str()), |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    match <[String; 1]>::try_from(readings) {
+        Ok([reading]) => Ok(reading),
+        Err(_) => Err(RepoError::ReadingRequired),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::panic;
+
+    use crate::{
+        assert_or_ok,
+        database::{
+            db::{DataBase, QueryExecutor, TransactionMode},
+            migration::MigrationMode,
+            repository::{
+                load_room_config, sample_dictionary_words, RepoError, Repository, RoomStatus, StatsScope, TimeoutEvent,
+                VoteResolution, VoteTally, WordRecord, COMPUTER_CANDIDATE_LIMIT,
+            },
+        },
+        dictionary::{DictionaryEntry, DictionaryFormat},
+        rules::{
+            computer::{Difficulty, COMPUTER_USER_ID},
+            config::{ConfigOverrides, DictionaryMode, Language, RoomConfig},
+            score::{PlayerStats, TimeWindow},
+            script::{Script, ScriptSet},
+            terminal::NEndingRule,
+            timeout::{TimeLimits, TurnTimeoutAction},
+            vote::{VotePolicy, VoteRule},
+        },
+        wrap_params,
+        define_test_guard,
+    };
+    use anyhow::Result;
+
+    define_test_guard!(Repository);
+
+    // セットアップ
+    async fn setup_repo() -> Result<Repository> {
+        let db = DataBase::new(":memory:").await?;
+        db.migrate(MigrationMode::Apply).await?;
+        Repository::new(db)
+    }
+
+    async fn setup_create_rooms(repo: &Repository, rooms: &Vec<u64>) {
+        for id in rooms {
+            repo.create_room(*id).await.unwrap_or_else(|e| {
+                panic!(
+                    "テスト対象外のcreate_roomでエラーが発生しました。\nエラー: {:?}",
+                    e
+                )
+            });
+        }
+    }
+    
+    async fn setup_add_users(repo: &Repository, users: &Vec<u64>, room_id: u64) {
+        for id in users {
+            repo.add_user(*id, room_id).await.unwrap_or_else(|e| {
+                panic!(
+                    "テスト対象外のadd_userでエラーが発生しました。\nエラー: {:?}",
+                    e
+                )
+            });
+        }
+    }
+
+    async fn setup_delete_rooms(repo: &Repository, rooms: &Vec<u64>) {
+        for id in rooms {
+            repo.delete_room(*id).await.unwrap_or_else(|e| {
+                panic!(
+                    "テスト対象外のdelete_roomでエラーが発生しました。\nエラー: {:?}",
+                    e
+                )
+            });
+        }
+    }
+
+    async fn setup_insert_words(repo: &Repository, room_id: u64, words: &Vec<&str>) {
+        for word in words {
+            repo.insert_word(room_id, word).await.unwrap_or_else(|e| {
+                panic!(
+                    "テスト対象外のinsert_wordでエラーが発生しました。\nエラー: {:?}",
+                    e
+                )
+            });
+        }
+    }
+
+    // 初期化テスト
+
+    #[tokio::test]
+    async fn test_initial_repository() -> Result<()> {
+        let repo = setup_repo().await;
+        assert_or_ok!(repo, "単純なRepositoryの初期化でエラーが発生しました。");
+
+        Ok(())
+    }
+
+    // ルーム操作テスト
+    #[tokio::test]
+    async fn test_create_room() -> Result<()> {
+        let repo = setup_repo().await?;
+
+        // 作成に問題がない
+        {
+            let result = repo.create_room(1).await;
+            assert_or_ok!(result, "1つ目のルーム作成時にエラーが発生しました。");
+        }
+
+        // 他のルーム作成に問題がない
+        {
+            let result = repo.create_room(2).await;
+            assert_or_ok!(result, "2つ目のルーム作成時にエラーが発生しました。");
+        }
+
+        // 重複するルームの作成
+        {
+            let result = repo.create_room(1).await;
+            assert_eq!(
+                result,
+                Err(RepoError::RoomAlreadyExists),
+                "重複するルームの作成で適切なエラーが発生しませんでした。\n内容: {:?}",
+                result
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_room() -> Result<()> {
+        let repo = setup_repo().await?;
+        let _ = setup_create_rooms(&repo, &vec![1]).await;
+
+        // 削除の確認
+        {
+            // 削除処理
+            let delete_count = repo.delete_room(1).await.unwrap_or_else(|e| {
+                panic!(
+                    "適切なルームの削除でエラーが発生しました。\nエラー: {:?}",
+                    e
+                )
+            });
+            assert_ne!(delete_count, 0, "削除されたルームの数が0件でした。");
+
+            // 再登録の確認
+            let recreate = repo.create_room(1).await;
+            assert_or_ok!(recreate, "ルームの削除後の作成に失敗しました。");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_rooms() -> Result<()> {
+        let repo = setup_repo().await?;
+        let room_list: Vec<u64> = vec![1, 3, 6, 8]; // ソート前提
+
+        // 初期化時テスト
+        {
+            let result = repo.get_rooms().await;
+            assert_eq!(
+                result,
+                Ok(Vec::<u64>::new()),
+                "初期化時に空であるルームリストが空ではありませんでした。"
+            );
+        }
+
+        setup_create_rooms(&repo, &room_list).await; // ルーム追加
+
+        // 追加時テスト
+        {
+            let result = repo.get_rooms().await;
+            assert!(
+                result.is_ok(),
+                "ルームの取得で不明のエラーが発生しました。\nエラー: {:?}",
+                result.unwrap_err()
+            );
+            let mut got_room_list = result.unwrap();
+            got_room_list.sort();
+            assert_eq!(
+                got_room_list, room_list,
+                "追加したルームに不整合が発生しました。\n{:?} != {:?}",
+                got_room_list, room_list
+            );
+        }
+
+        Ok(())
+    }
+
+    // 単語操作テスト
+
+    #[tokio::test]
+    async fn test_insert_word() -> Result<()> {
+        let repo = setup_repo().await?;
+
+        // ルーム未作成時挿入
+        {
+            let result = repo.insert_word(1, "test").await;
+            assert_eq!(
+                result,
+                Err(RepoError::RoomNotFound),
+                "存在しないルームへの単語挿入時、想定されない処理がされました。\nresult: {:?}",
+                result
+            );
+        }
+
+        setup_create_rooms(&repo, &vec![1]).await;
+
+        // ルームに挿入
+        {
+            let result = repo.insert_word(1, "apple").await;
+            assert_eq!(
+                result,
+                Ok(1),
+                "標準的な単語の追加に失敗しました。\nエラー: {:?}",
+                result.as_ref().err()
+            );
+        }
+
+        // 同一ワード挿入
+        {
+            let result = repo.insert_word(1, "apple").await;
+            assert_eq!(
+                result,
+                Err(RepoError::WordAlreadyExists),
+                "すでに存在する単語の挿入で想定されていない処理がされました。\nresult: {:?}",
+                result
+            );
+        }
+
+        // ルーム削除時
+        {
+            let _ = repo.delete_room(1).await?;
+            repo.create_room(1).await?;
+            let result = repo.insert_word(1, "apple").await;
+            assert_eq!(
+                result,
+                Ok(1),
+                "再生成後のルームへの単語挿入でエラーが発生しました。\nエラー: {:?}",
+                result.as_ref().err()
+            );
+        }
+
+        //  NULLに準ずるワード挿入
+        {
+            let result = repo.insert_word(1, "").await;
+            assert_eq!(
+                result,
+                Err(RepoError::NullWord),
+                "Nullに準ずるワードの挿入で想定されていない処理がされました。\nエラー: {:?}",
+                result
+            );
+        }
+
+        // 直前の単語から続かないワード挿入
+        {
+            let result = repo.insert_word(1, "banana").await;
+            assert_eq!(
+                result,
+                Err(RepoError::ChainMismatch),
+                "前の単語から続かない単語の挿入で想定されていない処理がされました。\nresult: {:?}",
+                result
+            );
+        }
+
+        // 直前の単語から続くワード挿入
+        {
+            let result = repo.insert_word(1, "egg").await;
+            assert_eq!(
+                result,
+                Ok(1),
+                "前の単語から続く単語の挿入でエラーが発生しました。\nエラー: {:?}",
+                result.as_ref().err()
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_words() -> Result<()> {
+        let repo = setup_repo().await?;
+        let word_list: Vec<&str> = vec!["apple", "egg", "god"];
+
+        // ルーム未作成時テスト
+        {
+            let result = repo.get_words(1).await;
+            assert_eq!(
+                result,
+                Err(RepoError::RoomNotFound),
+                "未作成のルームからの単語取得で、想定されていない処理がされました。\nresult: {:?}",
+                result.as_ref()
+            )
+        }
+
+        setup_create_rooms(&repo, &vec![1]).await;
+
+        // 作成後空取得テスト
+        {
+            let result = repo.get_words(1).await;
+            assert_eq!(
+                result,
+                Ok(Vec::<String>::new()),
+                "単語情報のないルームからの単語取得において、空の配列が返りませんでした。\nresult: {:?}",
+                result.as_ref()
+            );
+        }
+
+        setup_insert_words(&repo, 1, &word_list).await;
+
+        // 単語挿入後テスト
+        {
+            let result = repo.get_words(1).await;
+            assert_or_ok!(result, "正常な単語軍の取得でエラーが発生しました。");
+            let mut original = word_list.clone(); let mut target = result.unwrap(); original.sort();
+            target.sort();
+            assert_eq!(
+                original, target,
+                "挿入した単語群と取得した単語群が異なります。\noriginal: {:?}\ntarget: {:?}",
+                original, target
+            );
+        }
+
+        setup_delete_rooms(&repo, &vec![1]).await;
+        setup_create_rooms(&repo, &vec![1]).await;
+        // ルーム削除後取得テスト
+        {
+            let result = repo.get_words(1).await;
+            assert_eq!(
+                result,
+                Ok(Vec::<String>::new()),
+                "再作成されたルームからからのリストが返りませんでした。\nresult: {:?}",
+                result.as_ref()
+            );
+        }
+
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_vote() -> Result<()> {
+        let repo = setup_repo().await?;
+        let _guard = TestGuard {
+            repo: Arc::new(repo.clone()),
+            dumppath: "dump/dump_vote_test.db".into(),
+        };
+        
+        // ルーム未作成テスト
+        {
+            let result = repo.get_vote_state(1).await;
+            assert_eq!(result, Ok(None), "ルーム未生成時に空であるべき投票の取得に問題が発生しました。\nresult: {:?}", result);
+        }
+        
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100,101,102,103,104], 1).await;
+
+        // 投票未作成テスト
+        {
+            let result = repo.get_vote_state(1).await;
+            assert_eq!(result, Ok(None), "初期化時に空であるべき投票の取得に問題が発生しました。\nresult: {:?}", result);
+        }
+        
+        // 未作成投票エラーテスト
+        {
+            let result = repo.vote(1, 100, "good").await;
+            assert_eq!(result, Err(RepoError::VoteNotExists), "投票未作成時にVoteNotExistsエラー以外が返されました。\nresult: {:?}", result);
+        }
+        
+        // 投票作成
+        {
+            let result = repo.add_vote_state(1, 100, "test").await;
+            assert_or_ok!(result, "正常な投票の作成でエラーが発生しました。");
+        }
+        
+        // 投票
+        {
+            let result_good = repo.vote(1, 101, "good").await;
+            assert_or_ok!(result_good, "goodの投票に失敗しました。");
+            let result_bad = repo.vote(1, 101, "bad").await;
+            assert_or_ok!(result_bad, "badの投票に失敗しました。");
+            let result_none = repo.vote(1, 101, "none").await;
+            assert_or_ok!(result_none, "noneの投票に失敗しました。");
+        }
+        
+        // 不正投票
+        {
+            let result_null_state = repo.vote(1, 102, "").await;
+            assert_eq!(result_null_state, Err(RepoError::InvalidVoteState), "空文字列の投票でInvalidVoteState以外のエラーが発生しました。\nresult: {:?}", result_null_state);
+            let result_invalid_state = repo.vote(1, 103, "invalid").await;
+            assert_eq!(result_invalid_state, Err(RepoError::InvalidVoteState), "不正な投票でInvalidVoteState以外のエラーが発生しました。\nresult: {:?}", result_invalid_state);
+        }
+        
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_vote_message() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100, 101], 1).await;
+        repo.set_queue(1, vec![100, 101]).await?;
+
+        // 投票がない状態ではメッセージを記録できない
+        {
+            let result = repo.set_vote_message(1, 500).await;
+            assert_eq!(result, Err(RepoError::VoteNotExists), "投票未作成時にメッセージが記録されました。\nresult: {:?}", result);
+        }
+
+        repo.add_vote_state(1, 100, "test").await?;
+        repo.vote(1, 101, "good").await?;
+        repo.set_vote_message(1, 500).await?;
+
+        // メッセージの記録で投票状態がリセットされない
+        {
+            let vote = repo.get_vote_state(1).await?.expect("投票が取得できませんでした。");
+            assert_eq!(vote.message_id, Some(500), "投票メッセージが記録されていません。");
+            assert_eq!(vote.good, vec![101], "メッセージの記録で投票がリセットされました。\nvote: {:?}", vote);
+        }
+
+        // 参加者以外は投票できない
+        {
+            let result = repo.vote(1, 999, "good").await;
+            assert_eq!(result, Err(RepoError::UserNotFound), "参加者以外の投票でUserNotFound以外が返されました。\nresult: {:?}", result);
+        }
+
+        // 新しい単語の投票ではメッセージが引き継がれない
+        {
+            repo.remove_user(1, 100).await?;
+            let vote = repo.get_vote_state(1).await?.expect("手番が取得できませんでした。");
+            assert_eq!(vote.word, None, "手番の移動後に単語が残っています。");
+            assert_eq!(vote.message_id, None, "手番の移動後に投票メッセージが残っています。");
+            let result = repo.vote(1, 101, "good").await;
+            assert_eq!(result, Err(RepoError::VoteNotExists), "単語提出前の投票でVoteNotExists以外が返されました。\nresult: {:?}", result);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_add_vote_state_chain() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100, 101], 1).await;
+        setup_insert_words(&repo, 1, &vec!["りんご"]).await;
+
+        // 直前の単語から続かない投票
+        {
+            let result = repo.add_vote_state(1, 100, "らっぱ").await;
+            assert_eq!(result, Err(RepoError::ChainMismatch), "前の単語から続かない単語で投票が作成されました。\nresult: {:?}", result);
+            let vote = repo.get_vote_state(1).await;
+            assert_eq!(vote, Ok(None), "接続エラー後に投票が残っています。\nresult: {:?}", vote);
+        }
+
+        // 既出単語の投票
+        {
+            let result = repo.add_vote_state(1, 100, "りんご").await;
+            assert_eq!(result, Err(RepoError::WordAlreadyExists), "既出単語で投票が作成されました。\nresult: {:?}", result);
+        }
+
+        // カタカナで続く投票
+        {
+            let result = repo.add_vote_state(1, 100, "ゴリラ").await;
+            assert_or_ok!(result, "前の単語から続く単語の投票作成でエラーが発生しました。");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_add_vote_state_turn() -> Result<()> {
+        let repo = setup_repo().await?;
+
+        // ルーム未作成
+        {
+            assert_eq!(repo.is_awaiting_word(1, 100).await, Ok(false), "存在しないルームで単語を受け付けています。");
+            let result = repo.add_vote_state(1, 100, "りんご").await;
+            assert_eq!(result, Err(RepoError::RoomNotFound), "存在しないルームで投票が作成されました。\nresult: {:?}", result);
+        }
+
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100, 101], 1).await;
+
+        // 参加者以外
+        {
+            assert_eq!(repo.is_awaiting_word(1, 200).await, Ok(false), "参加者以外の発言を単語として受け付けています。");
+            let result = repo.add_vote_state(1, 200, "りんご").await;
+            assert_eq!(result, Err(RepoError::UserNotFound), "参加者以外が投票を作成しました。\nresult: {:?}", result);
+        }
+
+        assert_eq!(repo.is_awaiting_word(1, 100).await, Ok(true), "参加者の単語を受け付けていません。");
+        assert_or_ok!(repo.add_vote_state(1, 100, "りんご").await, "投票の作成に失敗しました。");
+
+        // 手番以外のユーザー
+        {
+            assert_eq!(repo.is_awaiting_word(1, 101).await, Ok(false), "手番以外のユーザーの単語を受け付けています。");
+            let result = repo.add_vote_state(1, 101, "ごりら").await;
+            assert_eq!(result, Err(RepoError::NotFirstUser), "手番以外のユーザーが投票を作成しました。\nresult: {:?}", result);
+        }
+
+        // 投票中の再提出
+        {
+            assert_eq!(repo.is_awaiting_word(1, 100).await, Ok(false), "投票中に単語を受け付けています。");
+            let result = repo.add_vote_state(1, 100, "ごりら").await;
+            assert_eq!(result, Err(RepoError::VoteInProgress), "投票中に単語が差し替えられました。\nresult: {:?}", result);
+            let vote = repo.get_vote_state(1).await?.expect("投票が取得できませんでした。");
+            assert_eq!(vote.word.as_deref(), Some("りんご"), "投票中の単語が変更されています。");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_members() -> Result<()> {
+        let repo = setup_repo().await?;
+
+        // ルーム未作成
+        {
+            let result = repo.get_members(1).await;
+            assert_eq!(result, Err(RepoError::RoomNotFound), "存在しないルームの参加者取得で想定外の結果が返されました。\nresult: {:?}", result);
+            let result = repo.add_user(100, 1).await;
+            assert_eq!(result, Err(RepoError::RoomNotFound), "存在しないルームへの参加で想定外の結果が返されました。\nresult: {:?}", result);
+        }
+
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![102, 100, 101], 1).await;
+        assert_eq!(repo.get_members(1).await, Ok(vec![102, 100, 101]), "参加者が参加順に取得できませんでした。");
+        repo.set_queue(1, vec![102, 100, 101]).await?;
+
+        // 手番のユーザーの退出で次のユーザーに手番が移る
+        {
+            assert_or_ok!(repo.add_vote_state(1, 102, "りんご").await, "投票の作成に失敗しました。");
+            assert_or_ok!(repo.remove_user(1, 102).await, "手番のユーザーの退出に失敗しました。");
+            let vote = repo.get_vote_state(1).await?.expect("退出後に手番が失われました。");
+            assert_eq!((vote.user_id, vote.word), (100, None), "退出後の手番が次のユーザーではありません。");
+            assert_eq!(repo.get_members(1).await, Ok(vec![100, 101]), "退出したユーザーが残っています。");
+        }
+
+        // 存在しないユーザーの退出
+        {
+            let result = repo.remove_user(1, 102).await;
+            assert_eq!(result, Err(RepoError::UserNotFound), "存在しないユーザーの退出で想定外の結果が返されました。\nresult: {:?}", result);
+        }
+
+        // 最後の1人まで退出
+        {
+            assert_or_ok!(repo.remove_user(1, 100).await, "ユーザーの退出に失敗しました。");
+            let vote = repo.get_vote_state(1).await?;
+            assert_eq!(vote.map(|v| v.user_id), Some(101), "2人目の退出後の手番が残りのユーザーではありません。");
+            assert_or_ok!(repo.remove_user(1, 101).await, "最後のユーザーの退出に失敗しました。");
+            assert_eq!(repo.get_vote_state(1).await, Ok(None), "全員退出後に手番が残っています。");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_queue_ring() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+
+        // 0人
+        {
+            assert_eq!(repo.get_queue(1).await, Ok(vec![]), "参加者がいないルームの順番が空ではありません。");
+            assert_or_ok!(repo.set_queue(1, vec![]).await, "空の順番の設定に失敗しました。");
+            let result = repo.get_queue(2).await;
+            assert_eq!(result, Err(RepoError::RoomNotFound), "存在しないルームの順番取得で想定外の結果が返されました。\nresult: {:?}", result);
+        }
+
+        // 1人（自分自身への輪）
+        {
+            setup_add_users(&repo, &vec![100], 1).await;
+            assert_eq!(repo.get_queue(1).await, Ok(vec![100]), "1人のルームの順番が想定と異なります。");
+            repo.add_vote_state(1, 100, "りんご").await?;
+            assert_eq!(repo.next_user(1).await, Ok(100), "1人のルームで手番が自分に戻りませんでした。");
+        }
+
+        // 2人（手番のユーザーの次に入る）
+        {
+            setup_add_users(&repo, &vec![101], 1).await;
+            assert_eq!(repo.get_queue(1).await, Ok(vec![100, 101]), "2人のルームの順番が想定と異なります。");
+            assert_eq!(repo.next_user(1).await, Ok(101), "2人目に手番が移りませんでした。");
+            assert_eq!(repo.get_queue(1).await, Ok(vec![101, 100]), "順番が手番のユーザーから始まっていません。");
+            assert_eq!(repo.next_user(1).await, Ok(100), "手番が1人目に戻りませんでした。");
+            assert_eq!(repo.next_user(1).await, Ok(101));
+        }
+
+        // N人
+        {
+            setup_add_users(&repo, &vec![102, 103], 1).await;
+            assert_eq!(repo.get_queue(1).await, Ok(vec![101, 103, 102, 100]), "手番のユーザーの次に参加者が入っていません。");
+
+            // 手番以外のユーザーの退出
+            repo.remove_user(1, 102).await?;
+            assert_eq!(repo.get_queue(1).await, Ok(vec![101, 103, 100]), "退出後の順番が想定と異なります。");
+
+            // 手番のユーザーの退出
+            repo.remove_user(1, 101).await?;
+            assert_eq!(repo.get_queue(1).await, Ok(vec![103, 100]), "手番のユーザーの退出後の順番が想定と異なります。");
+
+            // 順番の再設定
+            setup_add_users(&repo, &vec![104], 1).await;
+            repo.set_queue(1, vec![100, 104, 103]).await?;
+            assert_eq!(repo.get_queue(1).await, Ok(vec![103, 100, 104]), "再設定した順番が想定と異なります。");
+            assert_eq!(repo.next_user(1).await, Ok(100));
+            assert_eq!(repo.next_user(1).await, Ok(104));
+            assert_eq!(repo.next_user(1).await, Ok(103), "手番が1周しませんでした。");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_queue_errors() -> Result<()> {
+        let repo = setup_repo().await?;
+
+        let result = repo.set_queue(1, vec![100]).await;
+        assert_eq!(result, Err(RepoError::RoomNotFound), "存在しないルームの順番設定で想定外の結果が返されました。\nresult: {:?}", result);
+
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100, 101], 1).await;
+
+        let result = repo.set_queue(1, vec![100, 101, 999]).await;
+        assert_eq!(result, Err(RepoError::UserNotFound), "参加していないユーザーを含む順番が設定されました。\nresult: {:?}", result);
+        let result = repo.set_queue(1, vec![100, 100]).await;
+        assert_eq!(result, Err(RepoError::InvalidQueue), "重複したユーザーを含む順番が設定されました。\nresult: {:?}", result);
+        let result = repo.set_queue(1, vec![101]).await;
+        assert_eq!(result, Err(RepoError::InvalidQueue), "参加者が欠けた順番が設定されました。\nresult: {:?}", result);
+
+        // 失敗した設定で順番が変わらない
+        assert_eq!(repo.get_queue(1).await, Ok(vec![100, 101]), "失敗した設定で順番が変更されました。");
+
+        let result = repo.next_user(1).await;
+        assert_eq!(result, Err(RepoError::VoteNotExists), "手番がない状態で手番が進みました。\nresult: {:?}", result);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_broken_chain() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100, 101, 102, 103], 1).await;
+        repo.add_vote_state(1, 101, "りんご").await?;
+        assert_eq!(repo.get_queue(1).await, Ok(vec![101, 102, 103, 100]));
+
+        // 102 → 103 のリンクを壊す
+        repo.db
+            .execute(
+                "UPDATE room_members SET next = NULL WHERE room_id = 1 AND user_id = 102",
+                [],
+            )
+            .await?;
+        let result = repo.get_queue(1).await;
+        assert_eq!(result, Err(RepoError::BrokenChain), "壊れた輪が検出されませんでした。\nresult: {:?}", result);
+
+        // 手番から正しくつながっている部分を残して修復する
+        assert_eq!(repo.repair_queue(1).await, Ok(vec![101, 102, 100, 103]), "修復後の順番が想定と異なります。");
+        assert_eq!(repo.get_queue(1).await, Ok(vec![101, 102, 100, 103]), "修復後に輪になっていません。");
+
+        // prevの不整合も検出し、手番を進める処理では自動で修復する
+        repo.db
+            .execute(
+                "UPDATE room_members SET prev = 103 WHERE room_id = 1 AND user_id = 102",
+                [],
+            )
+            .await?;
+        assert_eq!(repo.get_queue(1).await, Err(RepoError::BrokenChain), "prevの不整合が検出されませんでした。");
+        assert_eq!(repo.next_user(1).await, Ok(100), "壊れた輪で手番が進みませんでした。");
+        assert_eq!(repo.get_queue(1).await, Ok(vec![100, 102, 103, 101]), "自動修復後の順番が想定と異なります。");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_restart_game() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100, 101], 1).await;
+        setup_insert_words(&repo, 1, &vec!["しりとり", "りんご"]).await;
+        assert_or_ok!(repo.add_vote_state(1, 100, "ごりら").await, "投票の作成に失敗しました。");
+        assert_or_ok!(repo.vote(1, 101, "good").await, "投票に失敗しました。");
+        repo.finish_game(1, Some(100)).await?;
+
+        assert_or_ok!(repo.restart_game(1).await, "ゲームの再開始に失敗しました。");
+        assert_eq!(repo.get_room_status(1).await, Ok(RoomStatus::Active), "再開始後のゲームが進行中ではありません。");
+        assert_eq!(repo.get_game_result(1).await, Ok(None), "再開始後に前回の結果が残っています。");
+        assert_eq!(repo.get_words(1).await, Ok(Vec::<String>::new()), "再開始後に既出単語が残っています。");
+        assert_eq!(repo.get_members(1).await, Ok(vec![100, 101]), "再開始で参加者が変わりました。");
+
+        // 前回の投票状態は新しい投票に持ち越されない
+        assert_or_ok!(repo.add_vote_state(1, 100, "らっぱ").await, "再開始後の投票の作成に失敗しました。");
+        let vote = repo.get_vote_state(1).await?.expect("投票が取得できませんでした。");
+        assert!(vote.good.is_empty(), "前回の投票状態が残っています。\nvote: {:?}", vote);
+
+        let result = repo.restart_game(2).await;
+        assert_eq!(result, Err(RepoError::RoomNotFound), "存在しないルームの再開始で想定外の結果が返されました。\nresult: {:?}", result);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_game_status() -> Result<()> {
+        let repo = setup_repo().await?;
+
+        // ルーム未作成テスト
+        {
+            let result = repo.get_room_status(1).await;
+            assert_eq!(result, Err(RepoError::RoomNotFound), "存在しないルームの状態取得で想定外の結果が返されました。\nresult: {:?}", result);
+            let result = repo.finish_game(1, None).await;
+            assert_eq!(result, Err(RepoError::RoomNotFound), "存在しないルームの終了で想定外の結果が返されました。\nresult: {:?}", result);
+        }
+
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100, 101], 1).await;
+
+        // 作成直後は進行中
+        {
+            let result = repo.get_room_status(1).await;
+            assert_eq!(result, Ok(RoomStatus::Active), "作成直後のルームが進行中ではありません。\nresult: {:?}", result);
+            let result = repo.get_game_result(1).await;
+            assert_eq!(result, Ok(None), "進行中のゲームに結果が存在します。\nresult: {:?}", result);
+        }
+
+        // 一時停止中は単語を受け付けない
+        {
+            assert_or_ok!(repo.pause_game(1).await, "ゲームの一時停止に失敗しました。");
+            let result = repo.pause_game(1).await;
+            assert_eq!(result, Err(RepoError::GameNotActive), "一時停止中のゲームの一時停止で想定外の結果が返されました。\nresult: {:?}", result);
+            let result = repo.add_vote_state(1, 100, "りんご").await;
+            assert_eq!(result, Err(RepoError::GameNotActive), "一時停止中のゲームで投票が作成されました。\nresult: {:?}", result);
+            assert_or_ok!(repo.resume_game(1).await, "ゲームの再開に失敗しました。");
+            let result = repo.resume_game(1).await;
+            assert_eq!(result, Err(RepoError::GameNotPaused), "進行中のゲームの再開で想定外の結果が返されました。\nresult: {:?}", result);
+        }
+
+        // 終了処理
+        {
+            assert_or_ok!(repo.add_vote_state(1, 100, "きりん").await, "投票の作成に失敗しました。");
+            let terminal = repo.is_terminal_word(1, "きりん").await;
+            assert_eq!(terminal, Ok(true), "「ん」で終わる単語が終了判定されませんでした。\nresult: {:?}", terminal);
+
+            assert_or_ok!(repo.finish_game(1, Some(100)).await, "ゲームの終了に失敗しました。");
+            let result = repo.get_game_result(1).await?;
+            assert_eq!(result.as_ref().and_then(|r| r.loser_id), Some(100), "敗者が記録されていません。\nresult: {:?}", result);
+            assert_eq!(repo.get_vote_state(1).await, Ok(None), "終了後に投票が残っています。");
+
+            let result = repo.finish_game(1, None).await;
+            assert_eq!(result, Err(RepoError::GameNotActive), "終了済みゲームの終了で想定外の結果が返されました。\nresult: {:?}", result);
+            let result = repo.insert_word(1, "りんご").await;
+            assert_eq!(result, Err(RepoError::GameNotActive), "終了済みゲームに単語が追加されました。\nresult: {:?}", result);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_n_ending_rule() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+
+        assert_eq!(repo.get_n_ending_rule(1).await, Ok(NEndingRule::Lose), "初期ルールが「ん」で負けではありません。");
+        assert_or_ok!(repo.set_n_ending_rule(1, NEndingRule::Continue).await, "ルールの変更に失敗しました。");
+        assert_eq!(repo.is_terminal_word(1, "きりん").await, Ok(false), "「ん」を許可したルームで終了判定されました。");
+
+        let result = repo.set_n_ending_rule(2, NEndingRule::Continue).await;
+        assert_eq!(result, Err(RepoError::RoomNotFound), "存在しないルームのルール変更で想定外の結果が返されました。\nresult: {:?}", result);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_vote_rule() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+
+        assert_eq!(repo.get_vote_rule(1).await, Ok(VoteRule::default()), "初期ルールが過半数ではありません。");
+        let rule = VoteRule {
+            policy: VotePolicy::Supermajority { percent: 70 },
+            quorum: 2,
+        };
+        assert_or_ok!(repo.set_vote_rule(1, rule).await, "投票ルールの変更に失敗しました。");
+        assert_eq!(repo.get_vote_rule(1).await, Ok(rule), "変更した投票ルールが取得できませんでした。");
+
+        let result = repo.set_vote_rule(2, rule).await;
+        assert_eq!(result, Err(RepoError::RoomNotFound), "存在しないルームの投票ルール変更で想定外の結果が返されました。\nresult: {:?}", result);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_vote() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100, 101, 102], 1).await;
+        repo.set_queue(1, vec![100, 101, 102]).await?;
+
+        // 投票未作成
+        {
+            let result = repo.resolve_vote(1).await;
+            assert_eq!(result, Err(RepoError::VoteNotExists), "投票未作成時にVoteNotExists以外が返されました。\nresult: {:?}", result);
+        }
+
+        // 承認
+        {
+            repo.add_vote_state(1, 100, "りんご").await?;
+            repo.vote(1, 101, "good").await?;
+            assert_eq!(repo.resolve_vote(1).await, Ok(VoteResolution::Pending), "過半数に届かない時点で確定しました。");
+
+            repo.vote(1, 102, "good").await?;
+            let result = repo.resolve_vote(1).await;
+            assert_eq!(
+                result,
+                Ok(VoteResolution::Accepted {
+                    user_id: 100,
+                    word: "りんご".into(),
+                    reading: "りんご".into(),
+                    turn: 1,
+                    tally: VoteTally { good: 2, bad: 0 },
+                    next_user_id: 101,
+                }),
+                "承認された単語が反映されませんでした。"
+            );
+
+            let vote = repo.get_vote_state(1).await?.expect("手番が取得できませんでした。");
+            assert_eq!((vote.user_id, vote.word.as_deref()), (101, None), "手番が次のユーザーへ進んでいません。");
+            assert!(vote.good.is_empty(), "確定後に投票状態がリセットされていません。\nvote: {:?}", vote);
+        }
+
+        // 否決
+        {
+            repo.add_vote_state(1, 101, "ごりら").await?;
+            repo.vote(1, 100, "bad").await?;
+            repo.vote(1, 102, "bad").await?;
+            let result = repo.resolve_vote(1).await;
+            assert_eq!(
+                result,
+                Ok(VoteResolution::Rejected {
+                    user_id: 101,
+                    word: "ごりら".into(),
+                    reading: "ごりら".into(),
+                    tally: VoteTally { good: 0, bad: 2 },
+                }),
+                "否決された単語の結果が想定と異なります。"
+            );
+
+            let vote = repo.get_vote_state(1).await?.expect("手番が取得できませんでした。");
+            assert_eq!((vote.user_id, vote.word.as_deref()), (101, None), "否決後に手番が同じユーザーへ戻っていません。");
+            assert_eq!(repo.get_words(1).await?, vec!["りんご".to_string()], "否決された単語が既出単語に追加されました。");
+        }
+
+        // 「ん」で終わる単語の承認でゲーム終了
+        {
+            repo.add_vote_state(1, 101, "ごはん").await?;
+            repo.vote(1, 100, "good").await?;
+            repo.vote(1, 102, "good").await?;
+            let result = repo.resolve_vote(1).await;
+            assert!(
+                matches!(result, Ok(VoteResolution::GameOver { user_id: 101, turn: 2, .. })),
+                "「ん」で終わる単語でゲームが終了しませんでした。\nresult: {:?}",
+                result
+            );
+            assert_eq!(repo.get_room_status(1).await, Ok(RoomStatus::Finished));
+            let game_result = repo.get_game_result(1).await?.expect("ゲーム結果が取得できませんでした。");
+            assert_eq!(game_result.loser_id, Some(101), "敗者が記録されていません。");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_vote_solo() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100], 1).await;
+        repo.set_queue(1, vec![100]).await?;
+
+        // 投票できる参加者がいなければそのまま承認され、同じユーザーが続ける
+        repo.add_vote_state(1, 100, "りんご").await?;
+        let result = repo.resolve_vote(1).await;
+        assert!(
+            matches!(result, Ok(VoteResolution::Accepted { user_id: 100, next_user_id: 100, .. })),
+            "1人のルームで単語が承認されませんでした。\nresult: {:?}",
+            result
+        );
+
+        Ok(())
+    }
+
+    /// 投票・手番の開始時刻を過去にずらします
+    async fn backdate_vote(repo: &Repository, room_id: u64, secs: i64) {
+        repo.db
+            .execute(
+                "UPDATE room_votes SET updated_at = datetime('now', ?2) WHERE room_id = ?1",
+                wrap_params!(room_id, format!("-{} seconds", secs)),
+            )
+            .await
+            .unwrap_or_else(|e| panic!("テスト対象外の時刻変更でエラーが発生しました。\nエラー: {:?}", e));
+    }
+
+    #[tokio::test]
+    async fn test_time_limits() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+
+        assert_eq!(repo.get_time_limits(1).await, Ok(TimeLimits::default()), "初期状態で制限時間が設定されています。");
+        let limits = TimeLimits {
+            vote_secs: Some(60),
+            turn_secs: Some(120),
+            on_turn_timeout: TurnTimeoutAction::Eliminate,
+        };
+        assert_or_ok!(repo.set_time_limits(1, limits).await, "制限時間の変更に失敗しました。");
+        assert_eq!(repo.get_time_limits(1).await, Ok(limits), "変更した制限時間が取得できませんでした。");
+
+        let result = repo.set_time_limits(2, limits).await;
+        assert_eq!(result, Err(RepoError::RoomNotFound), "存在しないルームの制限時間変更で想定外の結果が返されました。\nresult: {:?}", result);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_room_settings() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1, 2]).await;
+        assert_eq!(repo.get_room_config(1).await, Ok(RoomConfig::default()), "初期状態で設定が既定値ではありません。");
+
+        // サーバーの設定は、サーバーに紐づいたルームにだけ引き継がれる
+        repo.update_guild_settings(10, ConfigOverrides {
+            n_ending: Some(NEndingRule::Continue),
+            min_word_length: Some(2),
+            vote_timeout: Some(60),
+            ..Default::default()
+        }).await?;
+        repo.set_room_guild(1, 10).await?;
+        let config = repo.get_room_config(1).await?;
+        assert_eq!(config.n_ending, NEndingRule::Continue, "サーバーの設定が引き継がれていません。");
+        assert_eq!(config.time_limits.vote_secs, Some(60), "サーバーの設定が引き継がれていません。");
+        assert_eq!(repo.get_room_config(2).await, Ok(RoomConfig::default()), "別のルームにサーバーの設定が反映されました。");
+
+        // ルームの設定が優先され、変更しない項目はそのまま残る
+        repo.update_room_settings(1, ConfigOverrides { vote_timeout: Some(0), ..Default::default() }).await?;
+        repo.update_room_settings(1, ConfigOverrides { language: Some(Language::English), ..Default::default() }).await?;
+        let config = repo.get_room_config(1).await?;
+        assert_eq!(config.time_limits.vote_secs, None, "ルームの設定(0 = 無制限)が優先されていません。");
+        assert_eq!(config.language, Language::English);
+        assert_eq!(config.min_word_length, 2, "サーバーの設定が失われました。");
+        assert_eq!(
+            repo.get_room_settings(1).await?,
+            ConfigOverrides { vote_timeout: Some(0), language: Some(Language::English), ..Default::default() },
+            "ルームに保存された設定が一致しません。"
+        );
+
+        // リセットするとサーバーの設定に戻る
+        repo.reset_room_settings(1).await?;
+        assert_eq!(repo.get_room_config(1).await?.time_limits.vote_secs, Some(60), "リセット後にサーバーの設定に戻っていません。");
+        repo.reset_guild_settings(10).await?;
+        assert_eq!(repo.get_room_config(1).await, Ok(RoomConfig::default()), "サーバーの設定のリセット後に既定値に戻っていません。");
+
+        let result = repo.update_room_settings(3, ConfigOverrides::default()).await;
+        assert_eq!(result, Err(RepoError::RoomNotFound), "存在しないルームの設定変更で想定外の結果が返されました。\nresult: {:?}", result);
+        let result = repo.set_room_guild(3, 10).await;
+        assert_eq!(result, Err(RepoError::RoomNotFound), "存在しないルームのサーバー登録で想定外の結果が返されました。\nresult: {:?}", result);
+
+        // ルームを削除すると設定も消える
+        repo.delete_room(1).await?;
+        let count = repo
+            .db
+            .query("SELECT COUNT(*) FROM room_settings", [], |row| row.get::<_, i64>(0))
+            .await?;
+        assert_eq!(count, vec![0], "削除したルームの設定が残っています。");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_word_restrictions() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100, 101], 1).await;
+        repo.update_room_settings(1, ConfigOverrides {
+            min_word_length: Some(3),
+            allowed_scripts: Some(ScriptSet::from_scripts([Script::Hiragana, Script::Katakana])),
+            ..Default::default()
+        }).await?;
+
+        let result = repo.add_vote_state(1, 100, "いす").await;
+        assert_eq!(result, Err(RepoError::WordTooShort), "最低文字数に満たない単語が受け付けられました。\nresult: {:?}", result);
+        let result = repo.add_vote_state(1, 100, "林檎です").await;
+        assert_eq!(result, Err(RepoError::ScriptNotAllowed), "許可されていない文字が受け付けられました。\nresult: {:?}", result);
+        assert_or_ok!(repo.add_vote_state(1, 100, "ラーメン").await, "条件を満たす単語が受け付けられませんでした。");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_chain_rules() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1, 2]).await;
+        setup_add_users(&repo, &vec![100, 101], 1).await;
+        setup_add_users(&repo, &vec![100, 101], 2).await;
+        setup_insert_words(&repo, 1, &vec!["りんご"]).await;
+        setup_insert_words(&repo, 2, &vec!["りんご"]).await;
+
+        // 既定では濁音と清音を区別する
+        let result = repo.add_vote_state(2, 100, "こま").await;
+        assert_eq!(result, Err(RepoError::ChainMismatch), "既定の設定で濁音の違う単語が受け付けられました。\nresult: {:?}", result);
+
+        // ルームの設定で濁音を無視すると、同じ単語でも続けられる
+        repo.update_room_settings(1, ConfigOverrides { ignore_dakuten: Some(true), ..Default::default() }).await?;
+        let config = repo.get_room_config(1).await?;
+        assert!(config.chain_rules.ignore_dakuten, "濁音の設定が読み込まれていません。");
+        assert_or_ok!(repo.add_vote_state(1, 100, "こま").await, "濁音を無視する設定で単語が受け付けられませんでした。");
+
+        // 他のルームには影響しない
+        let config = repo.get_room_config(2).await?;
+        assert!(!config.chain_rules.ignore_dakuten, "他のルームの設定が変わっています。");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_word_reading() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100, 101], 1).await;
+        repo.set_queue(1, vec![100, 101]).await?;
+        repo.load_dictionary("test", DictionaryFormat::Plain, vec![
+            DictionaryEntry::new("胡麻", "ごま"),
+            DictionaryEntry::new("今日", "きょう"),
+            DictionaryEntry::new("今日", "こんにち"),
+        ]).await?;
+
+        // よみを指定した単語は、よみで接続する
+        repo.add_vote_state(1, 100, "林檎(リンゴ)").await?;
+        let vote = repo.get_vote_state(1).await?.expect("投票が作成されていません。");
+        assert_eq!(
+            (vote.word.as_deref(), vote.reading.as_deref()),
+            (Some("林檎"), Some("りんご")),
+            "表記とよみが分けて保存されていません。"
+        );
+        repo.vote(1, 101, "good").await?;
+        assert!(matches!(repo.resolve_vote(1).await?, VoteResolution::Accepted { .. }));
+
+        // よみが同じ単語は既出として扱う
+        let result = repo.add_vote_state(1, 101, "りんご").await;
+        assert_eq!(result, Err(RepoError::WordAlreadyExists), "よみが同じ単語が受け付けられました。\nresult: {:?}", result);
+
+        // よみを省略した場合は辞書から探す
+        let result = repo.add_vote_state(1, 101, "今日").await;
+        assert_eq!(result, Err(RepoError::ReadingRequired), "よみが複数ある単語が受け付けられました。\nresult: {:?}", result);
+        let result = repo.add_vote_state(1, 101, "牛蒡").await;
+        assert_eq!(result, Err(RepoError::ReadingRequired), "辞書にない漢字の単語が受け付けられました。\nresult: {:?}", result);
+        repo.add_vote_state(1, 101, "胡麻").await?;
+        repo.vote(1, 100, "good").await?;
+        repo.resolve_vote(1).await?;
+
+        let last = repo.get_last_word(1).await?.expect("最終単語が取得できませんでした。");
+        assert_eq!((last.word.as_str(), last.reading.as_str()), ("胡麻", "ごま"), "辞書のよみが保存されていません。");
+        let result = repo.add_vote_state(1, 100, "まくら まくら").await;
+        assert_or_ok!(result, "空白区切りでよみを指定した単語が受け付けられませんでした。");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_canonical_duplicates() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100], 1).await;
+        setup_insert_words(&repo, 1, &vec!["りんご", "ゴリラ"]).await;
+
+        for word in ["リンゴ", "ﾘﾝｺﾞ", "林檎(リンゴ)", "ごりら", "ｺﾞﾘﾗ"] {
+            let result = repo.add_vote_state(1, 100, word).await;
+            assert_eq!(result, Err(RepoError::WordAlreadyExists), "表記ゆれの単語が受け付けられました: {}\nresult: {:?}", word, result);
+        }
+
+        // トリガーでも正規化キーで既出を判定する
+        let result = repo
+            .db
+            .execute(
+                "INSERT INTO room_votes (room_id, current_user_id, word, reading, word_key) VALUES(1, 100, 'ｺﾞﾘﾗ', 'ごりら', 'ごりら')",
+                [],
+            )
+            .await;
+        assert!(result.is_err(), "既出の正規化キーの投票が作成されました。");
+
+        assert_or_ok!(repo.add_vote_state(1, 100, "ﾗｯﾊﾟ").await, "新しい単語が受け付けられませんでした。");
+        let vote = repo.get_vote_state(1).await?.expect("投票が作成されていません。");
+        assert_eq!(vote.reading.as_deref(), Some("らっぱ"), "よみが正規化されていません。");
 
         Ok(())
     }
 
-    // ルーム操作テスト
     #[tokio::test]
-    async fn test_create_room() -> Result<()> {
+    async fn test_dictionary() -> Result<()> {
         let repo = setup_repo().await?;
+        let entries = vec![
+            DictionaryEntry::new("林檎", "リンゴ"),
+            DictionaryEntry::new("林檎", "りんご"),
+            DictionaryEntry::new("ゴリラ", "ゴリラ"),
+        ];
+        let count = repo.load_dictionary("test", DictionaryFormat::Plain, entries).await?;
+        assert_eq!(count, 2, "重複する単語がまとめられていません。");
 
-        // 作成に問題がない
-        {
-            let result = repo.create_room(1).await;
-            assert_or_ok!(result, "1つ目のルーム作成時にエラーが発生しました。");
-        }
+        let found = repo.lookup_reading("リンゴ").await?;
+        assert_eq!(found, vec![DictionaryEntry::new("林檎", "りんご")], "よみで検索できません。");
+        let found = repo.lookup_surface("ゴリラ").await?;
+        assert_eq!(found, vec![DictionaryEntry::new("ゴリラ", "ごりら")], "表記で検索できません。");
+        assert!(repo.is_in_dictionary("ごりら", "ごりら").await?, "よみが一致する仮名の単語が見つかりません。");
+        assert!(repo.is_in_dictionary("林檎", "リンゴ").await?, "表記とよみが一致する単語が見つかりません。");
+        assert!(!repo.is_in_dictionary("林檎", "ごりら").await?, "よみが異なる単語が見つかりました。");
+        assert!(!repo.is_in_dictionary("らっぱ", "らっぱ").await?, "辞書にない単語が見つかりました。");
 
-        // 他のルーム作成に問題がない
-        {
-            let result = repo.create_room(2).await;
-            assert_or_ok!(result, "2つ目のルーム作成時にエラーが発生しました。");
-        }
+        // 同じ名前で読み込むと置き換える
+        let count = repo.load_dictionary("test", DictionaryFormat::Skk, vec![DictionaryEntry::new("喇叭", "らっぱ")]).await?;
+        assert_eq!(count, 1);
+        let dictionaries = repo.list_dictionaries().await?;
+        assert_eq!(dictionaries.len(), 1, "辞書が置き換えられていません。\ndictionaries: {:?}", dictionaries);
+        assert_eq!((dictionaries[0].format, dictionaries[0].entries), (DictionaryFormat::Skk, 1));
+        assert!(!repo.is_in_dictionary("林檎", "りんご").await?, "置き換え前の単語が残っています。");
 
-        // 重複するルームの作成
-        {
-            let result = repo.create_room(1).await;
-            assert_eq!(
-                result,
-                Err(RepoError::RoomAlreadyExists),
-                "重複するルームの作成で適切なエラーが発生しませんでした。\n内容: {:?}",
-                result
-            );
-        }
+        repo.delete_dictionary("test").await?;
+        assert_eq!(repo.delete_dictionary("test").await, Err(RepoError::DictionaryNotFound));
+        assert!(repo.lookup_reading("らっぱ").await?.is_empty(), "削除した辞書の単語が残っています。");
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_delete_room() -> Result<()> {
+    async fn test_dictionary_required() -> Result<()> {
         let repo = setup_repo().await?;
-        let _ = setup_create_rooms(&repo, &vec![1]).await;
+        setup_create_rooms(&repo, &vec![1, 2]).await;
+        setup_add_users(&repo, &vec![100, 101], 1).await;
+        setup_add_users(&repo, &vec![100, 101], 2).await;
+        repo.load_dictionary("test", DictionaryFormat::Plain, vec![DictionaryEntry::new("林檎", "りんご")]).await?;
 
-        // 削除の確認
-        {
-            // 削除処理
-            let delete_count = repo.delete_room(1).await.unwrap_or_else(|e| {
-                panic!(
-                    "適切なルームの削除でエラーが発生しました。\nエラー: {:?}",
-                    e
-                )
-            });
-            assert_ne!(delete_count, 0, "削除されたルームの数が0件でした。");
+        // 既定では辞書を使わない
+        assert_or_ok!(repo.add_vote_state(1, 100, "らっぱ").await, "辞書を使わない設定で単語が拒否されました。");
 
-            // 再登録の確認
-            let recreate = repo.create_room(1).await;
-            assert_or_ok!(recreate, "ルームの削除後の作成に失敗しました。");
-        }
+        repo.update_room_settings(2, ConfigOverrides {
+            dictionary_mode: Some(DictionaryMode::Required),
+            ..Default::default()
+        }).await?;
+        let result = repo.add_vote_state(2, 100, "らっぱ").await;
+        assert_eq!(result, Err(RepoError::NotInDictionary), "辞書にない単語が受け付けられました。\nresult: {:?}", result);
+        assert_or_ok!(repo.add_vote_state(2, 100, "リンゴ").await, "よみが辞書にある単語が受け付けられませんでした。");
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_get_rooms() -> Result<()> {
+    async fn test_computer_player() -> Result<()> {
         let repo = setup_repo().await?;
-        let room_list: Vec<u64> = vec![1, 3, 6, 8]; // ソート前提
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100], 1).await;
 
-        // 初期化時テスト
-        {
-            let result = repo.get_rooms().await;
-            assert_eq!(
-                result,
-                Ok(Vec::<u64>::new()),
-                "初期化時に空であるルームリストが空ではありませんでした。"
-            );
-        }
+        assert_eq!(repo.get_computer_level(1).await?, None);
+        repo.add_computer(1, Difficulty::Easy).await?;
+        assert_eq!(repo.add_computer(1, Difficulty::Hard).await, Err(RepoError::UserAlreadyExists));
+        assert_eq!(repo.get_queue(1).await?, vec![100, COMPUTER_USER_ID], "コンピューターが手番の輪に入っていません。");
+        repo.set_computer_level(1, Difficulty::Hard).await?;
+        assert_eq!(repo.get_computer_level(1).await?, Some(Difficulty::Hard));
 
-        setup_create_rooms(&repo, &room_list).await; // ルーム追加
+        // コンピューターは他の参加者の単語に賛成する
+        repo.add_vote_state(1, 100, "しりとり").await?;
+        let vote = repo.get_vote_state(1).await?.unwrap();
+        assert_eq!(vote.good, vec![COMPUTER_USER_ID], "コンピューターが賛成していません。\nvote: {:?}", vote);
+        assert!(matches!(repo.resolve_vote(1).await?, VoteResolution::Accepted { next_user_id: COMPUTER_USER_ID, .. }));
 
-        // 追加時テスト
-        {
-            let result = repo.get_rooms().await;
-            assert!(
-                result.is_ok(),
-                "ルームの取得で不明のエラーが発生しました。\nエラー: {:?}",
-                result.unwrap_err()
-            );
-            let mut got_room_list = result.unwrap();
-            got_room_list.sort();
-            assert_eq!(
-                got_room_list, room_list,
-                "追加したルームに不整合が発生しました。\n{:?} != {:?}",
-                got_room_list, room_list
-            );
-        }
+        repo.load_dictionary(
+            "test",
+            DictionaryFormat::Plain,
+            ["りんご", "りす", "りょかん", "しりとり", "すいか", "すし", "ごま"]
+                .into_iter()
+                .map(|word| DictionaryEntry::new(word, word))
+                .collect(),
+        )
+        .await?;
+
+        // 「ん」で終わる単語と既出の単語は選ばず、つよいコンピューターは続く単語が少ない方を選ぶ
+        let word = repo.choose_computer_word(1).await?;
+        assert_eq!(word, Some(DictionaryEntry::new("りんご", "りんご")), "想定した単語が選ばれていません。");
+
+        // 続けられる単語がなければNone
+        repo.insert_word(1, "りんご").await?;
+        repo.insert_word(1, "ごま").await?;
+        assert_eq!(repo.choose_computer_word(1).await?, None, "続かない単語が選ばれました。");
+
+        repo.remove_user(1, COMPUTER_USER_ID).await?;
+        assert_eq!(repo.choose_computer_word(1).await, Err(RepoError::UserNotFound));
 
         Ok(())
     }
 
-    // 単語操作テスト
-
     #[tokio::test]
-    async fn test_insert_word() -> Result<()> {
+    async fn test_computer_candidates_are_bounded() -> Result<()> {
         let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100], 1).await;
+        repo.add_computer(1, Difficulty::Hard).await?;
 
-        // ルーム未作成時挿入
-        {
-            let result = repo.insert_word(1, "test").await;
-            assert_eq!(
-                result,
-                Err(RepoError::RoomNotFound),
-                "存在しないルームへの単語挿入時、想定されない処理がされました。\nresult: {:?}",
-                result
-            );
+        // 候補の上限より多い単語と、除かれるべき単語
+        let kana: Vec<char> = "かきくけこさしすせそたちつてと".chars().collect();
+        let mut words = Vec::new();
+        for a in &kana {
+            for b in &kana {
+                for c in &kana {
+                    words.push([*a, *b, *c].iter().collect::<String>());
+                }
+            }
         }
+        words.extend(["ぬま", "ぬかん", "ぬし"].map(str::to_string));
+        assert!(words.len() > COMPUTER_CANDIDATE_LIMIT * 10);
+        let mut entries: Vec<DictionaryEntry> = words.iter().map(|word| DictionaryEntry::new(word, word)).collect();
+        entries.push(DictionaryEntry::new("カカオ", "かかお"));
+        repo.load_dictionary("test", DictionaryFormat::Plain, entries).await?;
+        repo.insert_word(1, "ぬま").await?;
+
+        let (all, nu, katakana) = repo
+            .db
+            .transaction(TransactionMode::Read, move |tx| -> Result<_> {
+                let mut config = load_room_config(tx, 1)?;
+                let all = sample_dictionary_words(tx, 1, &[], &config, Difficulty::Hard)?;
+                let nu = sample_dictionary_words(tx, 1, &['ぬ'], &config, Difficulty::Hard)?;
+                config.allowed_scripts = ScriptSet::from_scripts([Script::Katakana]);
+                let katakana = sample_dictionary_words(tx, 1, &[], &config, Difficulty::Hard)?;
+                Ok((all, nu, katakana))
+            })
+            .await?;
+        assert_eq!(all.len(), COMPUTER_CANDIDATE_LIMIT, "読み込んだ候補の数が上限と一致しません。");
+        assert_eq!(nu, vec![DictionaryEntry::new("ぬし", "ぬし")], "既出の単語か「ん」で終わる単語が候補に含まれています。");
+        assert_eq!(katakana, vec![DictionaryEntry::new("カカオ", "かかお")], "使えない文字の単語が候補に含まれています。");
 
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_force_resolve_and_delete_word() -> Result<()> {
+        let repo = setup_repo().await?;
         setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100, 101, 102], 1).await;
+        repo.set_queue(1, vec![100, 101, 102]).await?;
 
-        // ルームに挿入
-        {
-            let result = repo.insert_word(1, "apple").await;
-            assert_eq!(
-                result,
-                Ok(1),
-                "標準的な単語の追加に失敗しました。\nエラー: {:?}",
-                result.as_ref().err()
-            );
-        }
+        // 1票だけでは過半数に届かないが、強制的に確定させると投票済みの票で決まる
+        repo.add_vote_state(1, 100, "しりとり").await?;
+        repo.vote(1, 101, "good").await?;
+        assert_eq!(repo.resolve_vote(1).await?, VoteResolution::Pending);
+        assert!(matches!(repo.force_resolve_vote(1).await?, VoteResolution::Accepted { turn: 1, .. }));
+        assert_eq!(repo.force_resolve_vote(1).await, Err(RepoError::VoteNotExists));
 
-        // 同一ワード挿入
-        {
-            let result = repo.insert_word(1, "apple").await;
-            assert_eq!(
-                result,
-                Err(RepoError::WordAlreadyExists),
-                "すでに存在する単語の挿入で想定されていない処理がされました。\nresult: {:?}",
-                result
-            );
-        }
+        setup_insert_words(&repo, 1, &vec!["りんご"]).await;
+        let deleted = repo.delete_word(1, 1).await?;
+        assert_eq!(deleted.word, "しりとり");
+        assert_eq!(repo.get_words(1).await?, vec!["りんご"], "単語が削除されていません。");
+        assert_eq!(repo.delete_word(1, 1).await, Err(RepoError::WordNotFound));
+        assert_eq!(repo.delete_word(2, 1).await, Err(RepoError::RoomNotFound));
 
-        // ルーム削除時
-        {
-            let _ = repo.delete_room(1).await?;
-            let _ = repo.create_room(1).await?;
-            let result = repo.insert_word(1, "apple").await;
-            assert_eq!(
-                result,
-                Ok(1),
-                "再生成後のルームへの単語挿入でエラーが発生しました。\nエラー: {:?}",
-                result.as_ref().err()
-            );
-        }
+        Ok(())
+    }
 
-        //  NULLに準ずるワード挿入
-        {
-            let result = repo.insert_word(1, "").await;
-            assert_eq!(
-                result,
-                Err(RepoError::NullWord),
-                "Nullに準ずるワードの挿入で想定されていない処理がされました。\nエラー: {:?}",
-                result
-            );
+    #[tokio::test]
+    async fn test_export_import_room() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100, 101], 1).await;
+        repo.set_queue(1, vec![100, 101]).await?;
+        let settings = ConfigOverrides { min_word_length: Some(2), ..Default::default() };
+        repo.update_room_settings(1, settings).await?;
+        for (user_id, voter, word) in [(100, 101, "しりとり"), (101, 100, "林檎(りんご)")] {
+            repo.add_vote_state(1, user_id, word).await?;
+            repo.vote(1, voter, "good").await?;
+            repo.resolve_vote(1).await?;
         }
 
+        let archive = repo.export_room(1).await?;
+        assert_eq!(archive.status, RoomStatus::Active);
+        assert_eq!(archive.settings, settings, "ルームの設定が書き出されていません。");
+        assert_eq!(archive.words.len(), 2);
+        assert_eq!(repo.export_room(2).await, Err(RepoError::RoomNotFound));
+
+        // 取り込んだルームは、単語・投稿者・承認日時・投票結果・設定が同じになる
+        assert_eq!(repo.import_room(2, archive.clone()).await?, 2);
+        let imported = repo.export_room(2).await?;
+        assert_eq!(imported.settings, archive.settings);
+        assert_eq!(
+            imported.words,
+            archive.words.iter().map(|record| WordRecord { room_id: 2, ..record.clone() }).collect::<Vec<_>>(),
+            "取り込んだ履歴が一致しません。"
+        );
+        assert_eq!(repo.import_room(2, archive.clone()).await, Err(RepoError::RoomAlreadyExists));
+
+        // 続かない単語や既出の単語を含む履歴は取り込まず、ルームも作成しない
+        let with_words = |words: &[&str]| {
+            let mut archive = archive.clone();
+            archive.words = (1..)
+                .zip(words)
+                .map(|(turn, word)| WordRecord {
+                    turn,
+                    word: word.to_string(),
+                    reading: word.to_string(),
+                    ..archive.words[0].clone()
+                })
+                .collect();
+            archive
+        };
+        assert_eq!(repo.import_room(3, with_words(&["りんご", "しりとり"])).await, Err(RepoError::ChainMismatch));
+        assert_eq!(
+            repo.import_room(3, with_words(&["しりとり", "りす", "すし", "シリトリ"])).await,
+            Err(RepoError::WordAlreadyExists)
+        );
+        assert_eq!(repo.get_room_status(3).await, Err(RepoError::RoomNotFound), "失敗した取り込みでルームが残りました。");
+
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_get_words() -> Result<()> {
+    async fn test_player_stats() -> Result<()> {
         let repo = setup_repo().await?;
-        let word_list: Vec<&str> = vec!["apple", "banana", "god"];
+        setup_create_rooms(&repo, &vec![1, 2]).await;
+        setup_add_users(&repo, &vec![100, 101], 1).await;
+        repo.set_room_guild(1, 10).await?;
+        repo.set_queue(1, vec![100, 101]).await?;
 
-        // ルーム未作成時テスト
-        {
-            let result = repo.get_words(1).await;
-            assert_eq!(
-                result,
-                Err(RepoError::RoomNotFound),
-                "未作成のルームからの単語取得で、想定されていない処理がされました。\nresult: {:?}",
-                result.as_ref()
-            )
+        // 100が承認、101が否決の後に承認、100が「ん」で終わる単語で負け
+        for (user_id, voter, word, state) in [
+            (100, 101, "しりとり", "good"),
+            (101, 100, "りす", "bad"),
+            (101, 100, "りんご", "good"),
+            (100, 101, "ごはん", "good"),
+        ] {
+            repo.add_vote_state(1, user_id, word).await?;
+            repo.vote(1, voter, state).await?;
+            assert!(!matches!(repo.resolve_vote(1).await?, VoteResolution::Pending), "投票が確定していません。");
         }
 
+        let stats = repo.get_player_stats(StatsScope::Room(1), TimeWindow::All).await?;
+        assert_eq!(
+            stats,
+            vec![
+                PlayerStats {
+                    user_id: 100,
+                    accepted: 2,
+                    rejected: 0,
+                    won: 0,
+                    lost: 1,
+                    longest_word: Some("しりとり".to_string()),
+                    longest_length: 4,
+                    fastest_secs: Some(0),
+                    best_streak: 2,
+                },
+                PlayerStats {
+                    user_id: 101,
+                    accepted: 1,
+                    rejected: 1,
+                    won: 1,
+                    lost: 0,
+                    longest_word: Some("りんご".to_string()),
+                    longest_length: 3,
+                    fastest_secs: Some(0),
+                    best_streak: 1,
+                },
+            ],
+            "成績が正しく集計されていません。"
+        );
+
+        // サーバー全体と期間
+        assert_eq!(repo.get_player_stats(StatsScope::Guild(10), TimeWindow::Day).await?, stats);
+        assert_eq!(repo.get_player_stats(StatsScope::Guild(11), TimeWindow::All).await?, vec![]);
+        assert_eq!(repo.get_player_stats(StatsScope::Room(2), TimeWindow::All).await?, vec![]);
+
+        assert_eq!(repo.get_user_stats(StatsScope::Room(1), TimeWindow::Week, 101).await?, stats[1]);
+        assert_eq!(
+            repo.get_user_stats(StatsScope::Room(1), TimeWindow::All, 999).await?,
+            PlayerStats { user_id: 999, ..Default::default() },
+            "記録のないユーザーの成績が0になっていません。"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_process_timeouts() -> Result<()> {
+        let repo = setup_repo().await?;
         setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100, 101, 102], 1).await;
+        repo.set_queue(1, vec![100, 101, 102]).await?;
+        let mut limits = TimeLimits {
+            vote_secs: Some(60),
+            turn_secs: Some(60),
+            on_turn_timeout: TurnTimeoutAction::Skip,
+        };
+        repo.set_time_limits(1, limits).await?;
 
-        // 作成後空取得テスト
+        // 期限前は何もしない
+        repo.add_vote_state(1, 100, "りんご").await?;
+        repo.set_vote_message(1, 500).await?;
+        repo.vote(1, 101, "good").await?;
+        assert_eq!(repo.process_timeouts().await, Ok((vec![], vec![])), "期限前に投票が確定しました。");
+
+        // 投票の期限切れ
         {
-            let result = repo.get_words(1).await;
-            assert_eq!(
-                result,
-                Ok(Vec::<String>::new()),
-                "単語情報のないルームからの単語取得において、空の配列が返りませんでした。\nresult: {:?}",
-                result.as_ref()
+            backdate_vote(&repo, 1, 120).await;
+            let (events, _) = repo.process_timeouts().await?;
+            assert!(
+                matches!(
+                    events.as_slice(),
+                    [TimeoutEvent::VoteResolved {
+                        room_id: 1,
+                        message_id: Some(500),
+                        resolution: VoteResolution::Accepted { next_user_id: 101, .. },
+                    }]
+                ),
+                "期限切れの投票が投票済みの票で確定しませんでした。\nevents: {:?}",
+                events
             );
         }
 
-        setup_insert_words(&repo, 1, &word_list).await;
-
-        // 単語挿入後テスト
+        // 手番の時間切れで次のユーザーへ飛ばす
         {
-            let result = repo.get_words(1).await;
-            assert_or_ok!(result, "正常な単語軍の取得でエラーが発生しました。");
-            let mut original = word_list.clone(); let mut target = result.unwrap(); original.sort();
-            target.sort();
+            backdate_vote(&repo, 1, 120).await;
+            let (events, _) = repo.process_timeouts().await?;
             assert_eq!(
-                original, target,
-                "挿入した単語群と取得した単語群が異なります。\noriginal: {:?}\ntarget: {:?}",
-                original, target
+                events,
+                vec![TimeoutEvent::TurnSkipped { room_id: 1, user_id: 101, next_user_id: 102 }],
+                "時間切れの手番が飛ばされませんでした。"
             );
+            assert_eq!(repo.process_timeouts().await, Ok((vec![], vec![])), "飛ばした直後の手番が時間切れになりました。");
         }
 
-        setup_delete_rooms(&repo, &vec![1]).await;
-        setup_create_rooms(&repo, &vec![1]).await;
-        // ルーム削除後取得テスト
+        // 手番の時間切れで脱落させる
         {
-            let result = repo.get_words(1).await;
+            limits.on_turn_timeout = TurnTimeoutAction::Eliminate;
+            repo.set_time_limits(1, limits).await?;
+            backdate_vote(&repo, 1, 120).await;
+            let (events, _) = repo.process_timeouts().await?;
             assert_eq!(
-                result,
-                Ok(Vec::<String>::new()),
-                "再作成されたルームからからのリストが返りませんでした。\nresult: {:?}",
-                result.as_ref()
+                events,
+                vec![TimeoutEvent::PlayerEliminated { room_id: 1, user_id: 102, next_user_id: Some(100) }],
+                "時間切れのユーザーが脱落しませんでした。"
             );
+            assert_eq!(repo.get_members(1).await?, vec![100, 101], "脱落したユーザーが参加者に残っています。");
+            let vote = repo.get_vote_state(1).await?.expect("手番が取得できませんでした。");
+            assert_eq!(vote.user_id, 100, "脱落後に手番が次のユーザーへ移っていません。");
         }
 
         Ok(())
     }
-    
+
     #[tokio::test]
-    async fn test_vote() -> Result<()> {
+    async fn test_process_timeouts_skips_failed_room() -> Result<()> {
         let repo = setup_repo().await?;
-        let _guard = TestGuard {
-            repo: Arc::new(repo.clone()),
-            dumppath: "dump/dump_vote_test.db".into(),
+        setup_create_rooms(&repo, &vec![1, 2]).await;
+        let limits = TimeLimits {
+            vote_secs: None,
+            turn_secs: Some(60),
+            on_turn_timeout: TurnTimeoutAction::Skip,
         };
-        
+        for (room_id, users) in [(1, vec![100, 101]), (2, vec![200, 201])] {
+            setup_add_users(&repo, &users, room_id).await;
+            repo.set_queue(room_id, users.clone()).await?;
+            repo.set_time_limits(room_id, limits).await?;
+            // 最初の単語を承認し、次のユーザーの手番にする
+            repo.add_vote_state(room_id, users[0], "りんご").await?;
+            repo.vote(room_id, users[1], "good").await?;
+            repo.force_resolve_vote(room_id).await?;
+            backdate_vote(&repo, room_id, 120).await;
+        }
+
+        // 設定が壊れたルームは飛ばし、他のルームの処理は続ける
+        repo.db
+            .execute("UPDATE room_settings SET language = 'unknown' WHERE room_id = 2", [])
+            .await?;
+        let (events, failures) = repo.process_timeouts().await?;
+        assert_eq!(
+            events,
+            vec![TimeoutEvent::TurnSkipped { room_id: 1, user_id: 101, next_user_id: 100 }],
+            "壊れたルームのために他のルームの処理が止まりました。"
+        );
+        assert!(
+            matches!(failures.as_slice(), [(2, RepoError::Other(_))]),
+            "処理に失敗したルームが返されていません。\nfailures: {:?}",
+            failures
+        );
+        let vote = repo.get_vote_state(2).await?.expect("手番が取得できませんでした。");
+        assert_eq!(vote.user_id, 201, "処理に失敗したルームの変更が取り消されていません。");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_process_timeouts_no_objection() -> Result<()> {
+        let repo = setup_repo().await?;
+        setup_create_rooms(&repo, &vec![1]).await;
+        setup_add_users(&repo, &vec![100, 101], 1).await;
+        repo.set_queue(1, vec![100, 101]).await?;
+        repo.set_vote_rule(1, VoteRule { policy: VotePolicy::NoObjection { seconds: 30 }, quorum: 0 }).await?;
+
+        // 投票の期限がなくても、異議なし期間が過ぎれば承認される
+        repo.add_vote_state(1, 100, "りんご").await?;
+        assert_eq!(repo.process_timeouts().await, Ok((vec![], vec![])), "異議なし期間の前に投票が確定しました。");
+        backdate_vote(&repo, 1, 60).await;
+        let (events, _) = repo.process_timeouts().await?;
+        assert!(
+            matches!(
+                events.as_slice(),
+                [TimeoutEvent::VoteResolved { resolution: VoteResolution::Accepted { .. }, .. }]
+            ),
+            "異議なし期間の経過で承認されませんでした。\nevents: {:?}",
+            events
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_word_history() -> Result<()> {
+        let repo = setup_repo().await?;
+
         // ルーム未作成テスト
         {
-            let result = repo.get_vote_state(1).await;
-            assert_eq!(result, Ok(None), "ルーム未生成時に空であるべき投票の取得に問題が発生しました。\nresult: {:?}", result);
+            let result = repo.get_last_word(1).await;
+            assert_eq!(result, Err(RepoError::RoomNotFound), "存在しないルームの最終単語取得で想定外の結果が返されました。\nresult: {:?}", result);
+            let result = repo.get_history_page(1, 0, 10).await;
+            assert_eq!(result, Err(RepoError::RoomNotFound), "存在しないルームの履歴取得で想定外の結果が返されました。\nresult: {:?}", result);
         }
-        
+
         setup_create_rooms(&repo, &vec![1]).await;
-        setup_add_users(&repo, &vec![100,101,102,103,104], 1).await;
 
-        // 投票未作成テスト
+        // 空の履歴
         {
-            let result = repo.get_vote_state(1).await;
-            assert_eq!(result, Ok(None), "初期化時に空であるべき投票の取得に問題が発生しました。\nresult: {:?}", result);
+            let result = repo.get_last_word(1).await;
+            assert_eq!(result, Ok(None), "履歴のないルームで最終単語が返されました。\nresult: {:?}", result);
         }
-        
-        // 未作成投票エラーテスト
+
+        let plays: Vec<(u64, &str, VoteTally)> = vec![
+            (100, "しりとり", VoteTally { good: 2, bad: 0 }),
+            (101, "りんご", VoteTally { good: 1, bad: 1 }),
+            (100, "ごりら", VoteTally { good: 2, bad: 0 }),
+            (101, "らっぱ", VoteTally { good: 0, bad: 0 }),
+        ];
+        for (i, (user_id, word, tally)) in plays.iter().enumerate() {
+            let turn = repo.record_word(1, Some(*user_id), word, *tally).await?;
+            assert_eq!(turn, i as u64 + 1, "手番が連番になっていません。");
+        }
+
+        // 最終単語
         {
-            let result = repo.vote(1, 100, "good".into()).await;
-            assert_eq!(result, Err(RepoError::VoteNotExists), "投票未作成時にVoteNotExistsエラー以外が返されました。\nresult: {:?}", result);
+            let last = repo.get_last_word(1).await?.expect("最終単語が取得できませんでした。");
+            assert_eq!((last.turn, last.word.as_str(), last.user_id), (4, "らっぱ", Some(101)), "最終単語が一致しません。\nlast: {:?}", last);
+            assert!(last.approved_at.is_some(), "承認日時が記録されていません。");
         }
-        
-        // 投票作成
+
+        // ページ取得（新しい順）
         {
-            let result = repo.add_vote_state(1, 100, "test".into()).await;
-            assert_or_ok!(result, "正常な投票の作成でエラーが発生しました。");
+            let page0: Vec<String> = repo.get_history_page(1, 0, 3).await?.into_iter().map(|r| r.word).collect();
+            assert_eq!(page0, vec!["らっぱ", "ごりら", "りんご"], "1ページ目の履歴が一致しません。");
+            let page1: Vec<String> = repo.get_history_page(1, 1, 3).await?.into_iter().map(|r| r.word).collect();
+            assert_eq!(page1, vec!["しりとり"], "2ページ目の履歴が一致しません。");
+            let page2 = repo.get_history_page(1, 2, 3).await?;
+            assert!(page2.is_empty(), "範囲外のページが空ではありません。\npage: {:?}", page2);
         }
-        
-        // 投票
+
+        // ユーザーごとの単語
         {
-            let result_good = repo.vote(1, 101, "good".into()).await;
-            assert_or_ok!(result_good, "goodの投票に失敗しました。");
-            let result_bad = repo.vote(1, 101, "bad".into()).await;
-            assert_or_ok!(result_bad, "badの投票に失敗しました。");
-            let result_none = repo.vote(1, 101, "none".into()).await;
-            assert_or_ok!(result_none, "noneの投票に失敗しました。");
+            let words = repo.get_words_by_user(1, 100).await?;
+            let summary: Vec<(u64, String, VoteTally)> = words.into_iter().map(|r| (r.turn, r.word, r.tally)).collect();
+            assert_eq!(
+                summary,
+                vec![(1, "しりとり".to_string(), VoteTally { good: 2, bad: 0 }), (3, "ごりら".to_string(), VoteTally { good: 2, bad: 0 })],
+                "ユーザーごとの単語が一致しません。"
+            );
         }
-        
-        // 不正投票
+
+        // 順序付きの単語一覧
         {
-            let result_null_state = repo.vote(1, 102, "".into()).await;
-            assert_eq!(result_null_state, Err(RepoError::InvalidVoteState), "空文字列の投票でInvalidVoteState以外のエラーが発生しました。\nresult: {:?}", result_null_state);
-            let result_invalid_state = repo.vote(1, 103, "invalid".into()).await;
-            assert_eq!(result_invalid_state, Err(RepoError::InvalidVoteState), "不正な投票でInvalidVoteState以外のエラーが発生しました。\nresult: {:?}", result_invalid_state);
+            let result = repo.get_words(1).await;
+            assert_eq!(result, Ok(vec!["しりとり".to_string(), "りんご".into(), "ごりら".into(), "らっぱ".into()]), "単語一覧が手番順ではありません。");
         }
-        
+
         Ok(())
     }
 
@@ -882,8 +4232,3 @@ mod tests {
     }
      */
 }
-
-fn row_to_u64(row: &Row<'_>, idx: usize) -> Result<u64, SqliteError> {
-    let i: i64 = row.get(idx)?;
-    Ok(i64_to_u64_bitwise(i))
-}
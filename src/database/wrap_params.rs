@@ -22,9 +22,9 @@ pub trait IntoValue {
     fn into_value(self) -> Value;
 }
 
-/// ---
-/// 各型の IntoValue 実装
-/// ---
+// ---
+// 各型の IntoValue 実装
+// ---
 
 impl IntoValue for u64 {
     fn into_value(self) -> Value {
@@ -38,6 +38,12 @@ impl IntoValue for i64 {
     }
 }
 
+impl IntoValue for u32 {
+    fn into_value(self) -> Value {
+        Value::Integer(self as i64)
+    }
+}
+
 impl IntoValue for &str {
     fn into_value(self) -> Value {
         Value::Text(self.to_string())
@@ -62,6 +68,15 @@ impl IntoValue for f64 {
     }
 }
 
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        match self {
+            Some(v) => v.into_value(),
+            None => Value::Null,
+        }
+    }
+}
+
 /// ---
 /// 汎用関数：IntoValue を呼び出す
 /// ---
@@ -0,0 +1,124 @@
+// src/dictionary/mod.rs
+pub mod parser;
+
+use anyhow::{Context, Result};
+
+use crate::rules::canonical::canonical_key;
+
+/// ---
+/// 読み込める辞書の形式
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DictionaryFormat {
+    /// 1行に1語（`表記` または `表記<TAB>よみ`、`表記,よみ`）
+    Plain,
+    /// MeCab IPADICのCSV（名詞のみ）
+    Ipadic,
+    /// MeCab UniDicのCSV（名詞のみ）
+    Unidic,
+    /// SKK-JISYO（送りなしエントリのみ）
+    Skk,
+    /// JMdictのXML
+    Jmdict,
+}
+
+impl DictionaryFormat {
+    pub const ALL: [DictionaryFormat; 5] = [
+        DictionaryFormat::Plain,
+        DictionaryFormat::Ipadic,
+        DictionaryFormat::Unidic,
+        DictionaryFormat::Skk,
+        DictionaryFormat::Jmdict,
+    ];
+
+    /// データベース保存用の文字列を返します
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DictionaryFormat::Plain => "plain",
+            DictionaryFormat::Ipadic => "ipadic",
+            DictionaryFormat::Unidic => "unidic",
+            DictionaryFormat::Skk => "skk",
+            DictionaryFormat::Jmdict => "jmdict",
+        }
+    }
+
+    /// データベースの文字列から変換します
+    pub fn parse(s: &str) -> Option<Self> {
+        DictionaryFormat::ALL.into_iter().find(|format| format.as_str() == s)
+    }
+}
+
+/// ---
+/// 辞書の1語
+/// readingはnormalize_readingで正規化したよみです。
+/// ---
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DictionaryEntry {
+    pub surface: String,
+    pub reading: String,
+}
+
+impl DictionaryEntry {
+    pub fn new(surface: &str, reading: &str) -> Self {
+        Self {
+            surface: surface.trim().to_string(),
+            reading: normalize_reading(reading),
+        }
+    }
+}
+
+/// ---
+/// 検索用によみを正規化します。
+/// 既出判定の正規化キーと同じ規則（NFKC、カタカナ → ひらがな、英字 → 小文字、空白の除去）です。
+/// ---
+pub fn normalize_reading(reading: &str) -> String {
+    canonical_key(reading)
+}
+
+/// ---
+/// 辞書ファイルの内容を読み込みます。
+/// UTF-8として読めない場合は、SKK-JISYOやIPADICの配布形式であるEUC-JPとして読みます。
+/// ---
+pub fn parse(format: DictionaryFormat, bytes: &[u8]) -> Result<Vec<DictionaryEntry>> {
+    let text = decode(bytes)?;
+    let entries = match format {
+        DictionaryFormat::Plain => parser::parse_plain(&text),
+        DictionaryFormat::Ipadic => parser::parse_mecab(&text, parser::IPADIC_READING_COLUMN),
+        DictionaryFormat::Unidic => parser::parse_mecab(&text, parser::UNIDIC_READING_COLUMN),
+        DictionaryFormat::Skk => parser::parse_skk(&text),
+        DictionaryFormat::Jmdict => parser::parse_jmdict(&text).context("JMdictのXMLを読み込めません")?,
+    };
+    Ok(entries)
+}
+
+fn decode(bytes: &[u8]) -> Result<String> {
+    // BOMは読み飛ばす
+    let bytes = bytes.strip_prefix("\u{FEFF}".as_bytes()).unwrap_or(bytes);
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Ok(text.to_string());
+    }
+    let (text, _, had_errors) = encoding_rs::EUC_JP.decode(bytes);
+    anyhow::ensure!(!had_errors, "辞書ファイルの文字コードがUTF-8またはEUC-JPではありません");
+    Ok(text.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_reading() {
+        assert_eq!(normalize_reading("リンゴ"), "りんご");
+        assert_eq!(normalize_reading(" Apple Pie "), "applepie");
+        assert_eq!(normalize_reading("ラーメン"), "らーめん", "長音記号が保持されていません。");
+        assert_eq!(normalize_reading("ｺﾞﾘﾗ"), "ごりら", "半角カナが正規化されていません。");
+    }
+
+    #[test]
+    fn test_decode_euc_jp() -> Result<()> {
+        let (bytes, _, _) = encoding_rs::EUC_JP.encode("りんご /林檎/\n");
+        let entries = parse(DictionaryFormat::Skk, &bytes)?;
+        assert_eq!(entries, vec![DictionaryEntry::new("林檎", "りんご")], "EUC-JPの辞書が読み込めません。");
+        Ok(())
+    }
+}
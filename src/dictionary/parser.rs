@@ -0,0 +1,283 @@
+// src/dictionary/parser.rs
+use anyhow::Result;
+use quick_xml::{escape::resolve_predefined_entity, events::Event, Reader};
+
+use crate::dictionary::DictionaryEntry;
+
+/// IPADICのCSVで「読み」が入っている列
+pub const IPADIC_READING_COLUMN: usize = 11;
+/// UniDicのCSVで「語彙素読み(lForm)」が入っている列
+pub const UNIDIC_READING_COLUMN: usize = 10;
+
+/// 品詞が入っている列（IPADIC・UniDic共通）
+const POS_COLUMN: usize = 4;
+
+/// ---
+/// 1行に1語のテキストを読み込みます。
+/// `表記<TAB>よみ` または `表記,よみ` の形式でよみを指定できます。
+/// よみがない場合は表記をそのままよみとして扱います（かな・英字の単語リスト向け）。
+/// 空行と#で始まる行は読み飛ばします。
+/// ---
+pub fn parse_plain(text: &str) -> Vec<DictionaryEntry> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.splitn(2, ['\t', ',']).map(str::trim);
+            let surface = fields.next().filter(|s| !s.is_empty())?;
+            let reading = fields.next().filter(|s| !s.is_empty()).unwrap_or(surface);
+            Some(DictionaryEntry::new(surface, reading))
+        })
+        .collect()
+}
+
+/// ---
+/// MeCab辞書（IPADIC・UniDic）のCSVから名詞を読み込みます。
+/// よみのない語（"*"）は読み飛ばします。
+/// ---
+pub fn parse_mecab(text: &str, reading_column: usize) -> Vec<DictionaryEntry> {
+    text.lines()
+        .filter_map(|line| {
+            let fields = split_csv_line(line);
+            let surface = fields.first()?;
+            let pos = fields.get(POS_COLUMN)?;
+            let reading = fields.get(reading_column)?;
+            if pos != "名詞" || surface.is_empty() || reading.is_empty() || reading == "*" {
+                return None;
+            }
+            Some(DictionaryEntry::new(surface, reading))
+        })
+        .collect()
+}
+
+/// ダブルクォートで囲まれた値を含むCSVの1行を分割します
+pub fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// ---
+/// SKK-JISYOを読み込みます（`よみ /候補1/候補2;注釈/`）。
+/// 送りありのエントリ、接頭辞・接尾辞、Lisp式の候補は読み飛ばします。
+/// ---
+pub fn parse_skk(text: &str) -> Vec<DictionaryEntry> {
+    let mut entries = Vec::new();
+    for line in text.lines() {
+        if line.starts_with(';') {
+            continue;
+        }
+        let Some((reading, candidates)) = line.split_once(' ') else {
+            continue;
+        };
+        let is_okuri_ari = reading
+            .chars()
+            .last()
+            .is_some_and(|c| c.is_ascii_alphabetic())
+            && !reading.chars().all(|c| c.is_ascii_alphanumeric());
+        if reading.is_empty() || is_okuri_ari || reading.starts_with('>') || reading.ends_with('>') {
+            continue;
+        }
+
+        for candidate in candidates.trim().trim_matches('/').split('/') {
+            let surface = candidate.split(';').next().unwrap_or_default().trim();
+            if surface.is_empty() || surface.starts_with('(') {
+                continue;
+            }
+            entries.push(DictionaryEntry::new(surface, reading));
+        }
+    }
+    entries
+}
+
+/// ---
+/// JMdictのXMLを読み込みます。
+/// 漢字表記(keb)ごとに、対応するよみ(reb)との組を登録します。
+/// re_restrがあるよみは指定された表記とだけ組み合わせ、re_nokanjiのよみと
+/// 漢字表記のないエントリはよみをそのまま表記として登録します。
+/// ---
+pub fn parse_jmdict(text: &str) -> Result<Vec<DictionaryEntry>> {
+    /// 読み取り中の<r_ele>
+    #[derive(Default)]
+    struct ReadingElement {
+        reb: String,
+        restrictions: Vec<String>,
+        no_kanji: bool,
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Field {
+        Keb,
+        Reb,
+        ReRestr,
+    }
+
+    let mut reader = Reader::from_str(text);
+    reader.config_mut().trim_text(true);
+
+    let mut entries = Vec::new();
+    let mut kebs: Vec<String> = Vec::new();
+    let mut readings: Vec<ReadingElement> = Vec::new();
+    let mut field: Option<Field> = None;
+    let mut value = String::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.name().as_ref() {
+                b"entry" => {
+                    kebs.clear();
+                    readings.clear();
+                }
+                b"r_ele" => readings.push(ReadingElement::default()),
+                b"keb" => field = Some(Field::Keb),
+                b"reb" => field = Some(Field::Reb),
+                b"re_restr" => field = Some(Field::ReRestr),
+                _ => {}
+            },
+            Event::Empty(e) if e.name().as_ref() == b"re_nokanji" => {
+                if let Some(reading) = readings.last_mut() {
+                    reading.no_kanji = true;
+                }
+            }
+            Event::Text(t) if field.is_some() => value.push_str(&t.decode()?),
+            Event::GeneralRef(r) if field.is_some() => {
+                let name = r.decode()?;
+                if let Some(c) = r.resolve_char_ref()? {
+                    value.push(c);
+                } else if let Some(resolved) = resolve_predefined_entity(&name) {
+                    value.push_str(resolved);
+                }
+            }
+            Event::End(e) => match e.name().as_ref() {
+                b"keb" | b"reb" | b"re_restr" => {
+                    let text = std::mem::take(&mut value);
+                    match (field.take(), readings.last_mut()) {
+                        (Some(Field::Keb), _) => kebs.push(text),
+                        (Some(Field::Reb), Some(reading)) => reading.reb = text,
+                        (Some(Field::ReRestr), Some(reading)) => reading.restrictions.push(text),
+                        _ => {}
+                    }
+                }
+                b"entry" => {
+                    for reading in &readings {
+                        if kebs.is_empty() || reading.no_kanji {
+                            entries.push(DictionaryEntry::new(&reading.reb, &reading.reb));
+                            continue;
+                        }
+                        for keb in &kebs {
+                            if reading.restrictions.is_empty() || reading.restrictions.contains(keb) {
+                                entries.push(DictionaryEntry::new(keb, &reading.reb));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(surface: &str, reading: &str) -> DictionaryEntry {
+        DictionaryEntry::new(surface, reading)
+    }
+
+    #[test]
+    fn test_parse_plain() {
+        let text = "# 単語リスト\nりんご\n林檎\tりんご\nゴリラ,ゴリラ\n\n";
+        assert_eq!(
+            parse_plain(text),
+            vec![entry("りんご", "りんご"), entry("林檎", "りんご"), entry("ゴリラ", "ごりら")]
+        );
+    }
+
+    #[test]
+    fn test_parse_ipadic() {
+        let text = "林檎,1285,1285,5000,名詞,一般,*,*,*,*,林檎,リンゴ,リンゴ\n\
+                    走る,772,772,5000,動詞,自立,*,*,五段・ラ行,基本形,走る,ハシル,ハシル\n\
+                    \"，\",5,5,500,名詞,サ変接続,*,*,*,*,*,*,*\n";
+        assert_eq!(parse_mecab(text, IPADIC_READING_COLUMN), vec![entry("林檎", "りんご")], "名詞のみが読み込まれていません。");
+    }
+
+    #[test]
+    fn test_parse_unidic() {
+        let text = "林檎,5146,5146,8000,名詞,普通名詞,一般,*,*,*,リンゴ,林檎,林檎,リンゴ,林檎,リンゴ,漢,*,*,*,*\n\
+                    \"a,b\",5146,5146,8000,名詞,普通名詞,一般,*,*,*,エービー,a,b,エービー,a,b,外,*,*,*,*\n";
+        assert_eq!(
+            parse_mecab(text, UNIDIC_READING_COLUMN),
+            vec![entry("林檎", "りんご"), entry("a,b", "えーびー")],
+            "クォートされた値が正しく分割されていません。"
+        );
+    }
+
+    #[test]
+    fn test_parse_skk() {
+        let text = ";; okuri-ari entries.\n\
+                    あるk /歩/\n\
+                    ;; okuri-nasi entries.\n\
+                    りんご /林檎;果物/(concat \"x\")/\n\
+                    ご> /御/\n\
+                    かんじ /漢字/感じ/\n";
+        assert_eq!(
+            parse_skk(text),
+            vec![entry("林檎", "りんご"), entry("漢字", "かんじ"), entry("感じ", "かんじ")]
+        );
+    }
+
+    #[test]
+    fn test_parse_jmdict() -> Result<()> {
+        let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE JMdict [
+<!ENTITY n "noun (common) (futsuumeishi)">
+]>
+<JMdict>
+<entry>
+<ent_seq>1</ent_seq>
+<k_ele><keb>林檎</keb></k_ele>
+<k_ele><keb>苹果</keb></k_ele>
+<r_ele><reb>りんご</reb></r_ele>
+<r_ele><reb>へいか</reb><re_restr>苹果</re_restr></r_ele>
+<r_ele><reb>リンゴ</reb><re_nokanji/></r_ele>
+<sense><pos>&n;</pos><gloss>apple</gloss></sense>
+</entry>
+<entry>
+<ent_seq>2</ent_seq>
+<r_ele><reb>ゴリラ</reb></r_ele>
+<sense><pos>&n;</pos><gloss>gorilla</gloss></sense>
+</entry>
+</JMdict>
+"#;
+        assert_eq!(
+            parse_jmdict(text)?,
+            vec![
+                entry("林檎", "りんご"),
+                entry("苹果", "りんご"),
+                entry("苹果", "へいか"),
+                entry("リンゴ", "りんご"),
+                entry("ゴリラ", "ごりら"),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,8 @@
+// src/lib.rs
+// Botと管理用CLI（shiritori-admin）で共有するモジュール
+pub mod archive;
+pub mod bot;
+pub mod database;
+pub mod dictionary;
+pub mod macros;
+pub mod rules;
@@ -4,9 +4,8 @@ macro_rules! db_to_repo {
     // 第二引数: { SQLITE_CONSTRAINT_XXXX => RepoError::Something, ... }
     ($expr:expr, { $( $sqlite_code:ident => $repo_variant:expr ),* $(,)? }) => {{
         use rusqlite::Error as SqliteError;
-        use rusqlite::ffi;
-        use crate::database::db::DatabaseError;
-        use crate::database::repository::RepoError;
+        use $crate::database::db::DatabaseError;
+        use $crate::database::repository::RepoError;
 
         match $expr {
             Ok(val) => Ok::<_, RepoError>(val),
@@ -15,7 +14,7 @@ macro_rules! db_to_repo {
                     SqliteError::SqliteFailure(ref ffi_err, _) => {
                         match ffi_err.extended_code {
                             $(
-                                code if code == ffi::$sqlite_code as i32 => Err($repo_variant),
+                                code if code == rusqlite::ffi::$sqlite_code as i32 => Err($repo_variant),
                             )*
                             _ => Err(RepoError::Database(sql_err)),
                         }
@@ -25,6 +24,7 @@ macro_rules! db_to_repo {
                 DatabaseError::Join(join_err) => {
                     Err(RepoError::Other(anyhow::anyhow!(join_err)))
                 }
+                other => Err(RepoError::Other(anyhow::anyhow!(other))),
             }
         }
     }};
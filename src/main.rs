@@ -1,19 +1,19 @@
 use std::sync::{atomic::AtomicBool, Arc};
 
 use anyhow::Result;
-use signal_hook::{consts::SIGTERM, flag, low_level::exit};
+use signal_hook::{consts::{SIGHUP, SIGINT, SIGTERM}, flag, iterator::Signals, low_level::exit};
 
-use crate::{bot::{config::BotConfig}};
+use shiritori::{
+    bot::{config::BotConfig, shiritori_bot::Bot},
+    database::{db::DataBase, migration::MigrationMode, repository::Repository},
+    dictionary::{self, DictionaryFormat},
+};
 
-mod bot;
-mod database;
-mod macros;
+/// 安全に終了するシグナル
+const TERM_SIGNALS: &[i32] = &[SIGTERM, SIGINT, SIGHUP];
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let term_flag = Arc::new(AtomicBool::new(false));
-    flag::register_conditional_default(SIGTERM, Arc::clone(&term_flag)).unwrap();
-
     let config = match BotConfig::from_env() {
         Ok(config) => config,
         Err(e) => {
@@ -21,14 +21,84 @@ async fn main() -> Result<()> {
             exit(1);
         }
     };
-    
-    loop {
 
+    // スキーマ移行の内容を確認して終了する
+    if std::env::args().any(|arg| arg == "--migrate-dry-run") {
+        let db = DataBase::new(&config.db_path()).await?;
+        let report = db.migrate(MigrationMode::DryRun).await?;
+        println!("schema version: {} -> {}", report.from_version, report.to_version);
+        for (version, name) in report.applied {
+            println!("  pending: {:04}_{}", version, name);
+        }
+        return Ok(());
+    }
+
+    // 辞書ファイルを読み込んで終了する（--load-dictionary FORMAT PATH [NAME]）
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(index) = args.iter().position(|arg| arg == "--load-dictionary") {
+        let (Some(format), Some(path)) = (args.get(index + 1), args.get(index + 2)) else {
+            anyhow::bail!("usage: --load-dictionary <plain|ipadic|unidic|skk|jmdict> <PATH> [NAME]");
+        };
+        let format = DictionaryFormat::parse(format).ok_or_else(|| anyhow::anyhow!("unknown dictionary format: {}", format))?;
+        let path = std::path::Path::new(path);
+        let name = match args.get(index + 3) {
+            Some(name) => name.clone(),
+            None => path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default(),
+        };
+
+        let db = DataBase::new(&config.db_path()).await?;
+        db.migrate(MigrationMode::Apply).await?;
+        let entries = dictionary::parse(format, &std::fs::read(path)?)?;
+        let count = Repository::new(db)?.load_dictionary(&name, format, entries).await?;
+        println!("loaded dictionary {}: {} entries", name, count);
+        return Ok(());
     }
-    
+
+    let shutdown_signal = shutdown_signal()?;
+    let mut bot = Bot::new(config).await?;
+    bot.start()?;
+
+    // シグナルを受け取るか、Gatewayが終了するまで動かす
+    let gateway_result = tokio::select! {
+        signal = shutdown_signal => {
+            println!("received signal {}, shutting down", signal);
+            Ok(())
+        }
+        result = bot.wait_gateway() => result,
+    };
+
+    let shutdown_result = bot.shutdown().await;
+    gateway_result?;
+    shutdown_result?;
+    println!("shutdown complete");
     Ok(())
 }
 
-fn safe_shutdown() {
+/// ---
+/// 終了シグナルを受け取ると完了するFutureを返します。
+/// 終了処理中にもう一度シグナルを受け取った場合は、終了処理を待たずに終了します。
+/// ---
+fn shutdown_signal() -> Result<impl Future<Output = i32>> {
+    let term_now = Arc::new(AtomicBool::new(false));
+    for &signal in TERM_SIGNALS {
+        // 登録順に実行されるため、1回目のシグナルではterm_nowがまだfalseで強制終了しない
+        flag::register_conditional_shutdown(signal, 1, Arc::clone(&term_now))?;
+        flag::register(signal, Arc::clone(&term_now))?;
+    }
 
-}
\ No newline at end of file
+    let mut signals = Signals::new(TERM_SIGNALS)?;
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    std::thread::spawn(move || {
+        if let Some(signal) = signals.forever().next() {
+            let _ = tx.send(signal);
+        }
+    });
+
+    Ok(async move {
+        match rx.await {
+            Ok(signal) => signal,
+            // シグナルを待つスレッドが終了した場合は待ち続ける
+            Err(_) => std::future::pending().await,
+        }
+    })
+}
@@ -0,0 +1,38 @@
+// src/rules/canonical.rs
+use unicode_normalization::UnicodeNormalization;
+
+use crate::rules::kana::to_hiragana;
+
+/// ---
+/// 既出判定に使う単語の正規化キーを返します。
+/// NFKC正規化（全角英数字・半角カナの統一）、カタカナ → ひらがな、小文字化、空白の除去を行うため、
+/// 「リンゴ」「りんご」「ﾘﾝｺﾞ」や「Ａｐｐｌｅ」「apple」は同じキーになります。
+/// ---
+pub fn canonical_key(word: &str) -> String {
+    word.nfkc()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .map(to_hiragana)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_key() {
+        let cases = [
+            ("りんご", "りんご"),
+            ("リンゴ", "りんご"),
+            ("ﾘﾝｺﾞ", "りんご"),
+            ("ヴァイオリン", "ゔぁいおりん"),
+            ("Ａｐｐｌｅ", "apple"),
+            (" ice　cream ", "icecream"),
+            ("ラーメン", "らーめん"),
+        ];
+        for (word, expected) in cases {
+            assert_eq!(canonical_key(word), expected, "正規化キーが誤っています: {}", word);
+        }
+    }
+}
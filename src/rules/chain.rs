@@ -0,0 +1,206 @@
+// src/rules/chain.rs
+use crate::rules::kana::{
+    is_small_kana, to_hiragana, to_large_kana, to_unvoiced, vowel_of, LONG_VOWEL_MARK,
+};
+
+/// ---
+/// 長音記号「ー」で終わる単語の扱い
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LongVowelRule {
+    /// 直前の仮名の母音を使う（コーヒー → い）
+    #[default]
+    Vowel,
+    /// 長音記号を無視して直前の仮名を使う（コーヒー → ひ）
+    Skip,
+}
+
+impl LongVowelRule {
+    /// データベース保存用の文字列を返します
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LongVowelRule::Vowel => "vowel",
+            LongVowelRule::Skip => "skip",
+        }
+    }
+
+    /// データベースの文字列から変換します
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "vowel" => Some(LongVowelRule::Vowel),
+            "skip" => Some(LongVowelRule::Skip),
+            _ => None,
+        }
+    }
+
+    /// 表示用の名前を返します
+    pub fn label(&self) -> &'static str {
+        match self {
+            LongVowelRule::Vowel => "直前の母音から続ける",
+            LongVowelRule::Skip => "長音の前の文字から続ける",
+        }
+    }
+}
+
+/// ---
+/// しりとりの接続判定ルール
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainRules {
+    /// 長音記号で終わる単語の扱い
+    pub long_vowel: LongVowelRule,
+    /// 小書き仮名を通常の仮名として扱う（いしゃ → や）
+    /// falseの場合は拗音単位（いしゃ → しゃ）でつなぐ
+    pub expand_small_kana: bool,
+    /// 濁音・半濁音と清音を同一視する（か ＝ が ＝ ぱ）
+    pub ignore_dakuten: bool,
+}
+
+impl Default for ChainRules {
+    fn default() -> Self {
+        Self {
+            long_vowel: LongVowelRule::Vowel,
+            expand_small_kana: true,
+            ignore_dakuten: false,
+        }
+    }
+}
+
+/// 比較用に単語を正規化します（カタカナ → ひらがな、英字 → 小文字、記号・空白の除去）
+fn normalize(word: &str) -> Vec<char> {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .map(to_hiragana)
+        .collect()
+}
+
+fn finish_unit(unit: Vec<char>, rules: &ChainRules) -> String {
+    unit.into_iter()
+        .map(|c| if rules.ignore_dakuten { to_unvoiced(c) } else { c })
+        .collect()
+}
+
+/// ---
+/// 単語の末尾の接続単位（次の単語が始まるべき仮名）を返します。
+/// 接続に使える文字がない場合はNoneを返します。
+/// ---
+pub fn tail_unit(word: &str, rules: &ChainRules) -> Option<String> {
+    let chars = normalize(word);
+
+    // 末尾の長音記号を読み飛ばす
+    let end = chars.iter().rposition(|&c| c != LONG_VOWEL_MARK)? + 1;
+    let last = chars[end - 1];
+
+    if end < chars.len()
+        && rules.long_vowel == LongVowelRule::Vowel
+        && let Some(vowel) = vowel_of(last)
+    {
+        return Some(finish_unit(vec![vowel], rules));
+    }
+
+    let unit = if is_small_kana(last) {
+        if rules.expand_small_kana || end == 1 || last == 'っ' {
+            vec![to_large_kana(last)]
+        } else {
+            vec![chars[end - 2], last]
+        }
+    } else {
+        vec![last]
+    };
+
+    Some(finish_unit(unit, rules))
+}
+
+/// ---
+/// 単語の先頭の接続単位を返します。
+/// 接続に使える文字がない場合はNoneを返します。
+/// ---
+pub fn head_unit(word: &str, rules: &ChainRules) -> Option<String> {
+    let chars = normalize(word);
+    let first = *chars.first()?;
+
+    let unit = match chars.get(1) {
+        Some(&second) if !rules.expand_small_kana && is_small_kana(second) && second != 'っ' => {
+            vec![first, second]
+        }
+        _ => vec![to_large_kana(first)],
+    };
+
+    Some(finish_unit(unit, rules))
+}
+
+/// ---
+/// prevの次にnextを続けられるかどうかを判定します。
+/// ---
+pub fn is_chained(prev: &str, next: &str, rules: &ChainRules) -> bool {
+    match (tail_unit(prev, rules), head_unit(next, rules)) {
+        (Some(tail), Some(head)) => tail == head,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(long_vowel: LongVowelRule, expand_small_kana: bool, ignore_dakuten: bool) -> ChainRules {
+        ChainRules {
+            long_vowel,
+            expand_small_kana,
+            ignore_dakuten,
+        }
+    }
+
+    #[test]
+    fn test_basic_chain() {
+        let r = ChainRules::default();
+        assert!(is_chained("りんご", "ごりら", &r), "基本的なしりとりが接続されませんでした。");
+        assert!(!is_chained("りんご", "らっぱ", &r), "接続しない単語が接続されました。");
+        assert!(is_chained("apple", "Egg", &r), "英単語の大文字小文字が同一視されませんでした。");
+        assert!(!is_chained("", "りんご", &r), "空文字列から接続されました。");
+    }
+
+    #[test]
+    fn test_hiragana_katakana() {
+        let r = ChainRules::default();
+        assert!(is_chained("ゴリラ", "らっぱ", &r), "カタカナからひらがなへ接続されませんでした。");
+        assert!(is_chained("らっぱ", "パンダ", &r), "ひらがなからカタカナへ接続されませんでした。");
+    }
+
+    #[test]
+    fn test_long_vowel() {
+        let vowel = rules(LongVowelRule::Vowel, true, false);
+        assert_eq!(tail_unit("コーヒー", &vowel), Some("い".into()));
+        assert!(is_chained("コーヒー", "いす", &vowel), "長音の母音で接続されませんでした。");
+        assert!(!is_chained("コーヒー", "ひまわり", &vowel), "長音の母音以外で接続されました。");
+
+        let skip = rules(LongVowelRule::Skip, true, false);
+        assert_eq!(tail_unit("コーヒー", &skip), Some("ひ".into()));
+        assert!(is_chained("コーヒー", "ひまわり", &skip), "長音を無視した接続がされませんでした。");
+    }
+
+    #[test]
+    fn test_small_kana() {
+        let expand = rules(LongVowelRule::Vowel, true, false);
+        assert_eq!(tail_unit("いしゃ", &expand), Some("や".into()));
+        assert!(is_chained("いしゃ", "やかん", &expand), "小書き仮名が通常の仮名として扱われませんでした。");
+        assert!(is_chained("ジャー", "あり", &expand), "拗音+長音の母音で接続されませんでした。");
+
+        let syllable = rules(LongVowelRule::Vowel, false, false);
+        assert_eq!(tail_unit("いしゃ", &syllable), Some("しゃ".into()));
+        assert!(is_chained("いしゃ", "しゃしん", &syllable), "拗音単位で接続されませんでした。");
+        assert!(!is_chained("いしゃ", "やかん", &syllable), "拗音単位のルールで小書き仮名が拡張されました。");
+        assert_eq!(tail_unit("コップ", &syllable), Some("ぷ".into()));
+    }
+
+    #[test]
+    fn test_dakuten() {
+        let strict = rules(LongVowelRule::Vowel, true, false);
+        assert!(!is_chained("かば", "はさみ", &strict), "濁音と清音が同一視されました。");
+
+        let loose = rules(LongVowelRule::Vowel, true, true);
+        assert!(is_chained("かば", "はさみ", &loose), "濁音と清音が同一視されませんでした。");
+        assert!(is_chained("かば", "パン", &loose), "濁音と半濁音が同一視されませんでした。");
+    }
+}
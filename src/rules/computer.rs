@@ -0,0 +1,150 @@
+// src/rules/computer.rs
+use crate::rules::{
+    chain::{tail_unit, ChainRules},
+    kana::voiced_forms,
+};
+
+/// ---
+/// コンピューターの参加者として使うユーザーID
+/// DiscordのユーザーIDは0にならないため、実在のユーザーと重なりません。
+/// ---
+pub const COMPUTER_USER_ID: u64 = 0;
+
+/// コンピューターの参加者かどうか
+pub fn is_computer(user_id: u64) -> bool {
+    user_id == COMPUTER_USER_ID
+}
+
+/// ---
+/// コンピューターの強さ
+/// 弱いほど短い単語しか知らず、強いと相手が続けにくい単語を選びます。
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    /// データベース保存用の文字列を返します
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    /// データベースの文字列から変換します
+    pub fn parse(s: &str) -> Option<Self> {
+        Difficulty::ALL.into_iter().find(|difficulty| difficulty.as_str() == s)
+    }
+
+    /// 表示用の名前を返します
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "よわい",
+            Difficulty::Normal => "ふつう",
+            Difficulty::Hard => "つよい",
+        }
+    }
+
+    /// ---
+    /// 使える単語のよみの最大文字数
+    /// Noneは制限なしを表します。
+    /// ---
+    pub fn max_reading_len(&self) -> Option<usize> {
+        match self {
+            Difficulty::Easy => Some(4),
+            Difficulty::Normal => Some(6),
+            Difficulty::Hard => None,
+        }
+    }
+
+    /// よみの文字数が使える範囲かどうか
+    pub fn knows(&self, reading: &str) -> bool {
+        self.max_reading_len().is_none_or(|max| reading.chars().count() <= max)
+    }
+
+    /// 候補のうち、次に続く単語が最も少ないものを選ぶかどうか
+    pub fn prefers_hard_endings(&self) -> bool {
+        *self == Difficulty::Hard
+    }
+
+    /// ---
+    /// ランダムな順に並べた候補から出す単語を選びます。
+    /// つよいコンピューターはfollowers（次に続けられる単語の数）が最も少ない候補を、
+    /// それ以外は先頭の候補を選びます。
+    /// ---
+    pub fn choose<T>(&self, candidates: Vec<T>, mut followers: impl FnMut(&T) -> u64) -> Option<T> {
+        if self.prefers_hard_endings() {
+            // 同数の場合は先に並んでいる候補を選ぶ
+            candidates.into_iter().enumerate().min_by_key(|(i, c)| (followers(c), *i)).map(|(_, c)| c)
+        } else {
+            candidates.into_iter().next()
+        }
+    }
+}
+
+/// ---
+/// 前の単語のよみに続く単語の、よみの先頭になり得る文字を返します。
+/// 辞書の検索範囲を絞るためのもので、実際に続くかどうかはis_chainedで確認してください。
+/// ---
+pub fn head_chars(prev_reading: &str, rules: &ChainRules) -> Vec<char> {
+    let Some(first) = tail_unit(prev_reading, rules).and_then(|tail| tail.chars().next()) else {
+        return Vec::new();
+    };
+    let mut chars = vec![first];
+    if rules.ignore_dakuten {
+        chars.extend(voiced_forms(first));
+    }
+    chars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_difficulty_parse() {
+        for difficulty in Difficulty::ALL {
+            assert_eq!(Difficulty::parse(difficulty.as_str()), Some(difficulty));
+        }
+        assert_eq!(Difficulty::parse("invalid"), None);
+    }
+
+    #[test]
+    fn test_knows() {
+        assert!(Difficulty::Easy.knows("りんご"));
+        assert!(!Difficulty::Easy.knows("ごりらごりら"), "よわいコンピューターが長い単語を使えます。");
+        assert!(Difficulty::Normal.knows("ごりらごりら"));
+        assert!(Difficulty::Hard.knows("ごりらごりらごりら"), "つよいコンピューターの語彙が制限されています。");
+    }
+
+    #[test]
+    fn test_choose() {
+        let candidates = vec!["りす", "りんご", "りか"];
+        let followers = |word: &&str| match *word {
+            "りんご" => 1,
+            _ => 10,
+        };
+        assert_eq!(Difficulty::Normal.choose(candidates.clone(), followers), Some("りす"));
+        assert_eq!(Difficulty::Hard.choose(candidates, followers), Some("りんご"), "続きにくい単語が選ばれていません。");
+        assert_eq!(Difficulty::Hard.choose(Vec::<&str>::new(), |_| 0), None);
+    }
+
+    #[test]
+    fn test_head_chars() {
+        let strict = ChainRules::default();
+        assert_eq!(head_chars("りんご", &strict), vec!['ご']);
+        assert_eq!(head_chars("コーヒー", &strict), vec!['い']);
+
+        let loose = ChainRules { ignore_dakuten: true, ..Default::default() };
+        assert_eq!(head_chars("かば", &loose), vec!['は', 'ば', 'ぱ'], "濁音・半濁音の単語が候補に含まれていません。");
+        assert!(head_chars("", &strict).is_empty());
+    }
+}
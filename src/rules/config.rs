@@ -0,0 +1,263 @@
+// src/rules/config.rs
+use crate::rules::{
+    chain::{ChainRules, LongVowelRule},
+    script::ScriptSet,
+    terminal::NEndingRule,
+    timeout::{TimeLimits, TurnTimeoutAction},
+    vote::VoteRule,
+};
+
+/// ---
+/// ルームで使う言語
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    Japanese,
+    English,
+}
+
+impl Language {
+    /// データベース保存用の文字列を返します
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Language::Japanese => "ja",
+            Language::English => "en",
+        }
+    }
+
+    /// データベースの文字列から変換します
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "ja" => Some(Language::Japanese),
+            "en" => Some(Language::English),
+            _ => None,
+        }
+    }
+
+    /// 表示用の名前を返します
+    pub fn label(&self) -> &'static str {
+        match self {
+            Language::Japanese => "日本語",
+            Language::English => "英語",
+        }
+    }
+}
+
+/// ---
+/// 辞書に載っていない単語の扱い
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DictionaryMode {
+    /// 辞書に載っている単語だけを受け付けます
+    Required,
+    /// 投票メッセージに辞書に載っているかどうかを表示します
+    Advisory,
+    #[default]
+    Off,
+}
+
+impl DictionaryMode {
+    /// データベース保存用の文字列を返します
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DictionaryMode::Required => "required",
+            DictionaryMode::Advisory => "advisory",
+            DictionaryMode::Off => "off",
+        }
+    }
+
+    /// データベースの文字列から変換します
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "required" => Some(DictionaryMode::Required),
+            "advisory" => Some(DictionaryMode::Advisory),
+            "off" => Some(DictionaryMode::Off),
+            _ => None,
+        }
+    }
+
+    /// 表示用の名前を返します
+    pub fn label(&self) -> &'static str {
+        match self {
+            DictionaryMode::Required => "辞書にある単語のみ",
+            DictionaryMode::Advisory => "投票時に表示",
+            DictionaryMode::Off => "使わない",
+        }
+    }
+}
+
+/// ---
+/// ルームのゲーム設定
+/// サーバーの設定とルームの設定を既定値に重ねたものです。
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RoomConfig {
+    /// 「ん」で終わる単語の扱い
+    pub n_ending: NEndingRule,
+    pub vote_rule: VoteRule,
+    pub time_limits: TimeLimits,
+    /// 単語の最低文字数（0は制限なし）
+    pub min_word_length: u32,
+    pub allowed_scripts: ScriptSet,
+    pub language: Language,
+    pub dictionary_mode: DictionaryMode,
+    /// 単語の接続判定（長音・小書き仮名・濁音の扱い）
+    pub chain_rules: ChainRules,
+}
+
+impl RoomConfig {
+    /// wordが最低文字数を満たしているかどうかを判定します（記号・空白は数えません）
+    pub fn is_long_enough(&self, word: &str) -> bool {
+        word.chars().filter(|c| c.is_alphanumeric()).count() >= self.min_word_length as usize
+    }
+}
+
+/// ---
+/// サーバー・ルームごとに保存する設定
+/// Noneの項目は上位の設定（ルームならサーバー、サーバーなら既定値）を引き継ぎます。
+/// 制限時間は0で無制限を表します。
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConfigOverrides {
+    pub n_ending: Option<NEndingRule>,
+    pub vote_rule: Option<VoteRule>,
+    pub vote_timeout: Option<u32>,
+    pub turn_timeout: Option<u32>,
+    pub turn_timeout_action: Option<TurnTimeoutAction>,
+    pub min_word_length: Option<u32>,
+    pub allowed_scripts: Option<ScriptSet>,
+    pub language: Option<Language>,
+    pub dictionary_mode: Option<DictionaryMode>,
+    pub long_vowel: Option<LongVowelRule>,
+    pub expand_small_kana: Option<bool>,
+    pub ignore_dakuten: Option<bool>,
+}
+
+impl ConfigOverrides {
+    /// 何も設定していないかどうか
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// patchで設定された項目を上書きします
+    pub fn merge(&mut self, patch: &ConfigOverrides) {
+        self.n_ending = patch.n_ending.or(self.n_ending);
+        self.vote_rule = patch.vote_rule.or(self.vote_rule);
+        self.vote_timeout = patch.vote_timeout.or(self.vote_timeout);
+        self.turn_timeout = patch.turn_timeout.or(self.turn_timeout);
+        self.turn_timeout_action = patch.turn_timeout_action.or(self.turn_timeout_action);
+        self.min_word_length = patch.min_word_length.or(self.min_word_length);
+        self.allowed_scripts = patch.allowed_scripts.or(self.allowed_scripts);
+        self.language = patch.language.or(self.language);
+        self.dictionary_mode = patch.dictionary_mode.or(self.dictionary_mode);
+        self.long_vowel = patch.long_vowel.or(self.long_vowel);
+        self.expand_small_kana = patch.expand_small_kana.or(self.expand_small_kana);
+        self.ignore_dakuten = patch.ignore_dakuten.or(self.ignore_dakuten);
+    }
+
+    /// 設定された項目をconfigに反映します
+    pub fn apply(&self, config: &mut RoomConfig) {
+        if let Some(n_ending) = self.n_ending {
+            config.n_ending = n_ending;
+        }
+        if let Some(vote_rule) = self.vote_rule {
+            config.vote_rule = vote_rule;
+        }
+        if let Some(secs) = self.vote_timeout {
+            config.time_limits.vote_secs = Some(secs).filter(|&secs| secs > 0);
+        }
+        if let Some(secs) = self.turn_timeout {
+            config.time_limits.turn_secs = Some(secs).filter(|&secs| secs > 0);
+        }
+        if let Some(action) = self.turn_timeout_action {
+            config.time_limits.on_turn_timeout = action;
+        }
+        if let Some(length) = self.min_word_length {
+            config.min_word_length = length;
+        }
+        if let Some(scripts) = self.allowed_scripts {
+            config.allowed_scripts = scripts;
+        }
+        if let Some(language) = self.language {
+            config.language = language;
+        }
+        if let Some(mode) = self.dictionary_mode {
+            config.dictionary_mode = mode;
+        }
+        if let Some(long_vowel) = self.long_vowel {
+            config.chain_rules.long_vowel = long_vowel;
+        }
+        if let Some(expand) = self.expand_small_kana {
+            config.chain_rules.expand_small_kana = expand;
+        }
+        if let Some(ignore) = self.ignore_dakuten {
+            config.chain_rules.ignore_dakuten = ignore;
+        }
+    }
+
+    /// 制限時間をすべて設定します
+    pub fn with_time_limits(mut self, limits: TimeLimits) -> Self {
+        self.vote_timeout = Some(limits.vote_secs.unwrap_or(0));
+        self.turn_timeout = Some(limits.turn_secs.unwrap_or(0));
+        self.turn_timeout_action = Some(limits.on_turn_timeout);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{script::Script, vote::VotePolicy};
+
+    #[test]
+    fn test_layered_config() {
+        let guild = ConfigOverrides {
+            n_ending: Some(NEndingRule::Continue),
+            vote_timeout: Some(60),
+            min_word_length: Some(2),
+            ..Default::default()
+        };
+        let room = ConfigOverrides {
+            vote_timeout: Some(0),
+            allowed_scripts: Some(ScriptSet::from_scripts([Script::Hiragana])),
+            ignore_dakuten: Some(true),
+            ..Default::default()
+        };
+
+        let mut config = RoomConfig::default();
+        guild.apply(&mut config);
+        room.apply(&mut config);
+        assert_eq!(config.n_ending, NEndingRule::Continue, "サーバーの設定が引き継がれていません。");
+        assert_eq!(config.min_word_length, 2, "サーバーの設定が引き継がれていません。");
+        assert_eq!(config.time_limits.vote_secs, None, "ルームの設定(0 = 無制限)が優先されていません。");
+        assert!(!config.allowed_scripts.allows("カタカナ"), "ルームの文字種の設定が反映されていません。");
+        assert_eq!(config.language, Language::Japanese, "既定値が使われていません。");
+        assert!(config.chain_rules.ignore_dakuten, "ルームの接続判定の設定が反映されていません。");
+        assert!(config.chain_rules.expand_small_kana, "接続判定の既定値が使われていません。");
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut settings = ConfigOverrides {
+            vote_rule: Some(VoteRule { policy: VotePolicy::Unanimous, quorum: 0 }),
+            min_word_length: Some(3),
+            ..Default::default()
+        };
+        settings.merge(&ConfigOverrides {
+            min_word_length: Some(1),
+            language: Some(Language::English),
+            ..Default::default()
+        });
+        assert_eq!(settings.vote_rule.map(|rule| rule.policy), Some(VotePolicy::Unanimous), "未指定の項目が消えました。");
+        assert_eq!(settings.min_word_length, Some(1), "指定した項目が上書きされていません。");
+        assert_eq!(settings.language, Some(Language::English));
+    }
+
+    #[test]
+    fn test_word_length() {
+        let config = RoomConfig { min_word_length: 3, ..Default::default() };
+        assert!(config.is_long_enough("りんご"));
+        assert!(!config.is_long_enough("い・す"), "記号が文字数に数えられています。");
+    }
+}
@@ -0,0 +1,112 @@
+// src/rules/kana.rs
+
+/// ---
+/// 長音記号
+/// ---
+pub const LONG_VOWEL_MARK: char = 'ー';
+
+/// 小書き仮名 → 通常の仮名 の対応表
+const SMALL_KANA: [(char, char); 12] = [
+    ('ぁ', 'あ'),
+    ('ぃ', 'い'),
+    ('ぅ', 'う'),
+    ('ぇ', 'え'),
+    ('ぉ', 'お'),
+    ('っ', 'つ'),
+    ('ゃ', 'や'),
+    ('ゅ', 'ゆ'),
+    ('ょ', 'よ'),
+    ('ゎ', 'わ'),
+    ('ゕ', 'か'),
+    ('ゖ', 'け'),
+];
+
+/// 濁音・半濁音 → 清音 の対応表
+const VOICED_KANA: [(char, char); 26] = [
+    ('が', 'か'), ('ぎ', 'き'), ('ぐ', 'く'), ('げ', 'け'), ('ご', 'こ'),
+    ('ざ', 'さ'), ('じ', 'し'), ('ず', 'す'), ('ぜ', 'せ'), ('ぞ', 'そ'),
+    ('だ', 'た'), ('ぢ', 'ち'), ('づ', 'つ'), ('で', 'て'), ('ど', 'と'),
+    ('ば', 'は'), ('び', 'ひ'), ('ぶ', 'ふ'), ('べ', 'へ'), ('ぼ', 'ほ'),
+    ('ぱ', 'は'), ('ぴ', 'ひ'), ('ぷ', 'ふ'), ('ぺ', 'へ'), ('ぽ', 'ほ'),
+    ('ゔ', 'う'),
+];
+
+/// 母音ごとの清音（通常の大きさ）の仮名
+const VOWEL_ROWS: [(char, &str); 5] = [
+    ('あ', "あかさたなはまやらわ"),
+    ('い', "いきしちにひみりゐ"),
+    ('う', "うくすつぬふむゆる"),
+    ('え', "えけせてねへめれゑ"),
+    ('お', "おこそとのほもよろを"),
+];
+
+/// ---
+/// カタカナをひらがなに変換します。
+/// カタカナ以外の文字はそのまま返します。
+/// ---
+pub fn to_hiragana(c: char) -> char {
+    match c {
+        // ァ(U+30A1) 〜 ヶ(U+30F6) はひらがなと同じ並び
+        'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        _ => c,
+    }
+}
+
+/// ---
+/// 小書き仮名かどうかを判定します。
+/// ---
+pub fn is_small_kana(c: char) -> bool {
+    let c = to_hiragana(c);
+    SMALL_KANA.iter().any(|&(small, _)| small == c)
+}
+
+/// ---
+/// 小書き仮名を通常の大きさの仮名に変換します。
+/// 小書き仮名以外はそのまま返します。
+/// ---
+pub fn to_large_kana(c: char) -> char {
+    let c = to_hiragana(c);
+    SMALL_KANA
+        .iter()
+        .find(|&&(small, _)| small == c)
+        .map(|&(_, large)| large)
+        .unwrap_or(c)
+}
+
+/// ---
+/// 濁点・半濁点を取り除いた清音を返します。
+/// 濁音・半濁音以外はそのまま返します。
+/// ---
+pub fn to_unvoiced(c: char) -> char {
+    let c = to_hiragana(c);
+    VOICED_KANA
+        .iter()
+        .find(|&&(voiced, _)| voiced == c)
+        .map(|&(_, plain)| plain)
+        .unwrap_or(c)
+}
+
+/// ---
+/// 仮名の母音（あいうえお）を返します。
+/// 「ん」や仮名以外など、母音が定まらない場合はNoneを返します。
+/// ---
+pub fn vowel_of(c: char) -> Option<char> {
+    let base = to_unvoiced(to_large_kana(c));
+    VOWEL_ROWS
+        .iter()
+        .find(|(_, row)| row.contains(base))
+        .map(|&(vowel, _)| vowel)
+}
+
+/// ---
+/// 清音に濁点・半濁点を付けた仮名を返します（は → ば, ぱ）。
+/// 濁音・半濁音がない仮名の場合は空のベクタを返します。
+/// ---
+pub fn voiced_forms(c: char) -> Vec<char> {
+    let c = to_hiragana(c);
+    VOICED_KANA
+        .iter()
+        .filter(|&&(_, plain)| plain == c)
+        .map(|&(voiced, _)| voiced)
+        .collect()
+}
@@ -0,0 +1,11 @@
+pub mod kana;
+pub mod chain;
+pub mod terminal;
+pub mod vote;
+pub mod timeout;
+pub mod script;
+pub mod config;
+pub mod reading;
+pub mod canonical;
+pub mod computer;
+pub mod score;
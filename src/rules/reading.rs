@@ -0,0 +1,122 @@
+// src/rules/reading.rs
+use crate::rules::script::Script;
+
+/// ---
+/// 投稿された単語（表記とよみ）
+/// readingは投稿でよみが指定された場合のみSomeになります。
+/// ---
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordInput {
+    pub surface: String,
+    pub reading: Option<String>,
+}
+
+impl WordInput {
+    /// ---
+    /// 投稿を表記とよみに分けます。
+    /// `表記(よみ)`（全角の括弧も可）と `表記 よみ` の形式を受け付けます。
+    /// 空白区切りはよみが仮名だけの場合に限り、それ以外は全体を表記として扱います（英語の複合語など）。
+    /// ---
+    pub fn parse(input: &str) -> Self {
+        let input = input.trim();
+        Self::parse_parenthesized(input)
+            .or_else(|| Self::parse_spaced(input))
+            .unwrap_or_else(|| Self {
+                surface: input.to_string(),
+                reading: None,
+            })
+    }
+
+    fn parse_parenthesized(input: &str) -> Option<Self> {
+        let rest = input.strip_suffix(')').or_else(|| input.strip_suffix('）'))?;
+        let open = rest.rfind(['(', '（'])?;
+        let surface = rest[..open].trim();
+        let reading = rest[open..].trim_start_matches(['(', '（']).trim();
+        if surface.is_empty() || !is_valid_reading(reading) {
+            return None;
+        }
+        Some(Self {
+            surface: surface.to_string(),
+            reading: Some(reading.to_string()),
+        })
+    }
+
+    fn parse_spaced(input: &str) -> Option<Self> {
+        let (surface, reading) = input.rsplit_once(char::is_whitespace)?;
+        let (surface, reading) = (surface.trim(), reading.trim());
+        if surface.is_empty() || !is_kana(reading) {
+            return None;
+        }
+        Some(Self {
+            surface: surface.to_string(),
+            reading: Some(reading.to_string()),
+        })
+    }
+}
+
+/// ---
+/// 表記に漢字が含まれるかどうか
+/// 漢字を含まない表記は、表記そのものをよみとして扱えます。
+/// ---
+pub fn contains_kanji(word: &str) -> bool {
+    word.chars().any(|c| Script::of(c) == Some(Script::Kanji))
+}
+
+/// よみとして使える文字列か（空でなく、漢字を含まない）
+fn is_valid_reading(reading: &str) -> bool {
+    !reading.is_empty() && !contains_kanji(reading) && reading.chars().any(char::is_alphanumeric)
+}
+
+/// 仮名（と長音記号）だけの文字列か
+fn is_kana(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars().all(|c| {
+            c == 'ー' || matches!(Script::of(c), Some(Script::Hiragana | Script::Katakana))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(surface: &str, reading: Option<&str>) -> WordInput {
+        WordInput {
+            surface: surface.to_string(),
+            reading: reading.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let cases = [
+            ("林檎(りんご)", input("林檎", Some("りんご"))),
+            ("林檎（リンゴ）", input("林檎", Some("リンゴ"))),
+            (" 林檎 ( りんご ) ", input("林檎", Some("りんご"))),
+            ("林檎 りんご", input("林檎", Some("りんご"))),
+            ("林檎　りんご", input("林檎", Some("りんご"))),
+            ("りんご", input("りんご", None)),
+            ("林檎", input("林檎", None)),
+            ("ice cream", input("ice cream", None)),
+            ("DVD(でぃーぶいでぃー)", input("DVD", Some("でぃーぶいでぃー"))),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(WordInput::parse(text), expected, "投稿の分割が誤っています: {}", text);
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_reading() {
+        assert_eq!(WordInput::parse("(りんご)"), input("(りんご)", None), "表記のない投稿がよみとして分割されました。");
+        assert_eq!(WordInput::parse("りんご()"), input("りんご()", None), "空のよみが受け付けられました。");
+        assert_eq!(WordInput::parse("林檎(林檎)"), input("林檎(林檎)", None), "漢字のよみが受け付けられました。");
+        assert_eq!(WordInput::parse("赤い 林檎"), input("赤い 林檎", None), "漢字を含むよみが空白で分割されました。");
+    }
+
+    #[test]
+    fn test_contains_kanji() {
+        assert!(contains_kanji("林檎"));
+        assert!(contains_kanji("赤いりんご"));
+        assert!(!contains_kanji("リンゴ"));
+        assert!(!contains_kanji("apple"));
+    }
+}
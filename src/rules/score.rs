@@ -0,0 +1,233 @@
+// src/rules/score.rs
+
+/// ---
+/// 成績を集計する期間
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeWindow {
+    Day,
+    Week,
+    Month,
+    /// すべての期間
+    #[default]
+    All,
+}
+
+impl TimeWindow {
+    pub const ALL: [TimeWindow; 4] = [TimeWindow::Day, TimeWindow::Week, TimeWindow::Month, TimeWindow::All];
+
+    /// コマンドの引数用の文字列を返します
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeWindow::Day => "day",
+            TimeWindow::Week => "week",
+            TimeWindow::Month => "month",
+            TimeWindow::All => "all",
+        }
+    }
+
+    /// コマンドの引数の文字列から変換します
+    pub fn parse(s: &str) -> Option<Self> {
+        TimeWindow::ALL.into_iter().find(|window| window.as_str() == s)
+    }
+
+    /// 表示用の名前を返します
+    pub fn label(&self) -> &'static str {
+        match self {
+            TimeWindow::Day => "24時間",
+            TimeWindow::Week => "7日間",
+            TimeWindow::Month => "30日間",
+            TimeWindow::All => "すべての期間",
+        }
+    }
+
+    /// ---
+    /// 集計に含める日数
+    /// Noneは制限なしを表します。
+    /// ---
+    pub fn days(&self) -> Option<u32> {
+        match self {
+            TimeWindow::Day => Some(1),
+            TimeWindow::Week => Some(7),
+            TimeWindow::Month => Some(30),
+            TimeWindow::All => None,
+        }
+    }
+}
+
+/// ---
+/// ランキングの並び順に使う成績
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LeaderboardMetric {
+    /// 承認された単語の数
+    #[default]
+    Words,
+    /// 勝ったゲームの数
+    Wins,
+    /// 最長の単語のよみの文字数
+    Longest,
+    /// 最速の回答の秒数（少ないほど上位）
+    Fastest,
+    /// 最長の連続承認数
+    Streak,
+}
+
+impl LeaderboardMetric {
+    pub const ALL: [LeaderboardMetric; 5] = [
+        LeaderboardMetric::Words,
+        LeaderboardMetric::Wins,
+        LeaderboardMetric::Longest,
+        LeaderboardMetric::Fastest,
+        LeaderboardMetric::Streak,
+    ];
+
+    /// コマンドの引数用の文字列を返します
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LeaderboardMetric::Words => "words",
+            LeaderboardMetric::Wins => "wins",
+            LeaderboardMetric::Longest => "longest",
+            LeaderboardMetric::Fastest => "fastest",
+            LeaderboardMetric::Streak => "streak",
+        }
+    }
+
+    /// コマンドの引数の文字列から変換します
+    pub fn parse(s: &str) -> Option<Self> {
+        LeaderboardMetric::ALL.into_iter().find(|metric| metric.as_str() == s)
+    }
+
+    /// 表示用の名前を返します
+    pub fn label(&self) -> &'static str {
+        match self {
+            LeaderboardMetric::Words => "承認された単語数",
+            LeaderboardMetric::Wins => "勝利数",
+            LeaderboardMetric::Longest => "最長の単語",
+            LeaderboardMetric::Fastest => "最速の回答",
+            LeaderboardMetric::Streak => "最長連続承認",
+        }
+    }
+
+    /// ---
+    /// 成績からランキングに使う値を取り出します。
+    /// 値がない（一度も記録されていない）場合はNoneを返し、ランキングに載せません。
+    /// ---
+    pub fn value(&self, stats: &PlayerStats) -> Option<u64> {
+        let value = match self {
+            LeaderboardMetric::Words => stats.accepted,
+            LeaderboardMetric::Wins => stats.won,
+            LeaderboardMetric::Longest => stats.longest_length,
+            LeaderboardMetric::Fastest => return stats.fastest_secs,
+            LeaderboardMetric::Streak => stats.best_streak,
+        };
+        (value > 0).then_some(value)
+    }
+
+    /// 値が小さいほど上位になるかどうか
+    pub fn ascending(&self) -> bool {
+        *self == LeaderboardMetric::Fastest
+    }
+
+    /// ---
+    /// 成績をランキングの順に並べ替え、値のないユーザーを除きます。
+    /// 同じ値の場合はユーザーIDの順に並べます。
+    /// ---
+    pub fn rank(&self, stats: Vec<PlayerStats>) -> Vec<(u64, PlayerStats)> {
+        let mut ranked: Vec<(u64, PlayerStats)> = stats
+            .into_iter()
+            .filter_map(|stats| self.value(&stats).map(|value| (value, stats)))
+            .collect();
+        ranked.sort_by(|(a, a_stats), (b, b_stats)| {
+            let order = if self.ascending() { a.cmp(b) } else { b.cmp(a) };
+            order.then(a_stats.user_id.cmp(&b_stats.user_id))
+        });
+        ranked
+    }
+}
+
+/// ---
+/// 1人のユーザーの成績
+/// ---
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PlayerStats {
+    pub user_id: u64,
+    /// 承認された単語の数
+    pub accepted: u64,
+    /// 否決された単語の数
+    pub rejected: u64,
+    pub won: u64,
+    pub lost: u64,
+    /// 承認された単語のうち、よみが最も長いもの
+    pub longest_word: Option<String>,
+    /// longest_wordのよみの文字数
+    pub longest_length: u64,
+    /// 手番が回ってから、承認された単語を出すまでの最短の秒数
+    pub fastest_secs: Option<u64>,
+    /// 否決されずに続けて承認された単語の最大数
+    pub best_streak: u64,
+}
+
+/// ---
+/// 単語の投票結果（古い順）から、否決されずに続けて承認された最大数を求めます。
+/// trueが承認、falseが否決を表します。
+/// ---
+pub fn best_streak(results: impl IntoIterator<Item = bool>) -> u64 {
+    let mut best = 0;
+    let mut current = 0;
+    for accepted in results {
+        current = if accepted { current + 1 } else { 0 };
+        best = best.max(current);
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        for window in TimeWindow::ALL {
+            assert_eq!(TimeWindow::parse(window.as_str()), Some(window));
+        }
+        for metric in LeaderboardMetric::ALL {
+            assert_eq!(LeaderboardMetric::parse(metric.as_str()), Some(metric));
+        }
+        assert_eq!(TimeWindow::parse("year"), None, "不明な期間が受け付けられました。");
+        assert_eq!(LeaderboardMetric::parse("score"), None, "不明な成績が受け付けられました。");
+    }
+
+    #[test]
+    fn test_best_streak() {
+        assert_eq!(best_streak([]), 0);
+        assert_eq!(best_streak([false, false]), 0);
+        assert_eq!(best_streak([true, true, false, true]), 2);
+        assert_eq!(best_streak([true, false, true, true, true]), 3, "否決後の連続承認が数えられていません。");
+    }
+
+    #[test]
+    fn test_rank() {
+        let stats = |user_id, accepted, fastest_secs| PlayerStats {
+            user_id,
+            accepted,
+            fastest_secs,
+            ..Default::default()
+        };
+        let all = vec![stats(1, 3, Some(10)), stats(2, 5, None), stats(3, 3, Some(4)), stats(4, 0, None)];
+
+        let words: Vec<(u64, u64)> = LeaderboardMetric::Words
+            .rank(all.clone())
+            .into_iter()
+            .map(|(value, stats)| (stats.user_id, value))
+            .collect();
+        assert_eq!(words, vec![(2, 5), (1, 3), (3, 3)], "承認数の順に並んでいません。");
+
+        let fastest: Vec<u64> = LeaderboardMetric::Fastest
+            .rank(all)
+            .into_iter()
+            .map(|(_, stats)| stats.user_id)
+            .collect();
+        assert_eq!(fastest, vec![3, 1], "最速の回答は短い順に並ぶ必要があります。");
+    }
+}
@@ -0,0 +1,210 @@
+// src/rules/script.rs
+
+/// 長音記号（ひらがな・カタカナのどちらでも使うため、文字の種類を判定しない）
+const PROLONGED_SOUND_MARK: char = 'ー';
+
+/// ---
+/// 単語に使われる文字の種類
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Script {
+    Hiragana,
+    Katakana,
+    Kanji,
+    /// 英字（全角を含む）
+    Latin,
+    /// 数字（全角を含む）
+    Digit,
+}
+
+impl Script {
+    pub const ALL: [Script; 5] = [
+        Script::Hiragana,
+        Script::Katakana,
+        Script::Kanji,
+        Script::Latin,
+        Script::Digit,
+    ];
+
+    /// データベース保存用の文字列を返します
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Script::Hiragana => "hiragana",
+            Script::Katakana => "katakana",
+            Script::Kanji => "kanji",
+            Script::Latin => "latin",
+            Script::Digit => "digit",
+        }
+    }
+
+    /// データベースの文字列から変換します
+    pub fn parse(s: &str) -> Option<Self> {
+        Script::ALL.into_iter().find(|script| script.as_str() == s)
+    }
+
+    /// 表示用の名前を返します
+    pub fn label(&self) -> &'static str {
+        match self {
+            Script::Hiragana => "ひらがな",
+            Script::Katakana => "カタカナ",
+            Script::Kanji => "漢字",
+            Script::Latin => "英字",
+            Script::Digit => "数字",
+        }
+    }
+
+    /// ---
+    /// 文字の種類を判定します。
+    /// どの種類にも当てはまらない文字（他の言語の文字など）はNoneを返します。
+    /// ---
+    pub fn of(c: char) -> Option<Self> {
+        match c {
+            'ぁ'..='ゟ' => Some(Script::Hiragana),
+            'ァ'..='ヿ' | 'ㇰ'..='ㇿ' | 'ｦ'..='ﾝ' => Some(Script::Katakana),
+            '\u{4E00}'..='\u{9FFF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{20000}'..='\u{2FFFF}'
+            | '々' => Some(Script::Kanji),
+            '0'..='9' | '０'..='９' => Some(Script::Digit),
+            'A'..='Z' | 'a'..='z' | 'Ａ'..='Ｚ' | 'ａ'..='ｚ' => Some(Script::Latin),
+            // アクセント付きのラテン文字
+            c if c.is_alphabetic() && ('\u{00C0}'..='\u{024F}').contains(&c) => Some(Script::Latin),
+            _ => None,
+        }
+    }
+
+    fn bit(&self) -> u8 {
+        1 << (*self as u8)
+    }
+}
+
+/// ---
+/// 単語に使ってよい文字の種類の組
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptSet {
+    bits: u8,
+}
+
+impl Default for ScriptSet {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl ScriptSet {
+    /// すべての種類を許可します
+    pub fn all() -> Self {
+        Self::from_scripts(Script::ALL)
+    }
+
+    pub fn from_scripts(scripts: impl IntoIterator<Item = Script>) -> Self {
+        Self {
+            bits: scripts.into_iter().fold(0, |bits, script| bits | script.bit()),
+        }
+    }
+
+    pub fn contains(&self, script: Script) -> bool {
+        self.bits & script.bit() != 0
+    }
+
+    pub fn is_all(&self) -> bool {
+        *self == Self::all()
+    }
+
+    pub fn scripts(&self) -> impl Iterator<Item = Script> + '_ {
+        Script::ALL.into_iter().filter(|script| self.contains(*script))
+    }
+
+    /// ---
+    /// wordの文字がすべて許可された種類かどうかを判定します。
+    /// 記号・空白・長音記号は判定しません。
+    /// ---
+    pub fn allows(&self, word: &str) -> bool {
+        if self.is_all() {
+            return true;
+        }
+        word.chars()
+            .filter(|c| c.is_alphanumeric() && *c != PROLONGED_SOUND_MARK)
+            .all(|c| Script::of(c).is_some_and(|script| self.contains(script)))
+    }
+
+    /// データベース保存用の文字列（カンマ区切り）を返します
+    pub fn to_db(self) -> String {
+        self.scripts().map(|script| script.as_str()).collect::<Vec<_>>().join(",")
+    }
+
+    /// ---
+    /// カンマ区切りの文字列から変換します。
+    /// 不明な種類が含まれる場合や、1つも指定されていない場合はNoneを返します。
+    /// ---
+    pub fn parse(s: &str) -> Option<Self> {
+        let scripts = s
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(Script::parse)
+            .collect::<Option<Vec<_>>>()?;
+        if scripts.is_empty() {
+            return None;
+        }
+        Some(Self::from_scripts(scripts))
+    }
+
+    /// 表示用の文字列を返します
+    pub fn label(&self) -> String {
+        if self.is_all() {
+            return "制限なし".to_string();
+        }
+        self.scripts().map(|script| script.label()).collect::<Vec<_>>().join("・")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_of() {
+        let cases = [
+            ('あ', Some(Script::Hiragana)),
+            ('ゞ', Some(Script::Hiragana)),
+            ('ア', Some(Script::Katakana)),
+            ('ｱ', Some(Script::Katakana)),
+            ('漢', Some(Script::Kanji)),
+            ('々', Some(Script::Kanji)),
+            ('a', Some(Script::Latin)),
+            ('Ｚ', Some(Script::Latin)),
+            ('é', Some(Script::Latin)),
+            ('7', Some(Script::Digit)),
+            ('７', Some(Script::Digit)),
+            ('한', None),
+            ('!', None),
+        ];
+        for (c, expected) in cases {
+            assert_eq!(Script::of(c), expected, "文字の種類の判定が誤っています: {}", c);
+        }
+    }
+
+    #[test]
+    fn test_allows() {
+        let kana = ScriptSet::from_scripts([Script::Hiragana, Script::Katakana]);
+        assert!(kana.allows("らーめん"), "長音記号を含むひらがなが拒否されました。");
+        assert!(kana.allows("ラーメン・です"), "記号を含むかなが拒否されました。");
+        assert!(!kana.allows("拉麺"), "漢字が許可されました。");
+        assert!(!kana.allows("ramen"), "英字が許可されました。");
+        assert!(!kana.allows("한글"), "不明な文字が許可されました。");
+        assert!(ScriptSet::all().allows("한글"), "すべて許可する場合に拒否されました。");
+    }
+
+    #[test]
+    fn test_parse() {
+        let set = ScriptSet::from_scripts([Script::Hiragana, Script::Kanji]);
+        assert_eq!(set.to_db(), "hiragana,kanji");
+        assert_eq!(ScriptSet::parse(&set.to_db()), Some(set));
+        assert_eq!(ScriptSet::parse(" kanji , hiragana "), Some(set), "空白を含む指定が読み込めません。");
+        assert_eq!(ScriptSet::parse("hiragana,cyrillic"), None, "不明な種類が受け付けられました。");
+        assert_eq!(ScriptSet::parse(""), None, "空の指定が受け付けられました。");
+    }
+}
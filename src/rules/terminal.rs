@@ -0,0 +1,70 @@
+// src/rules/terminal.rs
+use crate::rules::chain::{tail_unit, ChainRules};
+
+/// ---
+/// 「ん」で終わる単語の扱い
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NEndingRule {
+    /// 「ん」で終わる単語を出したプレイヤーの負け
+    #[default]
+    Lose,
+    /// 「ん」から始まる単語で続けることができる（ンジャメナ など）
+    Continue,
+}
+
+impl NEndingRule {
+    /// データベース保存用の文字列を返します
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NEndingRule::Lose => "lose",
+            NEndingRule::Continue => "continue",
+        }
+    }
+
+    /// データベースの文字列から変換します
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "lose" => Some(NEndingRule::Lose),
+            "continue" => Some(NEndingRule::Continue),
+            _ => None,
+        }
+    }
+
+    /// ---
+    /// wordを出した時点でゲームが終了するかどうかを判定します。
+    /// ---
+    pub fn is_terminal(&self, word: &str, rules: &ChainRules) -> bool {
+        match self {
+            NEndingRule::Lose => tail_unit(word, rules).is_some_and(|tail| tail == "ん"),
+            NEndingRule::Continue => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_n_ending_lose() {
+        let r = ChainRules::default();
+        assert!(NEndingRule::Lose.is_terminal("きりん", &r), "「ん」で終わる単語で終了しませんでした。");
+        assert!(NEndingRule::Lose.is_terminal("ラーメン", &r), "「ン」で終わる単語で終了しませんでした。");
+        assert!(!NEndingRule::Lose.is_terminal("りんご", &r), "「ん」で終わらない単語で終了しました。");
+    }
+
+    #[test]
+    fn test_n_ending_continue() {
+        let r = ChainRules::default();
+        assert!(!NEndingRule::Continue.is_terminal("きりん", &r), "「ん」を許可するルールで終了しました。");
+    }
+
+    #[test]
+    fn test_n_ending_parse() {
+        for rule in [NEndingRule::Lose, NEndingRule::Continue] {
+            assert_eq!(NEndingRule::parse(rule.as_str()), Some(rule));
+        }
+        assert_eq!(NEndingRule::parse("invalid"), None);
+    }
+}
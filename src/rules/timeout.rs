@@ -0,0 +1,58 @@
+// src/rules/timeout.rs
+
+/// ---
+/// 手番の制限時間を過ぎたプレイヤーの扱い
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TurnTimeoutAction {
+    /// 手番を次のプレイヤーへ飛ばす
+    #[default]
+    Skip,
+    /// ゲームから脱落させる
+    Eliminate,
+}
+
+impl TurnTimeoutAction {
+    /// データベース保存用の文字列を返します
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TurnTimeoutAction::Skip => "skip",
+            TurnTimeoutAction::Eliminate => "eliminate",
+        }
+    }
+
+    /// データベースの文字列から変換します
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "skip" => Some(TurnTimeoutAction::Skip),
+            "eliminate" => Some(TurnTimeoutAction::Eliminate),
+            _ => None,
+        }
+    }
+}
+
+/// ---
+/// ルームの制限時間
+/// Noneの場合は制限しません。
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TimeLimits {
+    /// 投票を締め切るまでの秒数
+    pub vote_secs: Option<u32>,
+    /// 単語を投稿するまでの秒数
+    pub turn_secs: Option<u32>,
+    pub on_turn_timeout: TurnTimeoutAction,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_parse() {
+        for action in [TurnTimeoutAction::Skip, TurnTimeoutAction::Eliminate] {
+            assert_eq!(TurnTimeoutAction::parse(action.as_str()), Some(action));
+        }
+        assert_eq!(TurnTimeoutAction::parse("invalid"), None, "不明な扱いが受け付けられました。");
+    }
+}
@@ -0,0 +1,274 @@
+// src/rules/vote.rs
+
+/// ---
+/// 投票の確定方法
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VotePolicy {
+    /// 投票できる参加者の過半数が賛成すれば承認
+    #[default]
+    Majority,
+    /// 投票できる参加者のpercent%以上が賛成すれば承認
+    Supermajority { percent: u32 },
+    /// 投票できる参加者全員が賛成すれば承認（反対が1票でもあれば否決）
+    Unanimous,
+    /// seconds秒以内に反対がなければ承認（反対が1票でもあれば否決）
+    NoObjection { seconds: u32 },
+}
+
+impl VotePolicy {
+    /// データベース保存用の種類名を返します
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VotePolicy::Majority => "majority",
+            VotePolicy::Supermajority { .. } => "supermajority",
+            VotePolicy::Unanimous => "unanimous",
+            VotePolicy::NoObjection { .. } => "no_objection",
+        }
+    }
+
+    /// 種類ごとのパラメータ（割合または秒数）を返します
+    pub fn threshold(&self) -> Option<u32> {
+        match self {
+            VotePolicy::Supermajority { percent } => Some(*percent),
+            VotePolicy::NoObjection { seconds } => Some(*seconds),
+            VotePolicy::Majority | VotePolicy::Unanimous => None,
+        }
+    }
+
+    /// ---
+    /// 種類名とパラメータから変換します。
+    /// パラメータが必要な種類で値がない、または範囲外の場合はNoneを返します。
+    /// ---
+    pub fn parse(s: &str, threshold: Option<u32>) -> Option<Self> {
+        match s {
+            "majority" => Some(VotePolicy::Majority),
+            "supermajority" => threshold
+                .filter(|percent| (1..=100).contains(percent))
+                .map(|percent| VotePolicy::Supermajority { percent }),
+            "unanimous" => Some(VotePolicy::Unanimous),
+            "no_objection" => threshold
+                .filter(|&seconds| seconds > 0)
+                .map(|seconds| VotePolicy::NoObjection { seconds }),
+            _ => None,
+        }
+    }
+}
+
+/// ---
+/// ルームの投票確定ルール
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct VoteRule {
+    pub policy: VotePolicy,
+    /// 確定に必要な最低投票数（投票できる参加者数を上限とする）
+    pub quorum: u32,
+}
+
+/// ---
+/// 判定時点の投票状況
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ballot {
+    pub good: u32,
+    pub bad: u32,
+    /// 投票できる参加者数（単語を出したプレイヤーを除く）
+    pub eligible: u32,
+    /// 投票開始からの経過秒数
+    pub elapsed_secs: i64,
+}
+
+/// ---
+/// 投票の判定結果
+/// ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// まだ確定しない
+    Pending,
+    Pass,
+    Fail,
+}
+
+impl VoteRule {
+    /// ---
+    /// 現在の投票状況から、結果が確定したかどうかを判定します。
+    /// 残りの票がどう入っても結果が変わらない時点で確定します。
+    /// ---
+    pub fn evaluate(&self, ballot: &Ballot) -> Verdict {
+        let good = ballot.good as u64;
+        let bad = ballot.bad as u64;
+        let eligible = ballot.eligible as u64;
+
+        // 投票できる参加者がいなければそのまま承認する
+        if eligible == 0 {
+            return Verdict::Pass;
+        }
+        let quorum_met = good + bad >= (self.quorum as u64).min(eligible);
+        let remaining = eligible.saturating_sub(good + bad);
+
+        match self.policy {
+            VotePolicy::Majority => {
+                if good * 2 > eligible && quorum_met {
+                    Verdict::Pass
+                } else if bad * 2 >= eligible {
+                    Verdict::Fail
+                } else {
+                    Verdict::Pending
+                }
+            }
+            VotePolicy::Supermajority { percent } => {
+                let required = percent as u64 * eligible;
+                if good * 100 >= required && quorum_met {
+                    Verdict::Pass
+                } else if (good + remaining) * 100 < required {
+                    Verdict::Fail
+                } else {
+                    Verdict::Pending
+                }
+            }
+            VotePolicy::Unanimous => {
+                if bad > 0 {
+                    Verdict::Fail
+                } else if good == eligible {
+                    Verdict::Pass
+                } else {
+                    Verdict::Pending
+                }
+            }
+            VotePolicy::NoObjection { seconds } => {
+                if bad > 0 {
+                    Verdict::Fail
+                } else if good == eligible || (ballot.elapsed_secs >= seconds as i64 && quorum_met) {
+                    Verdict::Pass
+                } else {
+                    Verdict::Pending
+                }
+            }
+        }
+    }
+
+    /// ---
+    /// 投票の期限を過ぎた時点で、投票済みの票から結果を確定させます。
+    /// 最低投票数に届いていない場合は否決します。
+    /// ---
+    pub fn evaluate_at_deadline(&self, ballot: &Ballot) -> Verdict {
+        let verdict = self.evaluate(ballot);
+        if verdict != Verdict::Pending {
+            return verdict;
+        }
+
+        let good = ballot.good as u64;
+        let bad = ballot.bad as u64;
+        let cast = good + bad;
+        if cast < (self.quorum as u64).min(ballot.eligible as u64) {
+            return Verdict::Fail;
+        }
+
+        let pass = match self.policy {
+            VotePolicy::Majority => good > bad,
+            VotePolicy::Supermajority { percent } => cast > 0 && good * 100 >= percent as u64 * cast,
+            VotePolicy::Unanimous => good > 0 && bad == 0,
+            VotePolicy::NoObjection { .. } => bad == 0,
+        };
+        if pass { Verdict::Pass } else { Verdict::Fail }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ballot(good: u32, bad: u32, eligible: u32) -> Ballot {
+        Ballot {
+            good,
+            bad,
+            eligible,
+            elapsed_secs: 0,
+        }
+    }
+
+    fn rule(policy: VotePolicy, quorum: u32) -> VoteRule {
+        VoteRule { policy, quorum }
+    }
+
+    #[test]
+    fn test_majority() {
+        let r = rule(VotePolicy::Majority, 0);
+        assert_eq!(r.evaluate(&ballot(2, 0, 3)), Verdict::Pass, "過半数の賛成で承認されませんでした。");
+        assert_eq!(r.evaluate(&ballot(1, 0, 3)), Verdict::Pending, "過半数に届かない時点で確定しました。");
+        assert_eq!(r.evaluate(&ballot(1, 1, 2)), Verdict::Fail, "同数で否決されませんでした。");
+        assert_eq!(r.evaluate(&ballot(0, 0, 0)), Verdict::Pass, "投票者がいない場合に承認されませんでした。");
+    }
+
+    #[test]
+    fn test_supermajority() {
+        let r = rule(VotePolicy::Supermajority { percent: 75 }, 0);
+        assert_eq!(r.evaluate(&ballot(3, 0, 4)), Verdict::Pass, "75%の賛成で承認されませんでした。");
+        assert_eq!(r.evaluate(&ballot(2, 0, 4)), Verdict::Pending, "75%に届かない時点で確定しました。");
+        assert_eq!(r.evaluate(&ballot(2, 2, 4)), Verdict::Fail, "75%に届かなくなった時点で否決されませんでした。");
+    }
+
+    #[test]
+    fn test_unanimous() {
+        let r = rule(VotePolicy::Unanimous, 0);
+        assert_eq!(r.evaluate(&ballot(3, 0, 3)), Verdict::Pass, "全員の賛成で承認されませんでした。");
+        assert_eq!(r.evaluate(&ballot(2, 0, 3)), Verdict::Pending, "全員が投票する前に確定しました。");
+        assert_eq!(r.evaluate(&ballot(0, 1, 3)), Verdict::Fail, "反対票で否決されませんでした。");
+    }
+
+    #[test]
+    fn test_no_objection() {
+        let r = rule(VotePolicy::NoObjection { seconds: 60 }, 0);
+        let waiting = Ballot { elapsed_secs: 30, ..ballot(0, 0, 3) };
+        assert_eq!(r.evaluate(&waiting), Verdict::Pending, "期限前に確定しました。");
+        let expired = Ballot { elapsed_secs: 60, ..ballot(0, 0, 3) };
+        assert_eq!(r.evaluate(&expired), Verdict::Pass, "期限までに反対がないのに承認されませんでした。");
+        assert_eq!(r.evaluate(&ballot(0, 1, 3)), Verdict::Fail, "反対票で否決されませんでした。");
+    }
+
+    #[test]
+    fn test_quorum() {
+        let r = rule(VotePolicy::Majority, 3);
+        assert_eq!(r.evaluate(&ballot(2, 0, 3)), Verdict::Pending, "最低投票数に届かない時点で承認されました。");
+        assert_eq!(r.evaluate(&ballot(2, 1, 3)), Verdict::Pass, "最低投票数に達しても承認されませんでした。");
+        // 最低投票数が参加者数より多い場合は参加者数まで
+        let r = rule(VotePolicy::Majority, 10);
+        assert_eq!(r.evaluate(&ballot(2, 0, 2)), Verdict::Pass, "最低投票数が参加者数で制限されていません。");
+
+        let r = rule(VotePolicy::NoObjection { seconds: 10 }, 1);
+        let expired = Ballot { elapsed_secs: 10, ..ballot(0, 0, 3) };
+        assert_eq!(r.evaluate(&expired), Verdict::Pending, "最低投票数に届かないのに承認されました。");
+    }
+
+    #[test]
+    fn test_evaluate_at_deadline() {
+        let r = rule(VotePolicy::Majority, 0);
+        assert_eq!(r.evaluate_at_deadline(&ballot(1, 0, 4)), Verdict::Pass, "期限時点で賛成多数なのに承認されませんでした。");
+        assert_eq!(r.evaluate_at_deadline(&ballot(0, 0, 4)), Verdict::Fail, "期限時点で票がないのに承認されました。");
+
+        let r = rule(VotePolicy::Supermajority { percent: 75 }, 0);
+        assert_eq!(r.evaluate_at_deadline(&ballot(2, 1, 5)), Verdict::Fail, "投票済みの票の75%に届かないのに承認されました。");
+        assert_eq!(r.evaluate_at_deadline(&ballot(3, 1, 5)), Verdict::Pass, "投票済みの票の75%の賛成で承認されませんでした。");
+
+        let r = rule(VotePolicy::Unanimous, 0);
+        assert_eq!(r.evaluate_at_deadline(&ballot(2, 0, 4)), Verdict::Pass, "期限時点で反対がないのに承認されませんでした。");
+
+        let r = rule(VotePolicy::Majority, 3);
+        assert_eq!(r.evaluate_at_deadline(&ballot(2, 0, 4)), Verdict::Fail, "最低投票数に届かないのに承認されました。");
+    }
+
+    #[test]
+    fn test_policy_parse() {
+        for policy in [
+            VotePolicy::Majority,
+            VotePolicy::Supermajority { percent: 60 },
+            VotePolicy::Unanimous,
+            VotePolicy::NoObjection { seconds: 30 },
+        ] {
+            assert_eq!(VotePolicy::parse(policy.as_str(), policy.threshold()), Some(policy));
+        }
+        assert_eq!(VotePolicy::parse("supermajority", None), None, "割合のない特別多数が受け付けられました。");
+        assert_eq!(VotePolicy::parse("supermajority", Some(101)), None, "100%を超える割合が受け付けられました。");
+        assert_eq!(VotePolicy::parse("no_objection", Some(0)), None, "0秒の期限が受け付けられました。");
+    }
+}
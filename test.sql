@@ -1,8 +1,6 @@
-insert into rooms values(1);
-insert into users values(10);
-insert into users values(11);
+insert into rooms (id) values(1);
 
-insert into room_members values(1, 10, null, null);
-insert into room_members values(1, 11, null, null);
+insert into room_members (room_id, user_id) values(1, 10);
+insert into room_members (room_id, user_id) values(1, 11);
 update room_members set next = 11, prev = 11 where user_id = 10;
-update room_members set next = 10, prev = 10 where user_id = 11;
\ No newline at end of file
+update room_members set next = 10, prev = 10 where user_id = 11;